@@ -0,0 +1,33 @@
+//! Formatting of the large numeric sequences.
+//!
+//! Compare the results with and without the `fast-fmt` feature:
+//! `cargo bench --bench numbers` and `cargo bench --bench numbers --features fast-fmt`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const LEN: usize = 1_000_000;
+
+fn floats(c: &mut Criterion) {
+    let values: Vec<f64> = (0..LEN).map(|i| i as f64 * 1.618_033_988_7).collect();
+    let mut group = c.benchmark_group("numbers");
+    group.throughput(Throughput::Elements(LEN as u64));
+    group.bench_function("Vec<f64>", |b| {
+        b.iter(|| uneval::write(black_box(&values), std::io::sink()).unwrap())
+    });
+    group.finish();
+}
+
+fn integers(c: &mut Criterion) {
+    let values: Vec<u64> = (0..LEN as u64)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+    let mut group = c.benchmark_group("numbers");
+    group.throughput(Throughput::Elements(LEN as u64));
+    group.bench_function("Vec<u64>", |b| {
+        b.iter(|| uneval::write(black_box(&values), std::io::sink()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, floats, integers);
+criterion_main!(benches);
@@ -0,0 +1 @@
+//! Tests compiling and running the code generated by `uneval`; everything is in the `tests` directory.
@@ -0,0 +1,144 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+use uneval::config::ChunkSize;
+
+#[derive(Serialize)]
+struct Record {
+    id: u32,
+    name: String,
+    pair: (u8, bool),
+}
+
+const USER: &str = r#"
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    id: u32,
+    name: String,
+    pair: (u8, bool),
+}
+
+fn main() {
+    let records: Vec<Record> = include!("records.rs");
+    let expected: Vec<Record> = (0..10_000)
+        .map(|id| Record { id, name: format!("record {}", id), pair: ((id % 256) as u8, id % 2 == 0) })
+        .collect();
+    assert!(records == expected, "records differ");
+    let index: std::collections::BTreeMap<String, Vec<u32>> = include!("index.rs");
+    assert_eq!(index.len(), 100);
+    assert_eq!(index["key 42"], [42, 43]);
+}
+"#;
+
+#[test]
+fn split_and_join() {
+    let dir = "test_fixtures/chunked";
+    create_dir_all(dir).unwrap();
+
+    let records: Vec<_> = (0..10_000)
+        .map(|id| Record {
+            id,
+            name: format!("record {}", id),
+            pair: ((id % 256) as u8, id % 2 == 0),
+        })
+        .collect();
+    let chunks =
+        uneval::to_files_chunked(&records, dir, "records", ChunkSize::Elements(2500)).unwrap();
+    assert_eq!(chunks, 4);
+    let main = std::fs::read_to_string(format!("{}/records.rs", dir)).unwrap();
+    assert_eq!(
+        main,
+        r#"vec![include!("records_0.rs"),include!("records_1.rs"),include!("records_2.rs"),include!("records_3.rs")].into_iter().flatten().collect()"#
+    );
+
+    let index: BTreeMap<_, _> = (0..100u32)
+        .map(|i| (format!("key {}", i), vec![i, i + 1]))
+        .collect();
+    let chunks = uneval::to_files_chunked(&index, dir, "index", ChunkSize::Bytes(1024)).unwrap();
+    assert!(chunks > 1);
+    for chunk in 0..chunks - 1 {
+        let len = std::fs::metadata(format!("{}/index_{}.rs", dir, chunk))
+            .unwrap()
+            .len();
+        assert!(
+            len <= 1024 + "vec![]".len() as u64,
+            "chunk {} is too large",
+            chunk
+        );
+    }
+
+    File::create(format!("{}/chunked-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/chunked-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/chunked-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn empty_and_unsupported() {
+    let dir: std::path::PathBuf = [env!("CARGO_TARGET_TMPDIR"), "chunked"].iter().collect();
+    create_dir_all(&dir).unwrap();
+    let empty: Vec<u8> = Vec::new();
+    assert_eq!(
+        uneval::to_files_chunked(&empty, &dir, "empty", ChunkSize::Elements(10)).unwrap(),
+        1
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("empty_0.rs")).unwrap(),
+        "vec![]"
+    );
+
+    let err =
+        uneval::to_files_chunked((1u8, 2u8), &dir, "tuple", ChunkSize::Elements(10)).unwrap_err();
+    assert!(
+        err.to_string().contains("only sequences and maps"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn hashed_and_stale() {
+    use std::collections::{HashMap, HashSet};
+
+    let dir: std::path::PathBuf = [env!("CARGO_TARGET_TMPDIR"), "chunked_hashed"]
+        .iter()
+        .collect();
+    let _ = std::fs::remove_dir_all(&dir);
+    create_dir_all(&dir).unwrap();
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+
+    let map: HashMap<u32, u32> = (0..100).map(|i| (i, i * 2)).collect();
+    let chunks = uneval::to_files_chunked(&map, &dir, "map", ChunkSize::Elements(10)).unwrap();
+    assert_eq!(chunks, 10);
+    let first = read("map_0.rs");
+    assert!(
+        first.starts_with("vec![(0u32,0u32),(10u32,20u32),"),
+        "{}",
+        first
+    );
+    // Another map with the same contents has different iteration order, but yields the same files.
+    let map: HashMap<u32, u32> = map.into_iter().collect();
+    uneval::to_files_chunked(&map, &dir, "map", ChunkSize::Elements(10)).unwrap();
+    assert_eq!(read("map_0.rs"), first);
+
+    let set: HashSet<u32> = (0..20).collect();
+    uneval::to_files_chunked_sorted(&set, &dir, "set", ChunkSize::Elements(10)).unwrap();
+    assert!(read("set_0.rs").starts_with("vec![0u32,10u32,"));
+
+    // Chunks which are not needed anymore are removed.
+    let chunks = uneval::to_files_chunked(&map, &dir, "map", ChunkSize::Elements(50)).unwrap();
+    assert_eq!(chunks, 2);
+    assert!(dir.join("map_1.rs").exists());
+    assert!(!dir.join("map_2.rs").exists());
+    assert!(dir.join("set_1.rs").exists());
+}
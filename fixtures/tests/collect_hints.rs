@@ -0,0 +1,91 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    io::Write,
+    process::Command,
+};
+
+#[derive(Serialize)]
+struct Index<C> {
+    name: String,
+    entries: C,
+}
+
+/// Nothing but the hint pins the collection type of the included index.
+const USER: &str = r#"
+#[derive(Debug)]
+pub struct Index<C> {
+    name: String,
+    entries: C,
+}
+
+fn main() {
+    let index = include!("generated.rs");
+    assert_eq!(index.name, "words");
+    let entries: Vec<(String, u32)> = index.entries.into_iter().collect();
+    assert_eq!(entries, [("a".to_string(), 1), ("b".to_string(), 2)]);
+}
+"#;
+
+fn write_fixture(dir: &str, name: &str, code: &str) {
+    create_dir_all(dir).unwrap();
+    File::create(format!("{}/generated.rs", dir))
+        .unwrap()
+        .write_all(code.as_bytes())
+        .unwrap();
+    File::create(format!("{}/{}-user.rs", dir, name))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/{}-user.snapshot", dir, name))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+}
+
+fn index() -> Index<BTreeMap<String, u32>> {
+    Index {
+        name: "words".into(),
+        entries: [("a".to_string(), 1), ("b".to_string(), 2)]
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[test]
+fn hinted() {
+    let mut code = Vec::new();
+    index()
+        .serialize(
+            &mut uneval::ser::Uneval::new(&mut code)
+                .with_collect_hint_at("Index.entries", "std::collections::BTreeMap<String, u32>"),
+        )
+        .unwrap();
+    let dir = "test_fixtures/collect_hints";
+    write_fixture(dir, "collect_hints", &String::from_utf8(code).unwrap());
+
+    let b = Batch::new();
+    b.run_match(format!("{}/collect_hints-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn unhinted() {
+    let dir = "test_fixtures/collect_hints_unhinted";
+    write_fixture(
+        dir,
+        "collect_hints_unhinted",
+        &uneval::to_string(index()).unwrap(),
+    );
+    let output = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+        .args(["--edition", "2021", "--emit=metadata", "--out-dir"])
+        .arg(env!("CARGO_TARGET_TMPDIR"))
+        .arg(format!("{}/collect_hints_unhinted-user.rs", dir))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("type annotations needed"), "{}", stderr);
+}
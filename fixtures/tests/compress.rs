@@ -0,0 +1,142 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+use uneval::config::Codec;
+
+/// Names of the sidecars included into the generated code, with the given extension.
+fn included(code: &str, extension: &str) -> Vec<String> {
+    code.split('"')
+        .filter(|part| part.starts_with("generated.rs.") && part.ends_with(extension))
+        .map(String::from)
+        .collect()
+}
+
+#[derive(Serialize)]
+struct Dataset {
+    bytes: Vec<u8>,
+    text: String,
+    small: Vec<u8>,
+}
+
+/// Multi-megabyte blob, which is compressible, but not trivially.
+const DATA: &str = r#"
+fn data(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if i % 7 == 0 { (state % 16) as u8 } else { b'a' + (i % 26) as u8 }
+        })
+        .collect()
+}
+"#;
+
+fn data(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            if i % 7 == 0 {
+                (state % 16) as u8
+            } else {
+                b'a' + (i % 26) as u8
+            }
+        })
+        .collect()
+}
+
+const USER: &str = r#"
+pub struct Dataset {
+    bytes: Vec<u8>,
+    text: String,
+    small: Vec<u8>,
+}
+
+fn main() {
+    let dataset: Dataset = include!("generated.rs");
+    assert!(dataset.bytes == data(4 << 20), "bytes differ");
+    assert!(dataset.text == "abcdefghij".repeat(100_000), "text differs");
+    assert_eq!(dataset.small, [1, 2, 3]);
+}
+"#;
+
+#[test]
+fn round_trip() {
+    let dir = "test_fixtures/compress";
+    create_dir_all(dir).unwrap();
+
+    let dataset = Dataset {
+        bytes: data(4 << 20),
+        text: "abcdefghij".repeat(100_000),
+        small: vec![1, 2, 3],
+    };
+    let path = format!("{}/generated.rs", dir);
+    uneval::to_file_compressed(&dataset, &path, 1024, Codec::Deflate).unwrap();
+
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(code.contains("flate2::read::DeflateDecoder"));
+    assert!(code.contains("small: vec![1u8,2u8,3u8]"));
+    let compressed = std::fs::metadata(format!("{}/{}", dir, included(&code, ".bin.deflate")[0]))
+        .unwrap()
+        .len();
+    assert!(compressed < (4 << 20) / 2);
+    assert!(std::fs::metadata(format!("{}/{}", dir, included(&code, ".txt.deflate")[0])).is_ok());
+
+    File::create(format!("{}/compress-user.rs", dir))
+        .unwrap()
+        .write_all(format!("{}{}", DATA, USER).as_bytes())
+        .unwrap();
+    File::create(format!("{}/compress-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/compress-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn gzip_and_incompressible() {
+    let dir = "test_fixtures/compress_gzip";
+    create_dir_all(dir).unwrap();
+    let path = format!("{}/generated.rs", dir);
+    // Bytes which don't get smaller when compressed are written as is.
+    let value = (vec![7u8; 4096], (0..=255u8).collect::<Vec<_>>());
+    uneval::to_file_compressed(&value, &path, 200, Codec::Gzip).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(code.contains("flate2::read::GzDecoder"));
+    assert_eq!(included(&code, ".bin").len(), 1);
+    assert!(std::fs::metadata(format!("{}/{}", dir, included(&code, ".bin.gz")[0])).is_ok());
+}
+
+#[test]
+fn requires_std() {
+    use uneval::{config::Env, error::UnevalError, ser::Uneval};
+
+    let dir = "test_fixtures/compress_alloc";
+    create_dir_all(dir).unwrap();
+    let path = format!("{}/generated.rs", dir);
+    let value = (vec![7u8; 4096],);
+    let uneval = Uneval::new(Vec::new()).with_env(Env::Alloc);
+    let err = uneval::to_file_compressed_with(&value, &path, 200, Codec::Deflate, uneval);
+    assert!(
+        matches!(&err, Err(UnevalError::RequiresStd { path, .. }) if path == ".0"),
+        "{:?}",
+        err
+    );
+    // Borrowed blobs are not compressed, so they are fine.
+    let uneval = Uneval::new(Vec::new())
+        .with_env(Env::Alloc)
+        .with_str_mode(uneval::config::StrMode::Borrowed);
+    uneval::to_file_compressed_with("x".repeat(4096), &path, 200, Codec::Deflate, uneval).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(code.starts_with("include_str!("), "{}", code);
+}
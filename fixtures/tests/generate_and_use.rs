@@ -14,6 +14,8 @@ struct Data {
     support_types: Option<String>,
     definition: String,
     value: String,
+    options: Option<String>,
+    sidecar_threshold: Option<usize>,
 }
 
 impl Data {
@@ -24,21 +26,33 @@ impl Data {
             create_dir(&path).unwrap();
         }
         path.push("dummy"); // a hack, so that folder isn't overwritten with file name
+        let target = format!("test_fixtures/{}/generated.rs", name);
+        let generate = match (&self.options, self.sidecar_threshold) {
+            (_, Some(threshold)) => format!(
+                "uneval::to_file_with_sidecars({}, {:?}, {})",
+                self.value, target, threshold
+            ),
+            (Some(options), None) => format!(
+                "serde::Serialize::serialize(&{}, &mut uneval::ser::Uneval::new(std::fs::File::create({:?}).unwrap()){})",
+                self.value, target, options
+            ),
+            (None, None) => format!("uneval::to_file({}, {:?})", self.value, target),
+        };
         write!(
-            File::create(&path.with_file_name(format!("{}-main.rs", name))).unwrap(),
+            File::create(path.with_file_name(format!("{}-main.rs", name))).unwrap(),
             include_str!("main.tpl"),
             name = name,
-            value = self.value
+            generate = generate
         )
         .unwrap();
         write!(
-            File::create(&path.with_file_name("definition.rs")).unwrap(),
+            File::create(path.with_file_name("definition.rs")).unwrap(),
             include_str!("definition.tpl"),
             definition = self.definition
         )
         .unwrap();
         write!(
-            File::create(&path.with_file_name(format!("{}-user.rs", name))).unwrap(),
+            File::create(path.with_file_name(format!("{}-user.rs", name))).unwrap(),
             include_str!("user.tpl"),
             types = self
                 .support_types
@@ -52,13 +66,13 @@ impl Data {
         )
         .unwrap();
         write!(
-            File::create(&path.with_file_name(format!("{}-main.snapshot", name))).unwrap(),
+            File::create(path.with_file_name(format!("{}-main.snapshot", name))).unwrap(),
             include_str!("main.snapshot.tpl"),
             name = name
         )
         .unwrap();
         write!(
-            File::create(&path.with_file_name(format!("{}-user.snapshot", name))).unwrap(),
+            File::create(path.with_file_name(format!("{}-user.snapshot", name))).unwrap(),
             include_str!("user.snapshot.tpl"),
         )
         .unwrap();
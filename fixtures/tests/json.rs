@@ -0,0 +1,115 @@
+use batch_run::Batch;
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+use uneval::error::UnevalError;
+use uneval::json::to_json_expr;
+use uneval::ser::Uneval;
+
+#[test]
+fn numbers() {
+    let value = json!([
+        1,
+        -2,
+        3000000000u64,
+        -3000000000i64,
+        u64::MAX,
+        1.0,
+        -0.5,
+        1e300
+    ]);
+    let code = to_json_expr(&value).unwrap();
+    assert_eq!(
+        code,
+        "serde_json::json!([1,-2,3000000000u64,-3000000000i64,18446744073709551615u64,1.0,-0.5,1e300])"
+    );
+    // The same code, to check that it yields the same value.
+    let generated = serde_json::json!([
+        1,
+        -2,
+        3000000000u64,
+        -3000000000i64,
+        18446744073709551615u64,
+        1.0,
+        -0.5,
+        1e300
+    ]);
+    assert_eq!(generated, value);
+}
+
+#[test]
+fn rejects_structs() {
+    #[derive(Serialize)]
+    struct Point {
+        x: i32,
+    }
+    assert!(matches!(
+        to_json_expr(Point { x: 1 }),
+        Err(UnevalError::Custom { .. })
+    ));
+}
+
+#[derive(Serialize)]
+struct Plugin {
+    name: String,
+    config: serde_json::Value,
+}
+
+const USER: &str = r#"
+#[derive(Debug, PartialEq)]
+pub struct Plugin {
+    name: String,
+    config: serde_json::Value,
+}
+
+fn main() {
+    let plugin: Plugin = include!("generated.rs");
+    assert_eq!(plugin.name, "cache");
+    assert_eq!(
+        plugin.config,
+        serde_json::json!({
+            "enabled": true,
+            "limits": {"size": 1048576, "ratio": 0.75, "big": 10000000000u64},
+            "hosts": ["a", "b\"c"],
+            "fallback": null,
+        })
+    );
+}
+"#;
+
+#[test]
+fn value_field() {
+    let dir = "test_fixtures/json";
+    create_dir_all(dir).unwrap();
+
+    let plugin = Plugin {
+        name: "cache".into(),
+        config: json!({
+            "enabled": true,
+            "limits": {"size": 1048576, "ratio": 0.75, "big": 10000000000u64},
+            "hosts": ["a", "b\"c"],
+            "fallback": null,
+        }),
+    };
+    plugin
+        .serialize(
+            &mut Uneval::new(File::create(format!("{}/generated.rs", dir)).unwrap())
+                .with_json_at("Plugin.config"),
+        )
+        .unwrap();
+    File::create(format!("{}/json-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/json-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/json-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
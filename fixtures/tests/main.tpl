@@ -1,10 +1,9 @@
-use uneval::to_file;
 use batch_run::{{Batch, config::Config}};
 
 mod definition;
 
 fn main() {{
-    to_file({value}, "test_fixtures/{name}/generated.rs").unwrap();
+    {generate}.unwrap();
     let b = Batch::new();
     b.run_match("test_fixtures/{name}/{name}-user.rs");
     b.run_with_config(Config::from_env().unwrap().with_stderr_no_color()).unwrap().assert_all_ok();
@@ -0,0 +1,271 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::{create_dir_all, File},
+    io::Write,
+};
+
+#[derive(Serialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+const USER: &str = r#"
+#[derive(Debug, PartialEq)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+include!("generated.rs");
+
+fn main() {
+    assert_eq!(ORIGIN, Point { x: 0, y: 0 });
+    assert_eq!(NAMES[&1], "one");
+    assert_eq!(NAMES[&2], "two");
+    assert_eq!(*PAIRS, vec![(1, "a".to_string()), (2, "b".to_string())]);
+    assert_eq!(*SINGLE, (5,));
+    assert_eq!(VERSION, [1, 2]);
+}
+"#;
+
+#[test]
+fn module() {
+    let dir = "test_fixtures/module";
+    create_dir_all(dir).unwrap();
+
+    let mut module = uneval::Module::new();
+    module
+        .add_const("ORIGIN", "Point", Point { x: 0, y: 0 })
+        .unwrap();
+    module.add_const("VERSION", "[u8; 2]", (1u8, 2u8)).unwrap();
+    module
+        .add_static(
+            "NAMES",
+            "std::collections::HashMap<u8, String>",
+            [(1u8, "one"), (2, "two")]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap();
+    module
+        .add_static("PAIRS", "Vec<(u8, String)>", vec![(1u8, "a"), (2, "b")])
+        .unwrap();
+    module.add_static("SINGLE", "(u8,)", (5u8,)).unwrap();
+    let code = module.into_string().unwrap();
+    assert_eq!(code.matches("mod __uneval_tuple_2").count(), 1);
+    assert_eq!(code.matches("mod __uneval_tuple_1").count(), 1);
+
+    File::create(format!("{}/generated.rs", dir))
+        .unwrap()
+        .write_all(code.as_bytes())
+        .unwrap();
+    File::create(format!("{}/module-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/module-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/module-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+const HEADER_USER: &str = r#"
+#![deny(warnings)]
+
+include!("generated.rs");
+
+fn main() {
+    let mut pairs = PAIRS.clone();
+    pairs.push((3, 4));
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(LIMIT, 10);
+}
+"#;
+
+#[test]
+fn module_with_header() {
+    let dir = "test_fixtures/module_header";
+    create_dir_all(dir).unwrap();
+
+    let mut module = uneval::Module::new()
+        .with_header(uneval::config::Header::new().with_source("tests/module.rs"));
+    module.add_const("LIMIT", "u32", 10u32).unwrap();
+    module
+        .add_static("PAIRS", "Vec<(u8, u8)>", vec![(1u8, 2u8)])
+        .unwrap();
+    let code = module.into_string().unwrap();
+    assert!(code.starts_with(&format!(
+        "// @generated by uneval {} -- do not edit\n// source: tests/module.rs\n#[allow(clippy::all, unused_mut)]\n#[rustfmt::skip]\nmod __uneval_tuple_2",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert_eq!(code.matches("#[rustfmt::skip]").count(), 3);
+
+    File::create(format!("{}/generated.rs", dir))
+        .unwrap()
+        .write_all(code.as_bytes())
+        .unwrap();
+    File::create(format!("{}/module_header-user.rs", dir))
+        .unwrap()
+        .write_all(HEADER_USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/module_header-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/module_header-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn human_readable() {
+    let addr: std::net::Ipv4Addr = "10.0.0.1".parse().unwrap();
+    let mut module = uneval::Module::new().with_human_readable(false);
+    module.add_const("ADDR", "[u8; 4]", addr).unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        "pub const ADDR: [u8; 4] = [10u8,0u8,0u8,1u8];\n"
+    );
+}
+
+#[test]
+fn chained_items() {
+    let mut module = uneval::Module::new();
+    module
+        .add_const("LIMIT", "u32", 10u32)
+        .and_then(|module| module.add_const("NAME", "&str", "uneval"))
+        .unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        "pub const LIMIT: u32 = 10u32;\npub const NAME: &str = \"uneval\";\n"
+    );
+}
+
+#[test]
+fn visibility() {
+    use uneval::config::{ConstOpts, StaticOpts};
+
+    let mut module = uneval::Module::new();
+    module
+        .add_const_with(
+            ConstOpts {
+                visibility: "",
+                ..ConstOpts::new("LIMIT", "u32")
+            },
+            10u32,
+        )
+        .unwrap()
+        .add_static_with(
+            StaticOpts {
+                visibility: "pub(crate)",
+                ..StaticOpts::new("NAMES", "Vec<String>")
+            },
+            vec!["a"],
+        )
+        .unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        concat!(
+            "const LIMIT: u32 = 10u32;\n",
+            "pub(crate) static NAMES: std::sync::LazyLock<Vec<String>> = ",
+            "std::sync::LazyLock::new(|| vec![\"a\".into()].into_iter().collect());\n"
+        )
+    );
+}
+
+#[test]
+fn item_attributes() {
+    use uneval::config::{ConstOpts, Header, StaticOpts};
+
+    let mut module = uneval::Module::new().with_header(
+        Header::new()
+            .with_lines(Vec::<String>::new())
+            .with_attributes(["#[rustfmt::skip]"]),
+    );
+    module
+        .add_const_with(
+            ConstOpts {
+                attributes: &["#[allow(clippy::unreadable_literal)]"],
+                ..ConstOpts::new("MASK", "u32")
+            },
+            0xFFFF_FFFFu32,
+        )
+        .unwrap()
+        .add_static_with(
+            StaticOpts {
+                attributes: &["#[cfg(feature = \"full-data\")]", "#[doc(hidden)]"],
+                ..StaticOpts::new("NAMES", "Vec<String>")
+            },
+            Vec::<String>::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        concat!(
+            "#[rustfmt::skip]\n#[allow(clippy::unreadable_literal)]\n",
+            "pub const MASK: u32 = 4294967295u32;\n",
+            "#[rustfmt::skip]\n#[cfg(feature = \"full-data\")]\n#[doc(hidden)]\n",
+            "pub static NAMES: std::sync::LazyLock<Vec<String>> = std::sync::LazyLock::new(|| Default::default());\n"
+        )
+    );
+}
+
+#[test]
+fn imports() {
+    let mut module = uneval::Module::new()
+        .with_header(uneval::config::Header::new().with_attributes(Vec::<String>::new()))
+        .with_import("crate::model::*")
+        .with_import("std::collections::BTreeMap");
+    module.add_const("LIMIT", "u32", 10u32).unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        format!(
+            "// @generated by uneval {} -- do not edit\nuse crate::model::*;\nuse std::collections::BTreeMap;\npub const LIMIT: u32 = 10u32;\n",
+            env!("CARGO_PKG_VERSION")
+        )
+    );
+}
+
+struct Broken;
+
+impl Serialize for Broken {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("broken"))
+    }
+}
+
+#[test]
+fn failed_item() {
+    fn module() -> uneval::Module {
+        let mut module = uneval::Module::new();
+        module.add_const("LIMIT", "u32", 10u32).unwrap();
+        // The partial code of the failed item is dropped, and the serializer can still be used.
+        let broken = module.add_const("PAIRS", "Vec<(u8, u8)>", vec![(1u8, Broken)]);
+        assert!(broken.is_err());
+        module.add_const("NAME", "&str", "uneval").unwrap();
+        module
+    }
+
+    assert_eq!(
+        module().into_string().unwrap(),
+        "pub const LIMIT: u32 = 10u32;\npub const NAME: &str = \"uneval\";\n"
+    );
+    let dir = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("module_failed_item");
+    create_dir_all(&dir).unwrap();
+    let chunks = module()
+        .to_files_chunked(&dir, "items", uneval::config::ChunkSize::Elements(1))
+        .unwrap();
+    assert_eq!(chunks, 2);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("items_1.rs")).unwrap(),
+        "pub const NAME: &str = \"uneval\";\n"
+    );
+}
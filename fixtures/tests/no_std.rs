@@ -0,0 +1,107 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+use uneval::config::Env;
+use uneval::error::UnevalError;
+use uneval::ser::Uneval;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    values: Vec<u8>,
+}
+
+const USER: &str = r#"
+#![no_std]
+extern crate alloc;
+extern crate std;
+
+use alloc::{string::String, vec::Vec};
+
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    name: String,
+    values: Vec<u8>,
+}
+
+fn main() {
+    let entries: Vec<Entry> = include!("generated.rs");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name, "first");
+    assert_eq!(entries[1].values, [3, 4, 5]);
+}
+"#;
+
+#[test]
+fn alloc_env() {
+    let dir = "test_fixtures/no_std";
+    create_dir_all(dir).unwrap();
+
+    let entries = vec![
+        Entry {
+            name: "first".into(),
+            values: vec![1, 2],
+        },
+        Entry {
+            name: "second".into(),
+            values: vec![3, 4, 5],
+        },
+    ];
+    entries
+        .serialize(
+            &mut Uneval::new(File::create(format!("{}/generated.rs", dir)).unwrap())
+                .with_env(Env::Alloc),
+        )
+        .unwrap();
+    File::create(format!("{}/no_std-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/no_std-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/no_std-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn core_env() {
+    let mut out = Vec::new();
+    let err = vec![1u8]
+        .serialize(&mut Uneval::new(&mut out).with_env(Env::Core))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::RequiresAlloc {
+            what: "sequence",
+            ..
+        }
+    ));
+
+    let err = "owned"
+        .serialize(&mut Uneval::new(&mut out).with_env(Env::Core))
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::RequiresAlloc {
+            what: "owned string",
+            ..
+        }
+    ));
+
+    let mut out = Vec::new();
+    (1u8, "borrowed")
+        .serialize(
+            &mut Uneval::new(&mut out)
+                .with_env(Env::Core)
+                .with_str_mode(uneval::config::StrMode::Borrowed),
+        )
+        .unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("\"borrowed\""));
+}
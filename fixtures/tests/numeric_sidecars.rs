@@ -0,0 +1,183 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+use uneval::{config::SeqMode, ser::Uneval};
+
+#[derive(Serialize)]
+struct Tables {
+    floats: Vec<f32>,
+    doubles: Box<[f64]>,
+    indices: Vec<u32>,
+    bytes: Vec<u8>,
+    small: Vec<f32>,
+    mixed: Vec<Option<u32>>,
+}
+
+/// Defines the tables with the special values, including the NaNs with non-default payloads,
+/// and their code for the user.
+macro_rules! tables {
+    ($($code:tt)*) => {
+        $($code)*
+        const TABLES: &str = stringify!($($code)*);
+    };
+}
+
+tables! {
+    fn floats() -> Vec<f32> {
+        let special = [0.0, -0.0, f32::INFINITY, f32::NEG_INFINITY, f32::MIN_POSITIVE / 2.0];
+        let nans = [0x7fc0_1234, 0xffc0_0001, 0x7f80_0001].map(f32::from_bits);
+        special.into_iter().chain(nans).chain((0..1000).map(|i| i as f32 / 7.0)).collect()
+    }
+
+    fn doubles() -> Box<[f64]> {
+        let nans = [0x7ff8_0000_dead_beef, 0xfff8_0000_0000_0001, 0x7ff0_0000_0000_0001]
+            .map(f64::from_bits);
+        nans.into_iter()
+            .chain([f64::MAX, f64::EPSILON])
+            .chain((0..500).map(|i| i as f64 / 3.0))
+            .collect()
+    }
+
+    fn indices() -> Vec<u32> {
+        (0..1000).map(|i| u32::MAX - i * 65_537).collect()
+    }
+}
+
+const USER: &str = r#"
+pub struct Tables {
+    floats: Vec<f32>,
+    doubles: Box<[f64]>,
+    indices: Vec<u32>,
+    bytes: Vec<u8>,
+    small: Vec<f32>,
+    mixed: Vec<Option<u32>>,
+}
+
+fn main() {
+    let tables: Tables = include!("generated.rs");
+    let bits = |values: &[f32]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&tables.floats), bits(&floats()));
+    let bits = |values: &[f64]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&tables.doubles), bits(&doubles()));
+    assert_eq!(tables.indices, indices());
+    assert_eq!(tables.bytes, vec![42; 100]);
+    assert_eq!(tables.small, [1.5]);
+    assert_eq!(tables.mixed.len(), 100);
+}
+"#;
+
+/// Names of the sidecars included into the generated code, in order.
+fn included(code: &str) -> Vec<&str> {
+    code.split('"')
+        .filter(|part| part.starts_with("generated.rs.") && part.ends_with(".bin"))
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    let dir = "test_fixtures/numeric_sidecars";
+    create_dir_all(dir).unwrap();
+
+    let tables = Tables {
+        floats: floats(),
+        doubles: doubles(),
+        indices: indices(),
+        bytes: vec![42; 100],
+        small: vec![1.5],
+        mixed: (0..100).map(Some).collect(),
+    };
+    let path = format!("{}/generated.rs", dir);
+    let uneval = Uneval::new(Vec::new())
+        .with_numeric_sidecar_at("Tables.floats")
+        .with_numeric_sidecar_at("Tables.doubles")
+        .with_numeric_sidecar_at("Tables.indices")
+        .with_numeric_sidecar_at("Tables.bytes")
+        .with_numeric_sidecar_at("Tables.small")
+        .with_numeric_sidecar_at("Tables.mixed")
+        .with_seq_mode_at("Tables.doubles", SeqMode::BoxedSlice);
+    uneval::to_file_with_sidecars_with(&tables, &path, 64, uneval).unwrap();
+
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(code.contains("f32::from_le_bytes"));
+    assert!(code.contains(".chunks_exact(8).map(|chunk| f64::from_le_bytes"));
+    assert!(code.contains(".collect::<Box<[_]>>()"));
+    assert!(code.contains("u32::from_le_bytes"));
+    assert!(code.contains("small: vec![1.5f32]"));
+    assert!(code.contains("mixed: vec![Some(0u32)"));
+    let lens: Vec<_> = included(&code)
+        .iter()
+        .map(|name| {
+            std::fs::metadata(format!("{}/{}", dir, name))
+                .unwrap()
+                .len()
+        })
+        .collect();
+    // Bytes are included as usual.
+    assert_eq!(
+        lens,
+        [
+            4 * tables.floats.len() as u64,
+            8 * tables.doubles.len() as u64,
+            4 * tables.indices.len() as u64,
+            100
+        ]
+    );
+
+    File::create(format!("{}/numeric-user.rs", dir))
+        .unwrap()
+        .write_all(format!("{}{}", TABLES, USER).as_bytes())
+        .unwrap();
+    File::create(format!("{}/numeric-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/numeric-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[derive(Serialize)]
+struct Le {
+    values: Vec<u32>,
+}
+
+#[test]
+fn little_endian() {
+    let dir = "test_fixtures/numeric_sidecars_le";
+    create_dir_all(dir).unwrap();
+    let path = format!("{}/generated.rs", dir);
+    let value = Le {
+        values: vec![0x0102_0304; 16],
+    };
+    let uneval = Uneval::new(Vec::new()).with_numeric_sidecar_at("Le.values");
+    uneval::to_file_with_sidecars_with(&value, &path, 16, uneval).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    let sidecar = format!("{}/{}", dir, included(&code)[0]);
+    let data = std::fs::read(&sidecar).unwrap();
+    assert_eq!(data, [4, 3, 2, 1].repeat(16));
+    // Without the option, numbers are emitted inline.
+    uneval::to_file_with_sidecars(&value, &path, 16).unwrap();
+    assert!(std::fs::metadata(&sidecar).is_err());
+}
+
+#[test]
+fn old_rust() {
+    let dir = "test_fixtures/numeric_sidecars_old";
+    create_dir_all(dir).unwrap();
+    let path = format!("{}/generated.rs", dir);
+    let value = Le {
+        values: vec![7; 16],
+    };
+    // Decoding requires `TryInto`, so the numbers are emitted inline for the older Rust.
+    let uneval = Uneval::new(Vec::new())
+        .with_numeric_sidecar_at("Le.values")
+        .with_min_rust_version(33);
+    uneval::to_file_with_sidecars_with(&value, &path, 16, uneval).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(included(&code).is_empty());
+    assert!(code.contains("values: vec![7u32,"));
+}
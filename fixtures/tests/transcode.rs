@@ -0,0 +1,72 @@
+use batch_run::Batch;
+use std::fs::{create_dir_all, File};
+use std::io::Write;
+
+const INPUTS: &[(&str, &str)] = &[
+    (
+        "numbers",
+        r#"{"primes": [2, 3, 5, 7], "squares": [1, 4, 9], "empty": []}"#,
+    ),
+    (
+        "floats",
+        r#"{"pi": 3.14159, "e": 2.71828, "negative": -0.5}"#,
+    ),
+    (
+        "capitals",
+        r#"{"Narnia": {"capital": "Cair Paravel", "ruler": "Aslan"}, "Ruritania": {"capital": "Strelsau"}}"#,
+    ),
+];
+
+const USER: &str = r#"
+use std::collections::{BTreeMap, HashMap};
+
+fn main() {
+    let numbers: HashMap<String, Vec<u64>> = include!("numbers.rs");
+    assert_eq!(numbers["primes"], vec![2, 3, 5, 7]);
+    assert_eq!(numbers["squares"], vec![1, 4, 9]);
+    assert!(numbers["empty"].is_empty());
+
+    let floats: HashMap<String, f64> = include!("floats.rs");
+    assert_eq!(floats["pi"], 3.14159);
+    assert_eq!(floats["e"], 2.71828);
+    assert_eq!(floats["negative"], -0.5);
+
+    let capitals: BTreeMap<String, BTreeMap<String, String>> = include!("capitals.rs");
+    assert_eq!(capitals["Narnia"]["capital"], "Cair Paravel");
+    assert_eq!(capitals["Narnia"]["ruler"], "Aslan");
+    assert_eq!(capitals["Ruritania"].len(), 1);
+}
+"#;
+
+#[test]
+fn json() {
+    let dir = "test_fixtures/transcode";
+    create_dir_all(dir).unwrap();
+
+    for (name, json) in INPUTS {
+        let file = File::create(format!("{}/{}.rs", dir, name)).unwrap();
+        uneval::transcode(&mut serde_json::Deserializer::from_str(json), file).unwrap();
+    }
+    File::create(format!("{}/transcode-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/transcode-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/transcode-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
+
+#[test]
+fn invalid_input() {
+    let mut out = Vec::new();
+    let result = uneval::transcode(&mut serde_json::Deserializer::from_str("[1, 2"), &mut out);
+    assert!(matches!(
+        result,
+        Err(uneval::error::UnevalError::Custom { .. })
+    ));
+}
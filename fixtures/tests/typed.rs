@@ -0,0 +1,82 @@
+use batch_run::Batch;
+use serde::Serialize;
+use std::{
+    fs::{create_dir_all, File},
+    io::Write,
+};
+
+#[derive(Serialize)]
+struct Entry {
+    name: &'static str,
+    tags: Vec<String>,
+}
+
+/// Neither the outer collection nor the empty tags are constrained by anything but the annotation.
+const USER: &str = r#"
+#[derive(Debug, PartialEq)]
+pub struct Entry {
+    name: String,
+    tags: Vec<String>,
+}
+
+fn main() {
+    assert_eq!(include!("generated.rs").len(), 2);
+    let names: Vec<_> = include!("generated.rs").into_iter().map(|entry| entry.name).collect();
+    assert_eq!(names, ["first", "second"]);
+}
+"#;
+
+#[test]
+fn typed() {
+    let dir = "test_fixtures/typed";
+    create_dir_all(dir).unwrap();
+
+    let entries = vec![
+        Entry {
+            name: "first",
+            tags: vec![],
+        },
+        Entry {
+            name: "second",
+            tags: vec!["b".into()],
+        },
+    ];
+    let code = uneval::to_string_typed(&entries, "Vec<Entry>").unwrap();
+    assert!(code.starts_with("{ let __uneval_value: Vec<Entry> = vec!["));
+    assert!(code.ends_with("; __uneval_value }"));
+    // The tuple conversion runtime stays inside the annotated expression.
+    assert_eq!(
+        uneval::to_string_typed((1u8, 2u8), "[u8; 2]").unwrap(),
+        format!(
+            "{{ let __uneval_value: [u8; 2] = {}; __uneval_value }}",
+            uneval::to_string((1u8, 2u8)).unwrap()
+        )
+    );
+
+    // The header attributes are applied to the binding, which must be accepted at the include site.
+    let header = uneval::config::Header::new();
+    let mut code = Vec::new();
+    uneval::write_typed(
+        &entries,
+        "Vec<Entry>",
+        uneval::ser::Uneval::new(&mut code).with_header(header),
+    )
+    .unwrap();
+    let code = String::from_utf8(code).unwrap();
+    assert!(code.contains(
+        "\n{ #[allow(clippy::all, unused_mut)]\n#[rustfmt::skip]\nlet __uneval_value: Vec<Entry> = "
+    ));
+    std::fs::write(format!("{}/generated.rs", dir), code).unwrap();
+    File::create(format!("{}/typed-user.rs", dir))
+        .unwrap()
+        .write_all(USER.as_bytes())
+        .unwrap();
+    File::create(format!("{}/typed-user.snapshot", dir))
+        .unwrap()
+        .write_all(include_bytes!("user.snapshot.tpl"))
+        .unwrap();
+
+    let b = Batch::new();
+    b.run_match(format!("{}/typed-user.rs", dir));
+    b.run().unwrap().assert_all_ok();
+}
@@ -0,0 +1,188 @@
+//! Procedural macro embedding the contents of data files as Rust code, without the build script.
+//!
+//! ```ignore
+//! let primes: std::collections::HashMap<String, Vec<u64>> =
+//!     uneval_macros::embed!("data/primes.json" as std::collections::HashMap<String, Vec<u64>>);
+//! ```
+//!
+//! The file is read at macro expansion, relative to the directory of the crate being compiled
+//! (i.e. to `CARGO_MANIFEST_DIR`), and converted to code with [`uneval::transcode`], so the expansion
+//! contains no `serde` at all. The format is chosen by the file extension: `.json`, `.yaml` or `.yml`
+//! (with `yaml` feature) and `.ron` (with `ron` feature).
+//!
+//! Note that the macro can't deserialize the data into the named type itself, since this type belongs
+//! to the crate which is being compiled. Instead, the generated code follows the data model of the format,
+//! and the type is only used to annotate the resulting value; so the type must be buildable from it,
+//! just like with [`uneval::transcode`]. For JSON, this means the sequences, maps with string keys,
+//! `u64`, `i64`, `f64`, `bool` and `String`, but not structs or optional values.
+//!
+//! The file is also passed to [`include_bytes!`], so that the crate is rebuilt when it changes.
+//!
+//! This crate can't be re-exported from `uneval` itself, since it depends on it.
+//!
+//! It also provides `#[derive(UnevalPath)]`, implementing [`uneval::TypePath`], so that the types can be emitted
+//! by their full paths with [`Uneval::with_type_path_of`][uneval::ser::Uneval::with_type_path_of].
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{DeriveInput, LitStr, Token, Type};
+
+struct Embed {
+    path: LitStr,
+    ty: Type,
+}
+
+impl Parse for Embed {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        input.parse::<Token![as]>()?;
+        let ty = input.parse()?;
+        Ok(Self { path, ty })
+    }
+}
+
+/// Embeds the contents of the data file as the value of the given type; see the [crate docs][crate] for details.
+#[proc_macro]
+pub fn embed(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as Embed);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(input: &Embed) -> syn::Result<TokenStream> {
+    let error = |message: String| syn::Error::new(input.path.span(), message);
+
+    let relative = input.path.value();
+    let mut path: PathBuf = std::env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| error("`CARGO_MANIFEST_DIR` is not set".into()))?
+        .into();
+    path.push(&relative);
+    let contents = std::fs::read(&path)
+        .map_err(|err| error(format!("can't read `{}`: {}", path.display(), err)))?;
+
+    let mut code = Vec::new();
+    let result = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => uneval::transcode(
+            &mut serde_json::Deserializer::from_slice(&contents),
+            &mut code,
+        ),
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => {
+            uneval::transcode(serde_yaml::Deserializer::from_slice(&contents), &mut code)
+        }
+        #[cfg(feature = "ron")]
+        Some("ron") => match ron::Deserializer::from_bytes(&contents) {
+            Ok(mut deserializer) => uneval::transcode(&mut deserializer, &mut code),
+            Err(err) => return Err(error(format!("can't parse `{}`: {}", relative, err))),
+        },
+        _ => {
+            return Err(error(format!(
+                "unsupported format of `{}`, expected `.json`{}{}",
+                relative,
+                if cfg!(feature = "yaml") {
+                    ", `.yaml`"
+                } else {
+                    ""
+                },
+                if cfg!(feature = "ron") {
+                    ", `.ron`"
+                } else {
+                    ""
+                },
+            )))
+        }
+    };
+    result.map_err(|err| error(format!("can't embed `{}`: {}", relative, err)))?;
+
+    let code = String::from_utf8(code).map_err(|err| error(err.to_string()))?;
+    let value: TokenStream = code
+        .parse()
+        .map_err(|err| error(format!("generated code can't be parsed: {}", err)))?;
+    let value = respan(value, input.path.span());
+    let tracked = path.to_string_lossy();
+    let ty = &input.ty;
+    Ok(quote! {
+        {
+            const _: &[u8] = include_bytes!(#tracked);
+            let value: #ty = #value;
+            value
+        }
+    })
+}
+
+/// Points the generated tokens to the path literal, so that the type errors in them are reported there.
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let proc_macro2::TokenTree::Group(group) = &token {
+                let mut inner =
+                    proc_macro2::Group::new(group.delimiter(), respan(group.stream(), span));
+                inner.set_span(span);
+                token = inner.into();
+            }
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+/// Implements [`uneval::TypePath`] for the struct or enum.
+///
+/// The name seen by the serializer is taken from `#[serde(rename = "...")]`, if there is one.
+#[proc_macro_derive(UnevalPath)]
+pub fn derive_uneval_path(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match derive_path(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn derive_path(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let mut name = ident.to_string();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                // Both `rename = "..."` and `rename(serialize = "...")` are possible.
+                if meta.input.peek(Token![=]) {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else {
+                    meta.parse_nested_meta(|inner| {
+                        let value = inner.value()?.parse::<LitStr>()?;
+                        if inner.path.is_ident("serialize") {
+                            name = value.value();
+                        }
+                        Ok(())
+                    })?;
+                }
+            } else if !meta.input.is_empty() && !meta.input.peek(Token![,]) {
+                // Skips the values of other attributes, like `tag = "..."` or `bound(...)`.
+                if meta.input.peek(Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else {
+                    meta.input.parse::<proc_macro2::Group>()?;
+                }
+            }
+            Ok(())
+        })?;
+    }
+    let ident_str = ident.to_string();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::uneval::TypePath for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const IDENT: &'static str = #ident_str;
+            const MODULE: &'static str = ::core::module_path!();
+        }
+    })
+}
@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+#[test]
+fn json() {
+    let numbers = uneval_macros::embed!("tests/data/primes.json" as HashMap<String, Vec<u64>>);
+    assert_eq!(numbers["primes"], [2, 3, 5, 7]);
+    assert!(numbers["empty"].is_empty());
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml() {
+    let populations = uneval_macros::embed!(
+        "tests/data/populations.yaml" as std::collections::BTreeMap<String, Vec<u64>>
+    );
+    assert_eq!(populations["Cair Paravel"], [1200, 1400]);
+    assert!(populations["Strelsau"].is_empty());
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn ron() {
+    let ratios =
+        uneval_macros::embed!("tests/data/ratios.ron" as std::collections::BTreeMap<String, f64>);
+    assert_eq!(ratios["golden"], 1.618);
+}
@@ -0,0 +1,47 @@
+use serde::Serialize;
+use uneval::TypePath;
+use uneval_macros::UnevalPath;
+
+mod model {
+    use super::*;
+
+    #[derive(Serialize, UnevalPath)]
+    pub struct Point {
+        pub x: i32,
+    }
+
+    #[derive(Serialize, UnevalPath)]
+    #[serde(rename = "RenderPoint", deny_unknown_fields)]
+    pub struct Renamed {
+        pub y: i32,
+    }
+
+    #[derive(Serialize, UnevalPath)]
+    #[serde(tag = "kind", rename(serialize = "Tagged"))]
+    pub enum Shape<T> {
+        Dot { at: T },
+    }
+}
+
+#[test]
+fn derived_paths() {
+    assert_eq!(model::Point::NAME, "Point");
+    assert_eq!(model::Point::path(), "crate::model::Point");
+    assert_eq!(model::Renamed::NAME, "RenderPoint");
+    assert_eq!(model::Renamed::IDENT, "Renamed");
+    assert_eq!(<model::Shape<u8>>::NAME, "Tagged");
+    assert_eq!(<model::Shape<u8>>::path(), "crate::model::Shape");
+
+    // The derived name is the one serde passes to the serializer.
+    let code = uneval::to_string(model::Shape::Dot { at: 1u8 }).unwrap();
+    assert_eq!(code, "Tagged {kind: \"Dot\".into(),at: 1u8}");
+
+    let mut out = Vec::new();
+    model::Point { x: 1 }
+        .serialize(&mut uneval::ser::Uneval::new(&mut out).with_type_path_of::<model::Point>())
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "crate::model::Point {x: 1i32}"
+    );
+}
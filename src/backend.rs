@@ -0,0 +1,117 @@
+//! Code emitted for the serde events; see [`Emitter`].
+
+use crate::config::{Env, MapMode, SeqMode, StrMode, TupleMode};
+
+/// Hooks deciding which Rust code is emitted for the values the serializer meets.
+///
+/// While [`Formatter`][crate::format::Formatter] decides the whitespace and separators, the emitter decides
+/// the expressions themselves: the code opening and closing every sequence, map, string and literal tuple,
+/// the unit value and the code standing for the empty container. The serializer still does the traversal,
+/// the paths, the options and the diagnostics, and writes the elements between the pieces the emitter returns;
+/// so the alternative backend (e.g. the one emitting only constant expressions) overrides only the hooks it cares
+/// about. Every method has the default implementation producing the usual code, the one of [`DefaultEmitter`].
+///
+/// The emitter is set with [`Uneval::with_emitter`][crate::ser::Uneval::with_emitter]:
+/// ```
+/// # use serde::Serialize;
+/// use uneval::backend::Emitter;
+/// use uneval::config::{Env, SeqMode};
+///
+/// /// Builds the sequences from arrays, e.g. `[1u8, 2u8].into()`.
+/// struct FromArrays;
+///
+/// impl Emitter for FromArrays {
+///     fn seq_open(&mut self, _mode: SeqMode, _env: Env) -> String {
+///         "[".into()
+///     }
+///     fn seq_close(&mut self, _mode: SeqMode) -> String {
+///         "].into()".into()
+///     }
+/// }
+///
+/// let mut out = Vec::new();
+/// vec![1u8, 2].serialize(&mut uneval::ser::Uneval::new(&mut out).with_emitter(FromArrays))?;
+/// assert_eq!(String::from_utf8(out).unwrap(), "[1u8,2u8].into()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+///
+/// The emitted pieces must stay consistent with each other and with the options of the serializer:
+/// e.g. the elements of [`SeqMode::Slice`] are still emitted in constant context.
+pub trait Emitter {
+    /// The code opening the sequence emitted in the given mode, e.g. `vec![`.
+    fn seq_open(&mut self, mode: SeqMode, env: Env) -> String {
+        mode.open(env)
+    }
+
+    /// The code closing the sequence emitted in the given mode, e.g. `].into_iter().collect()`.
+    ///
+    /// If it ends with `collect()`, the type hint set with
+    /// [`with_collect_hint_at`][crate::ser::Uneval::with_collect_hint_at] is inserted into it.
+    fn seq_close(&mut self, mode: SeqMode) -> String {
+        mode.close().into()
+    }
+
+    /// The code opening the map emitted in the given mode, e.g. `vec![`; the entries are emitted as tuples,
+    /// unless the map is static.
+    fn map_open(&mut self, mode: &MapMode, env: Env) -> String {
+        mode.open(env)
+    }
+
+    /// The code closing the map emitted in the given mode, e.g. `].into_iter().collect()`.
+    fn map_close(&mut self, mode: &MapMode) -> String {
+        mode.close().into()
+    }
+
+    /// The code preceding the string literal (or the `include_str!` of its sidecar) in the given mode,
+    /// e.g. `String::from(`.
+    fn str_open(&mut self, mode: StrMode, env: Env) -> String {
+        mode.open(env)
+    }
+
+    /// The code following the string literal in the given mode, e.g. `)`.
+    fn str_close(&mut self, mode: StrMode) -> String {
+        mode.close().into()
+    }
+
+    /// The code opening the tuple or array of the given length, emitted as [`TupleMode::Array`]
+    /// or [`TupleMode::Tuple`]; the conversion runtime of [`TupleMode::Convert`] is not customizable.
+    fn tuple_open(&mut self, mode: TupleMode, len: usize) -> String {
+        let _ = len;
+        match mode {
+            TupleMode::Array => "[".into(),
+            _ => "(".into(),
+        }
+    }
+
+    /// The code closing the tuple or array of the given length, emitted as [`TupleMode::Array`]
+    /// or [`TupleMode::Tuple`].
+    fn tuple_close(&mut self, mode: TupleMode, len: usize) -> String {
+        match (mode, len) {
+            (TupleMode::Array, _) => "]".into(),
+            // One-element tuple needs the trailing comma to be a tuple.
+            (_, 1) => ",)".into(),
+            _ => ")".into(),
+        }
+    }
+
+    /// The code of the unit value.
+    fn unit(&mut self) -> String {
+        "()".into()
+    }
+
+    /// The code replacing the empty sequence or map, if it is allowed to be emitted as the default value
+    /// (see [`with_empty_as_default`][crate::ser::Uneval::with_empty_as_default]),
+    /// with the type from the collect hint, if there is one.
+    fn default_value(&mut self, hint: Option<&str>) -> String {
+        match hint {
+            Some(hint) => format!("<{}>::default()", hint),
+            None => "Default::default()".into(),
+        }
+    }
+}
+
+/// The default emitter, producing the code described in the [crate-level documentation](crate).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultEmitter;
+
+impl Emitter for DefaultEmitter {}
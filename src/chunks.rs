@@ -0,0 +1,360 @@
+//! Splitting of the top-level sequences and maps into several files; see [`to_files_chunked`][crate::to_files_chunked].
+//!
+//! Every chunk file contains the `vec!` of elements (or key-value pairs), and the main file joins them with
+//! `vec![include!("name_0.rs"),include!("name_1.rs")].into_iter().flatten().collect()`.
+//! The outer `Vec` is iterated by value on any Rust version, unlike the array (Rust 1.53).
+//!
+//! Map entries (and, optionally, sequence elements) are sorted by their code before being split, so that
+//! the same value always yields the same files, even if its iteration order is random, like for `HashMap`.
+
+use crate::config::ChunkSize;
+use crate::error::UnevalError;
+use crate::funcs::write_atomically;
+use crate::ser::{SerResult, Uneval};
+use serde::ser::{self, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Serializer which accepts only sequences and maps, writing their elements into the chunk files.
+pub(crate) struct Splitter {
+    dir: PathBuf,
+    base_name: String,
+    size: ChunkSize,
+    chunk: Vec<u8>,
+    len: usize,
+    chunks: usize,
+    sort_seq: bool,
+    /// Code of the elements which are sorted before being split, if they are.
+    sorted: Option<Vec<Vec<u8>>>,
+}
+
+impl Splitter {
+    pub(crate) fn new(dir: PathBuf, base_name: &str, size: ChunkSize) -> Self {
+        Self {
+            dir,
+            base_name: base_name.into(),
+            size,
+            chunk: Vec::new(),
+            len: 0,
+            chunks: 0,
+            sort_seq: false,
+            sorted: None,
+        }
+    }
+
+    /// Sorts the elements of the top-level sequence, too, e.g. for `HashSet`.
+    pub(crate) fn with_sorted_seq(mut self) -> Self {
+        self.sort_seq = true;
+        self
+    }
+
+    /// Adds the element's code, either to the current chunk or to the ones to be sorted.
+    fn add(&mut self, code: Vec<u8>) -> SerResult {
+        match &mut self.sorted {
+            Some(sorted) => {
+                sorted.push(code);
+                Ok(())
+            }
+            None => self.push(&code),
+        }
+    }
+
+    fn chunk_name(&self, index: usize) -> String {
+        format!("{}_{}.rs", self.base_name, index)
+    }
+
+    /// Adds the element's code to the current chunk, writing the chunk first if the element doesn't fit there.
+    fn push(&mut self, code: &[u8]) -> SerResult {
+        let full = match self.size {
+            ChunkSize::Elements(max) => self.len >= max.max(1),
+            ChunkSize::Bytes(max) => self.len > 0 && self.chunk.len() + code.len() > max,
+        };
+        if full {
+            self.flush()?;
+        }
+        if self.len > 0 {
+            self.chunk.push(b',');
+        }
+        self.chunk.extend_from_slice(code);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> SerResult {
+        let path = self.dir.join(self.chunk_name(self.chunks));
+        let chunk = std::mem::take(&mut self.chunk);
+        write_atomically(&path, |writer| {
+            writer.write_all(b"vec![")?;
+            writer.write_all(&chunk)?;
+            writer.write_all(b"]")?;
+            Ok(())
+        })?;
+        self.len = 0;
+        self.chunks += 1;
+        Ok(())
+    }
+
+    /// Writes the last chunk and the main file, returning the number of chunks.
+    ///
+    /// Chunks left from the previous runs, which had more of them, are removed.
+    fn finish(&mut self) -> Result<usize, UnevalError> {
+        if let Some(mut sorted) = self.sorted.take() {
+            sorted.sort();
+            for code in sorted {
+                self.push(&code)?;
+            }
+        }
+        // Even the empty value gets one chunk, so that the element type can still be inferred.
+        if self.len > 0 || self.chunks == 0 {
+            self.flush()?;
+        }
+        let path = self.dir.join(format!("{}.rs", self.base_name));
+        write_atomically(&path, |writer| {
+            writer.write_all(b"vec![")?;
+            for index in 0..self.chunks {
+                if index > 0 {
+                    writer.write_all(b",")?;
+                }
+                write!(writer, "include!({:?})", self.chunk_name(index))?;
+            }
+            writer.write_all(b"].into_iter().flatten().collect()")?;
+            Ok(())
+        })?;
+        self.remove_stale()?;
+        Ok(self.chunks)
+    }
+
+    fn remove_stale(&self) -> SerResult {
+        let prefix = format!("{}_", self.base_name);
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let index = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(".rs"))
+                .filter(|index| index.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|index| index.parse::<usize>().ok());
+            if index.is_some_and(|index| index >= self.chunks) {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn element_code<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>, UnevalError> {
+    let mut code = Vec::new();
+    value.serialize(&mut Uneval::new(&mut code))?;
+    Ok(code)
+}
+
+fn unsupported<T>(what: &str) -> Result<T, UnevalError> {
+    Err(UnevalError::Custom {
+        message: format!(
+            "only sequences and maps can be split into chunks, got {}",
+            what
+        ),
+        path: String::new(),
+    })
+}
+
+impl<'a> ser::Serializer for &'a mut Splitter {
+    type Ok = usize;
+    type Error = UnevalError;
+
+    type SerializeSeq = &'a mut Splitter;
+    type SerializeTuple = ser::Impossible<usize, UnevalError>;
+    type SerializeTupleStruct = ser::Impossible<usize, UnevalError>;
+    type SerializeTupleVariant = ser::Impossible<usize, UnevalError>;
+    type SerializeMap = MapSplitter<'a>;
+    type SerializeStruct = ser::Impossible<usize, UnevalError>;
+    type SerializeStructVariant = ser::Impossible<usize, UnevalError>;
+
+    fn serialize_bool(self, _v: bool) -> Result<usize, UnevalError> {
+        unsupported("boolean")
+    }
+    fn serialize_i8(self, _v: i8) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_i16(self, _v: i16) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_i32(self, _v: i32) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_i64(self, _v: i64) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_u8(self, _v: u8) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_u16(self, _v: u16) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_u32(self, _v: u32) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_u64(self, _v: u64) -> Result<usize, UnevalError> {
+        unsupported("integer")
+    }
+    fn serialize_f32(self, _v: f32) -> Result<usize, UnevalError> {
+        unsupported("floating-point number")
+    }
+    fn serialize_f64(self, _v: f64) -> Result<usize, UnevalError> {
+        unsupported("floating-point number")
+    }
+    fn serialize_char(self, _v: char) -> Result<usize, UnevalError> {
+        unsupported("char")
+    }
+    fn serialize_str(self, _v: &str) -> Result<usize, UnevalError> {
+        unsupported("string")
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<usize, UnevalError> {
+        ser::Serializer::collect_seq(self, v)
+    }
+    fn serialize_none(self) -> Result<usize, UnevalError> {
+        unsupported("option")
+    }
+    fn serialize_some<T>(self, _value: &T) -> Result<usize, UnevalError>
+    where
+        T: ?Sized + Serialize,
+    {
+        unsupported("option")
+    }
+    fn serialize_unit(self) -> Result<usize, UnevalError> {
+        unsupported("unit")
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<usize, UnevalError> {
+        unsupported("struct")
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<usize, UnevalError> {
+        unsupported("enum")
+    }
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<usize, UnevalError>
+    where
+        T: ?Sized + Serialize,
+    {
+        unsupported("struct")
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<usize, UnevalError>
+    where
+        T: ?Sized + Serialize,
+    {
+        unsupported("enum")
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, UnevalError> {
+        if self.sort_seq {
+            self.sorted = Some(Vec::new());
+        }
+        Ok(self)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, UnevalError> {
+        unsupported("tuple")
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, UnevalError> {
+        unsupported("struct")
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, UnevalError> {
+        unsupported("enum")
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, UnevalError> {
+        self.sorted = Some(Vec::new());
+        Ok(MapSplitter {
+            splitter: self,
+            key: Vec::new(),
+        })
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, UnevalError> {
+        unsupported("struct")
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, UnevalError> {
+        unsupported("enum")
+    }
+}
+
+impl ser::SerializeSeq for &mut Splitter {
+    type Ok = usize;
+    type Error = UnevalError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        self.add(element_code(value)?)
+    }
+
+    fn end(self) -> Result<usize, UnevalError> {
+        self.finish()
+    }
+}
+
+/// Map entries are split as the `(key,value)` pairs, so that the chunks can be collected into any map.
+pub(crate) struct MapSplitter<'a> {
+    splitter: &'a mut Splitter,
+    key: Vec<u8>,
+}
+
+impl ser::SerializeMap for MapSplitter<'_> {
+    type Ok = usize;
+    type Error = UnevalError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = element_code(key)?;
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut entry = b"(".to_vec();
+        entry.append(&mut self.key);
+        entry.push(b',');
+        entry.append(&mut element_code(value)?);
+        entry.push(b')');
+        self.splitter.add(entry)
+    }
+
+    fn end(self) -> Result<usize, UnevalError> {
+        self.splitter.finish()
+    }
+}
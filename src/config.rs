@@ -0,0 +1,805 @@
+//! Options controlling the shape of generated code.
+
+/// The way to emit the values which Serde treats as tuples, i.e. both tuples and fixed-size arrays.
+///
+/// See the [crate-level documentation](crate#tuples-and-arrays) on why this is necessary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TupleMode {
+    /// Emit the inline conversion runtime, which yields either a tuple or an array, depending on the target type.
+    ///
+    /// This is the default, since it works in every case.
+    #[default]
+    Convert,
+    /// Emit the plain array literal, like `[1u8, 2u8, 3u8]`.
+    ///
+    /// This is more readable and can be used in constant context, but breaks on the real tuples -
+    /// both the ones with different element types (since they can't be array elements)
+    /// and the ones with the same types (since they'll be typed as arrays, not tuples).
+    /// Therefore, this mode should be set for the particular paths, where the target is known to be an array,
+    /// and not globally.
+    Array,
+    /// Emit the plain tuple literal, like `(1u8, "a".into())`.
+    ///
+    /// Like [`Array`][TupleMode::Array], this can be used in constant context, but works only for the real tuples.
+    Tuple,
+}
+
+/// The way to emit variable-length sequences, like `Vec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeqMode {
+    /// `vec![...].into_iter().collect()`, which works for any type implementing `FromIterator`.
+    ///
+    /// This is the default.
+    #[default]
+    VecCollect,
+    /// `vec![...]`, which works for `Vec` only.
+    PlainVec,
+    /// `Vec::from([...])`, which works for `Vec` only, but avoids the `vec!` macro.
+    FromArray,
+    /// `vec![...].into_boxed_slice()`, for boxed slices.
+    BoxedSlice,
+    /// `&[...]`, for `&'static [T]`, which requires no allocation at all.
+    ///
+    /// Elements of the slice are emitted in constant context, as if the whole slice was the `const` item,
+    /// so that the slice can be promoted to `'static`; in particular, strings become bare literals.
+    /// Elements which require allocation or can't be constructed in constant context are reported as
+    /// [`NotConst`][crate::error::UnevalError::NotConst] error. Note that outside of `const` items
+    /// (see [`to_const`][crate::funcs::to_const]) the slice is promoted only if its elements don't contain
+    /// function calls, e.g. `Duration::new`.
+    Slice,
+    /// `std::borrow::Cow::Borrowed(&[...])`, for `Cow<'static, [T]>`.
+    ///
+    /// Elements are emitted the same way as for [`Slice`][SeqMode::Slice], and the sequence is never
+    /// moved into the sidecar file.
+    CowSlice,
+    /// `phf::phf_set! {...}`, for the static sets.
+    ///
+    /// Elements of such sets must be literals, i.e. strings, byte strings, chars, integers or booleans;
+    /// everything else is reported as [`NotLiteral`][crate::error::UnevalError::NotLiteral] error.
+    /// The `phf` crate with `macros` feature must be available to the generated code.
+    ///
+    /// This mode is used for the top-level value of items of type `phf::Set<...>`, unless set explicitly.
+    #[cfg(feature = "phf")]
+    PhfSet,
+}
+
+impl SeqMode {
+    /// Whether this is a static set, which can be constructed in constant context
+    /// and which elements must be literals.
+    pub(crate) fn is_phf(self) -> bool {
+        #[cfg(feature = "phf")]
+        {
+            self == SeqMode::PhfSet
+        }
+        #[cfg(not(feature = "phf"))]
+        {
+            false
+        }
+    }
+
+    pub(crate) fn open(self, env: Env) -> String {
+        match self {
+            SeqMode::FromArray => format!("{}::from([", env.alloc_path("vec::Vec", "Vec")),
+            SeqMode::Slice => "&[".into(),
+            SeqMode::CowSlice => format!(
+                "{}::Borrowed(&[",
+                env.alloc_path("borrow::Cow", "std::borrow::Cow")
+            ),
+            #[cfg(feature = "phf")]
+            SeqMode::PhfSet => "phf::phf_set! {".into(),
+            _ => format!("{}![", env.alloc_path("vec", "vec")),
+        }
+    }
+
+    /// Whether the sequence is built at runtime, so that the empty one can be emitted as `Default::default()`.
+    pub(crate) fn has_default(self) -> bool {
+        !matches!(self, SeqMode::Slice | SeqMode::CowSlice) && !self.is_phf()
+    }
+
+    /// Whether the sequence is borrowed, i.e. its elements are emitted in constant context.
+    pub(crate) fn is_slice(self) -> bool {
+        matches!(self, SeqMode::Slice | SeqMode::CowSlice)
+    }
+
+    /// The suffix which converts the `&[u8; N]` (e.g. from `include_bytes!`) into the sequence.
+    pub(crate) fn bytes_suffix(self) -> &'static str {
+        match self {
+            SeqMode::VecCollect => ".iter().copied().collect()",
+            SeqMode::PlainVec | SeqMode::FromArray => ".to_vec()",
+            SeqMode::BoxedSlice => ".to_vec().into_boxed_slice()",
+            SeqMode::Slice | SeqMode::CowSlice => "",
+            #[cfg(feature = "phf")]
+            SeqMode::PhfSet => "",
+        }
+    }
+
+    /// The suffix which collects the iterator over numbers (e.g. decoded from `include_bytes!`) into the sequence.
+    ///
+    /// Slices and static sets can't be built from the iterator, so they never get there.
+    pub(crate) fn numbers_suffix(self, env: Env) -> String {
+        match self {
+            SeqMode::VecCollect => ".collect()".into(),
+            SeqMode::PlainVec | SeqMode::FromArray => {
+                format!(".collect::<{}<_>>()", env.alloc_path("vec::Vec", "Vec"))
+            }
+            SeqMode::BoxedSlice => {
+                format!(".collect::<{}<[_]>>()", env.alloc_path("boxed::Box", "Box"))
+            }
+            SeqMode::Slice | SeqMode::CowSlice => String::new(),
+            #[cfg(feature = "phf")]
+            SeqMode::PhfSet => String::new(),
+        }
+    }
+
+    pub(crate) fn close(self) -> &'static str {
+        match self {
+            SeqMode::VecCollect => "].into_iter().collect()",
+            SeqMode::PlainVec | SeqMode::Slice => "]",
+            SeqMode::FromArray => "])",
+            SeqMode::BoxedSlice => "].into_boxed_slice()",
+            SeqMode::CowSlice => "])",
+            #[cfg(feature = "phf")]
+            SeqMode::PhfSet => "}",
+        }
+    }
+}
+
+/// The way to emit maps.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MapMode {
+    /// `vec![(k, v), ...].into_iter().collect()`, which works for any type implementing `FromIterator<(K, V)>`.
+    ///
+    /// This is the default.
+    #[default]
+    Collect,
+    /// `<type_name>::from([(k, v), ...])`, e.g. `std::collections::HashMap::from([...])`.
+    ///
+    /// Since the concrete map type is not known to the serializer, its name (or path, if it is not in scope
+    /// of the generated code) must be provided explicitly.
+    FromArray {
+        /// The name of map type, as it should be written in the generated code.
+        type_name: String,
+    },
+    /// `phf::phf_map! {k => v, ...}`, for the static maps with zero initialization cost.
+    ///
+    /// Keys of such maps must be literals, i.e. strings, byte strings, chars, integers or booleans,
+    /// and values must be constant expressions, so that, for example, strings are emitted as `&'static str`
+    /// and sequences are not allowed (unless they are static sets or maps themselves).
+    /// Anything else is reported as [`NotLiteral`][crate::error::UnevalError::NotLiteral]
+    /// or [`NotConst`][crate::error::UnevalError::NotConst] error.
+    /// The `phf` crate with `macros` feature must be available to the generated code.
+    ///
+    /// This mode is used for the top-level value of items of type `phf::Map<...>`, unless set explicitly,
+    /// e.g. with `to_const(map, "CODES", "phf::Map<&str, u8>")`.
+    #[cfg(feature = "phf")]
+    Phf,
+}
+
+impl MapMode {
+    pub(crate) fn open(&self, env: Env) -> String {
+        match self {
+            MapMode::Collect => format!("{}![", env.alloc_path("vec", "vec")),
+            MapMode::FromArray { type_name } => format!("{}::from([", type_name),
+            #[cfg(feature = "phf")]
+            MapMode::Phf => "phf::phf_map! {".into(),
+        }
+    }
+
+    pub(crate) fn close(&self) -> &'static str {
+        match self {
+            MapMode::Collect => "].into_iter().collect()",
+            MapMode::FromArray { .. } => "])",
+            #[cfg(feature = "phf")]
+            MapMode::Phf => "}",
+        }
+    }
+
+    /// Whether this is a static map, which can be constructed in constant context,
+    /// which keys must be literals and which entries are written as `k => v`.
+    pub(crate) fn is_phf(&self) -> bool {
+        #[cfg(feature = "phf")]
+        {
+            *self == MapMode::Phf
+        }
+        #[cfg(not(feature = "phf"))]
+        {
+            false
+        }
+    }
+}
+
+/// The way to write integer literals.
+///
+/// Negative numbers in hexadecimal and binary formats are written as the negated literal, e.g. `-0x10i8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntFormat {
+    /// `3489660928u32`.
+    ///
+    /// This is the default.
+    #[default]
+    Decimal,
+    /// `0xD0000000u32`.
+    Hex,
+    /// `0xD000_0000u32`, with the digits grouped by four.
+    GroupedHex,
+    /// `0b10100101u8`.
+    Binary,
+    /// `0b1010_0101u8`, with the digits grouped by four.
+    GroupedBinary,
+}
+
+/// The way to emit strings.
+///
+/// In constant context, strings are always emitted as `&'static str`, unless the mode is
+/// [`CowBorrowed`][StrMode::CowBorrowed], which is constant as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StrMode {
+    /// `"...".into()`, which works for `String` and anything else convertible from `&str`.
+    ///
+    /// This is the default.
+    #[default]
+    IntoString,
+    /// Bare literal `"..."`, for `&'static str`.
+    ///
+    /// This requires no allocation, but doesn't work for `String`, which can't be created from the literal
+    /// without conversion; set this mode for the specific paths, if the value has both kinds of strings.
+    Borrowed,
+    /// `String::from("...")`, for `String` in generic contexts, where `into` can't be inferred.
+    StringFrom,
+    /// `std::borrow::Cow::Borrowed("...")`, for `Cow<'static, str>`.
+    CowBorrowed,
+    /// `"...".parse().unwrap()`, for the types which implement [`FromStr`][std::str::FromStr] and are serialized
+    /// as strings, like `IpAddr`, `SocketAddr` or timestamps of the date-time libraries.
+    ///
+    /// The target type of `parse` is inferred from the surrounding code, so this works for struct fields
+    /// and elements of typed collections, but not for generic contexts. Since parsing happens when
+    /// the generated code is executed, this can't be used in constant context.
+    Parse,
+}
+
+impl StrMode {
+    pub(crate) fn open(self, env: Env) -> String {
+        match self {
+            StrMode::IntoString | StrMode::Borrowed | StrMode::Parse => String::new(),
+            StrMode::StringFrom => format!("{}::from(", env.alloc_path("string::String", "String")),
+            StrMode::CowBorrowed => format!(
+                "{}::Borrowed(",
+                env.alloc_path("borrow::Cow", "std::borrow::Cow")
+            ),
+        }
+    }
+
+    /// Whether the emitted value requires allocation, i.e. can't be used in `core`-only environment.
+    pub(crate) fn allocates(self) -> bool {
+        matches!(
+            self,
+            StrMode::IntoString | StrMode::StringFrom | StrMode::CowBorrowed
+        )
+    }
+
+    pub(crate) fn close(self) -> &'static str {
+        match self {
+            StrMode::IntoString => ".into()",
+            StrMode::Borrowed => "",
+            StrMode::StringFrom | StrMode::CowBorrowed => ")",
+            StrMode::Parse => ".parse().unwrap()",
+        }
+    }
+}
+
+/// Constructor call, which is emitted instead of the struct literal, with the field values as its arguments.
+///
+/// This is necessary for the types which are serialized as structs with private fields, like
+/// [`Duration`][std::time::Duration]. The constructor must accept the fields in the order they are serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constructor {
+    /// The code before the arguments, e.g. `std::time::Duration::new(`.
+    pub open: String,
+    /// The code after the arguments, e.g. `)`.
+    pub close: String,
+    /// Whether the call can be used in constant context.
+    pub is_const: bool,
+}
+
+impl Constructor {
+    /// Creates the constructor, which can't be used in constant context.
+    pub fn new(open: impl Into<String>, close: impl Into<String>) -> Self {
+        Self {
+            open: open.into(),
+            close: close.into(),
+            is_const: false,
+        }
+    }
+
+    /// Constructors for the standard library types, keyed by the names they are serialized with.
+    pub(crate) fn builtins() -> impl Iterator<Item = (&'static str, Constructor)> {
+        [
+            (
+                "Duration",
+                Constructor {
+                    is_const: true,
+                    ..Constructor::new("core::time::Duration::new(", ")")
+                },
+            ),
+            (
+                "SystemTime",
+                Constructor::new(
+                    "std::time::SystemTime::UNIX_EPOCH + std::time::Duration::new(",
+                    ")",
+                ),
+            ),
+            (
+                "RangeInclusive",
+                Constructor {
+                    is_const: true,
+                    ..Constructor::new("core::ops::RangeInclusive::new(", ")")
+                },
+            ),
+        ]
+        .into_iter()
+    }
+}
+
+/// Representation of the enum serialized with the tag; see [`Uneval::with_tagged_enum`][crate::ser::Uneval::with_tagged_enum].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumTag {
+    /// Internally tagged enum, i.e. `#[serde(tag = "...")]`, with the name of the tag field.
+    ///
+    /// Newtype variants are serialized as the inner struct with the tag field added first, so the name of the enum
+    /// is lost; they are recognized by the tag field and wrapped into the variant found there, as long as only
+    /// one of the internally tagged enums uses this tag. Note that any other struct whose first field is named
+    /// as the tag is taken for such variant, too. Newtype variants holding maps are not supported.
+    Internal { tag: String },
+    /// Adjacently tagged enum, i.e. `#[serde(tag = "...", content = "...")]`, with the names of the tag
+    /// and content fields.
+    ///
+    /// Newtype variants holding a tuple, or a struct with the same name as the variant, are indistinguishable
+    /// from the tuple or struct variants, so they are emitted as the latter, unless marked with
+    /// [`Uneval::with_newtype_variant`][crate::ser::Uneval::with_newtype_variant].
+    Adjacent { tag: String, content: String },
+}
+
+impl EnumTag {
+    pub(crate) fn tag(&self) -> &str {
+        match self {
+            EnumTag::Internal { tag } | EnumTag::Adjacent { tag, .. } => tag,
+        }
+    }
+}
+
+/// The environment where the generated code will be used.
+///
+/// This affects only the code emitted by the serializer itself; the names provided in options
+/// (e.g. [`MapMode::FromArray`] or [`Constructor`]) are used as is. Statics generated by
+/// [`to_static`][crate::funcs::to_static] with the default [`StaticKind::Lazy`] always require `std`;
+/// use [`StaticKind::Plain`] for the `core`-only environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Env {
+    /// The usual environment with `std`.
+    ///
+    /// This is the default.
+    #[default]
+    Std,
+    /// `#![no_std]` crate with `extern crate alloc;`: the types from `alloc` are referred to by the full paths,
+    /// e.g. `::alloc::vec!` or `::alloc::string::String`.
+    Alloc,
+    /// `#![no_std]` crate without allocation: sequences, maps and strings emitted as owned values are rejected
+    /// with [`RequiresAlloc`][crate::error::UnevalError::RequiresAlloc] error. Strings must be emitted as
+    /// [`Borrowed`][StrMode::Borrowed] ones, and collections - as arrays or static sets and maps.
+    Core,
+}
+
+impl Env {
+    /// The path to the item from `alloc` crate, or its name in the `std` prelude.
+    fn alloc_path(self, path: &str, prelude: &str) -> String {
+        match self {
+            Env::Std => prelude.into(),
+            Env::Alloc | Env::Core => format!("::alloc::{}", path),
+        }
+    }
+}
+
+/// The edition of the crate where the generated code will be used.
+///
+/// This only decides which names are keywords, and so must be written as raw identifiers, e.g. `r#async`;
+/// since the raw identifiers are valid in every edition, the code generated for the later one compiles in the earlier
+/// ones, too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[non_exhaustive]
+pub enum Edition {
+    Rust2015,
+    /// Adds the `async`, `await`, `dyn` and `try` keywords.
+    Rust2018,
+    Rust2021,
+    /// Adds the `gen` keyword.
+    ///
+    /// This is the default, as the latest edition.
+    #[default]
+    Rust2024,
+}
+
+/// Compression of the sidecar files; see [`to_file_compressed`][crate::funcs::to_file_compressed].
+#[cfg(feature = "compress")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// Raw deflate stream, decompressed with `flate2::read::DeflateDecoder`.
+    ///
+    /// This is the default.
+    #[default]
+    Deflate,
+    /// Gzip file, decompressed with `flate2::read::GzDecoder`. Compared to [`Deflate`][Codec::Deflate],
+    /// the sidecars can be inspected with the usual tools, at the cost of the slightly larger size.
+    Gzip,
+}
+
+#[cfg(feature = "compress")]
+impl Codec {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Codec::Deflate => "deflate",
+            Codec::Gzip => "gz",
+        }
+    }
+
+    /// The path to the decoder wrapping the reader, as used in the generated code.
+    pub(crate) fn decoder(self) -> &'static str {
+        match self {
+            Codec::Deflate => "flate2::read::DeflateDecoder",
+            Codec::Gzip => "flate2::read::GzDecoder",
+        }
+    }
+
+    pub(crate) fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        use flate2::{write, Compression};
+        use std::io::Write;
+
+        match self {
+            Codec::Deflate => {
+                let mut encoder = write::DeflateEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Codec::Gzip => {
+                let mut encoder = write::GzEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// The way to initialize the generated `static` item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StaticKind {
+    /// [`std::sync::LazyLock`], available since Rust 1.80.
+    #[default]
+    Lazy,
+    /// `once_cell::sync::Lazy`, for the toolchains older than Rust 1.80. The `once_cell` crate must be available
+    /// to the generated code.
+    #[cfg(feature = "once_cell")]
+    OnceCell,
+    /// Plain `static NAME: Type = ...;`, initialized with the constant expression, so that the value
+    /// is placed into the read-only data of the binary and is never constructed at runtime.
+    ///
+    /// The value is emitted in constant context, like the one of [`to_const`][crate::funcs::to_const],
+    /// with the same restrictions: e.g. sequences must be [slices][SeqMode::Slice], and strings
+    /// are `&'static str`. This works in every [`Env`], including the `core`-only one.
+    Plain,
+}
+
+impl StaticKind {
+    /// Path to the lazily-initialized wrapper, if the value is constructed at runtime.
+    pub(crate) fn path(self) -> Option<&'static str> {
+        match self {
+            StaticKind::Lazy => Some("std::sync::LazyLock"),
+            #[cfg(feature = "once_cell")]
+            StaticKind::OnceCell => Some("once_cell::sync::Lazy"),
+            StaticKind::Plain => None,
+        }
+    }
+}
+
+/// Maximal size of the chunk file; see [`to_files_chunked`][crate::funcs::to_files_chunked].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkSize {
+    /// Number of elements (or map entries) in every chunk.
+    Elements(usize),
+    /// Length of the generated code in every chunk, in bytes.
+    ///
+    /// The elements are never split, so the chunk with a single element larger than this can be larger, too.
+    Bytes(usize),
+}
+
+/// Options of the generated `static` item; see [`to_static`][crate::funcs::to_static].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticOpts<'a> {
+    /// Name of the item.
+    pub name: &'a str,
+    /// Type of the value, as it should be written in the generated code, e.g. `crate::Table`.
+    pub ty: &'a str,
+    /// The way to initialize the item.
+    pub kind: StaticKind,
+    /// Visibility of the item, e.g. `pub(crate)`; empty for the private one.
+    pub visibility: &'a str,
+    /// Name of the function returning `&'static` reference to the value, e.g. `data`.
+    ///
+    /// If set, the item is the function `fn data() -> &'static Type` instead, with the `static` itself
+    /// hidden inside it, and the visibility is applied to the function.
+    pub accessor: Option<&'a str>,
+    /// Attributes of the item, each one written in full, e.g. `#[allow(clippy::unreadable_literal)]`.
+    ///
+    /// They are written after the ones from the [`Header`], if any; for the accessor, they are applied
+    /// to the function.
+    pub attributes: &'a [&'a str],
+}
+
+impl<'a> StaticOpts<'a> {
+    /// Creates the options for the public item, initialized with [`LazyLock`][std::sync::LazyLock].
+    pub fn new(name: &'a str, ty: &'a str) -> Self {
+        Self {
+            name,
+            ty,
+            kind: StaticKind::default(),
+            visibility: "pub",
+            accessor: None,
+            attributes: &[],
+        }
+    }
+}
+
+/// Options of the generated `const` item; see [`Module::add_const_with`][crate::Module::add_const_with].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstOpts<'a> {
+    /// Name of the item.
+    pub name: &'a str,
+    /// Type of the value, as it should be written in the generated code, e.g. `crate::Limits`.
+    pub ty: &'a str,
+    /// Visibility of the item, e.g. `pub(crate)`; empty for the private one.
+    pub visibility: &'a str,
+    /// Attributes of the item, e.g. `#[cfg(feature = "full-data")]`; see [`StaticOpts::attributes`].
+    pub attributes: &'a [&'a str],
+}
+
+impl<'a> ConstOpts<'a> {
+    /// Creates the options for the public item without attributes.
+    pub fn new(name: &'a str, ty: &'a str) -> Self {
+        Self {
+            name,
+            ty,
+            visibility: "pub",
+            attributes: &[],
+        }
+    }
+}
+
+/// Header of the generated file; see [`Uneval::with_header`][crate::ser::Uneval::with_header].
+///
+/// By default, this is the comment `// @generated by uneval x.y.z -- do not edit` before the code,
+/// and the attributes `#[allow(clippy::all, unused_mut)]` and `#[rustfmt::skip]` on every generated item.
+/// Attributes are added only to the items, i.e. `const` or `static` ones and the ones in [`Module`][crate::Module],
+/// and to the binding in the typed expressions (see [`write_typed`][crate::funcs::write_typed]),
+/// since the bare expressions can't have them.
+///
+/// The header contains nothing which changes between runs, like timestamps, so the files checked into
+/// the repository don't change unless the data does (or the version of `uneval`, which is the part
+/// of default line; to avoid even this, replace the lines with [`with_lines`][Header::with_lines]):
+/// ```
+/// use uneval::config::Header;
+///
+/// let header = Header::new()
+///     .with_lines(["@generated by uneval -- do not edit"])
+///     .with_source("data/config.json");
+/// let mut out = Vec::new();
+/// uneval::write_const(1u8, "ONE", "u8", uneval::ser::Uneval::new(&mut out).with_header(header))?;
+/// assert!(String::from_utf8(out).unwrap().starts_with(
+///     "// @generated by uneval -- do not edit\n// source: data/config.json\n#[allow(clippy::all, unused_mut)]"
+/// ));
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    lines: Vec<String>,
+    attributes: Vec<String>,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Header {
+    /// Creates the default header.
+    pub fn new() -> Self {
+        Self {
+            lines: vec![format!(
+                "@generated by uneval {} -- do not edit",
+                env!("CARGO_PKG_VERSION")
+            )],
+            attributes: vec![
+                "#[allow(clippy::all, unused_mut)]".into(),
+                "#[rustfmt::skip]".into(),
+            ],
+        }
+    }
+
+    /// Adds the description of the data the file was generated from, e.g. the path to the source file.
+    pub fn with_source(self, source: impl std::fmt::Display) -> Self {
+        self.with_line(format!("source: {}", source))
+    }
+
+    /// Adds the arbitrary line to the comment, e.g. the hash of the source data.
+    pub fn with_line(mut self, line: impl Into<String>) -> Self {
+        self.lines.push(line.into());
+        self
+    }
+
+    /// Replaces the lines of the comment, including the default one.
+    pub fn with_lines(mut self, lines: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.lines = lines.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replaces the attributes added to the generated items; each one must be written in full, e.g. `#[doc(hidden)]`.
+    pub fn with_attributes(
+        mut self,
+        attributes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.attributes = attributes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The comment, with every line terminated.
+    pub(crate) fn comment(&self) -> String {
+        self.lines
+            .iter()
+            .flat_map(|line| line.lines())
+            .map(|line| format!("// {}\n", line))
+            .collect()
+    }
+
+    pub(crate) fn attributes(&self) -> &[String] {
+        &self.attributes
+    }
+}
+
+/// Global options of the serializer, as the plain value which can be stored, cloned and reused.
+///
+/// The serializer is created from it with [`Uneval::with_config`][crate::ser::Uneval::with_config], and the
+/// `_with` functions take it instead of the serializer, e.g. [`to_string_with`][crate::to_string_with]
+/// or [`to_file_with`][crate::to_file_with]. Every field corresponds to the `with_*` method of the same name;
+/// the options for the specific paths or types, like [`with_seq_mode_at`][crate::ser::Uneval::with_seq_mode_at],
+/// are set on the serializer itself.
+/// ```
+/// use uneval::config::{IntFormat, UnevalConfig};
+///
+/// let config = UnevalConfig {
+///     int_format: IntFormat::Hex,
+///     into: true,
+///     ..UnevalConfig::default()
+/// };
+/// assert_eq!(uneval::to_string_with(vec![255u8], &config)?, "vec![0xFFu8.into()].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnevalConfig {
+    /// The way to emit tuples and arrays; see [`Uneval::with_tuple_mode`][crate::ser::Uneval::with_tuple_mode].
+    pub tuple_mode: TupleMode,
+    /// The way to emit sequences; see [`Uneval::with_seq_mode`][crate::ser::Uneval::with_seq_mode].
+    pub seq_mode: SeqMode,
+    /// The way to emit maps; see [`Uneval::with_map_mode`][crate::ser::Uneval::with_map_mode].
+    pub map_mode: MapMode,
+    /// The way to emit strings; see [`Uneval::with_str_mode`][crate::ser::Uneval::with_str_mode].
+    pub str_mode: StrMode,
+    /// The way to write integers; see [`Uneval::with_int_format`][crate::ser::Uneval::with_int_format].
+    pub int_format: IntFormat,
+    /// Paths to the structs and enums by their names; see [`Uneval::with_type_path`][crate::ser::Uneval::with_type_path].
+    pub type_paths: std::collections::HashMap<String, String>,
+    /// Trait used by the tuple conversion runtime; see [`Uneval::with_tuple_trait`][crate::ser::Uneval::with_tuple_trait].
+    pub tuple_trait: Option<String>,
+    /// Whether every value is converted with `.into()`; see [`Uneval::with_into`][crate::ser::Uneval::with_into].
+    pub into: bool,
+    /// Whether the map entries are sorted; see [`Uneval::with_sorted_maps`][crate::ser::Uneval::with_sorted_maps].
+    pub sorted_maps: bool,
+    /// Whether the empty sequences and maps are emitted as `Default::default()`;
+    /// see [`Uneval::with_empty_as_default`][crate::ser::Uneval::with_empty_as_default].
+    pub empty_as_default: bool,
+    /// Whether the skipped struct fields are filled from `Default`;
+    /// see [`Uneval::with_fill_defaults`][crate::ser::Uneval::with_fill_defaults].
+    pub fill_defaults: bool,
+    /// Whether the values are serialized in the human-readable form;
+    /// see [`Uneval::with_human_readable`][crate::ser::Uneval::with_human_readable].
+    pub human_readable: bool,
+    /// Whether the value must be the constant expression;
+    /// see [`Uneval::with_const_context`][crate::ser::Uneval::with_const_context].
+    pub const_context: bool,
+    /// The environment of the generated code; see [`Uneval::with_env`][crate::ser::Uneval::with_env].
+    pub env: Env,
+    /// The edition of the generated code; see [`Uneval::with_edition`][crate::ser::Uneval::with_edition].
+    pub edition: Edition,
+    /// The oldest Rust 1.x which must compile the generated code, if any;
+    /// see [`Uneval::with_min_rust_version`][crate::ser::Uneval::with_min_rust_version].
+    pub min_rust_version: Option<u32>,
+    /// The indentation of the pretty-printed code, if it is pretty-printed;
+    /// see [`Uneval::with_indent`][crate::ser::Uneval::with_indent].
+    pub indent: Option<String>,
+    /// The header of the generated code, if any; see [`Uneval::with_header`][crate::ser::Uneval::with_header].
+    pub header: Option<Header>,
+}
+
+impl Default for UnevalConfig {
+    /// The configuration of the serializer created with [`Uneval::new`][crate::ser::Uneval::new].
+    fn default() -> Self {
+        Self {
+            tuple_mode: TupleMode::default(),
+            seq_mode: SeqMode::default(),
+            map_mode: MapMode::default(),
+            str_mode: StrMode::default(),
+            int_format: IntFormat::default(),
+            type_paths: std::collections::HashMap::new(),
+            tuple_trait: None,
+            into: false,
+            sorted_maps: false,
+            empty_as_default: true,
+            fill_defaults: false,
+            human_readable: true,
+            const_context: false,
+            env: Env::default(),
+            edition: Edition::default(),
+            min_rust_version: None,
+            indent: None,
+            header: None,
+        }
+    }
+}
+
+/// Binary format of the values embedded as blobs; see [`Uneval::with_fallback_at`][crate::ser::Uneval::with_fallback_at].
+///
+/// The generated code deserializes the blob with the same crate, so it must be the (runtime) dependency
+/// of the crate using the generated code, together with `serde`.
+#[cfg(any(feature = "postcard", feature = "bincode"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackCodec {
+    /// [`postcard`](https://docs.rs/postcard), decoded with `postcard::from_bytes`.
+    #[cfg(feature = "postcard")]
+    Postcard,
+    /// [`bincode`](https://docs.rs/bincode) 2 with the standard configuration, decoded with
+    /// `bincode::serde::decode_from_slice`; requires the `serde` feature of `bincode`.
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+#[cfg(any(feature = "postcard", feature = "bincode"))]
+impl FallbackCodec {
+    pub(crate) fn encode<T: ?Sized + serde::Serialize>(
+        self,
+        value: &T,
+    ) -> Result<Vec<u8>, crate::error::UnevalError> {
+        let failed = |err: &dyn std::fmt::Display| crate::error::UnevalError::Custom {
+            message: format!("failed to encode the fallback blob: {}", err),
+            path: String::new(),
+        };
+        match self {
+            #[cfg(feature = "postcard")]
+            FallbackCodec::Postcard => postcard::to_allocvec(value).map_err(|err| failed(&err)),
+            #[cfg(feature = "bincode")]
+            FallbackCodec::Bincode => {
+                bincode::serde::encode_to_vec(value, bincode::config::standard())
+                    .map_err(|err| failed(&err))
+            }
+        }
+    }
+
+    /// The expression deserializing the value from the given bytes, as used in the generated code.
+    pub(crate) fn decode(self, bytes: &str) -> String {
+        match self {
+            #[cfg(feature = "postcard")]
+            FallbackCodec::Postcard => format!("::postcard::from_bytes({}).unwrap()", bytes),
+            #[cfg(feature = "bincode")]
+            FallbackCodec::Bincode => format!(
+                "::bincode::serde::decode_from_slice({}, ::bincode::config::standard()).unwrap().0",
+                bytes
+            ),
+        }
+    }
+}
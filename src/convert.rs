@@ -0,0 +1,196 @@
+//! The tuple conversion trait, for the types which are serialized as tuples; see [`FromTuple`].
+
+/// Conversion from the tuple of serialized values, used by the tuple conversion runtime.
+///
+/// By default, the runtime defines its own copy of this trait, which is implemented only for arrays and tuples
+/// (see the [crate-level documentation](crate#tuples-and-arrays)). With
+/// [`Uneval::with_tuple_trait`][crate::ser::Uneval::with_tuple_trait], the runtime is bound by this trait
+/// instead, so that any type implementing it can be emitted from a tuple - e.g. the vector types of math libraries,
+/// which are serialized as tuples (or, with [`with_tuple_struct_as_tuple`][crate::ser::Uneval::with_tuple_struct_as_tuple],
+/// as tuple structs). The generated code then refers to `uneval`, so it must be a regular dependency,
+/// not only the build one; this module is available without the default `serialize` feature, so that dependency
+/// can be declared with `default-features = false`, leaving `serde` and the serializer to the build one.
+///
+/// This trait is the extension point, and its signature won't change in the compatible releases. Besides arrays
+/// (of up to 32 elements) and tuples (of up to 16 elements), it is implemented for some third-party types:
+/// - with `glam` feature, for the `glam` vectors and quaternions, e.g. `glam::Vec3` from `(f32, f32, f32)`;
+/// - with `mint` feature, for the `mint` vectors and points;
+/// - with `nalgebra` feature, for the `nalgebra` vectors and points of up to 4 elements.
+///
+/// ```
+/// use uneval::convert::FromTuple;
+///
+/// struct Rgb(u8, u8, u8);
+///
+/// impl FromTuple<(u8, u8, u8)> for Rgb {
+///     fn from_tuple((r, g, b): (u8, u8, u8)) -> Self {
+///         Rgb(r, g, b)
+///     }
+/// }
+/// ```
+pub trait FromTuple<T>: Sized {
+    /// Creates the value from the tuple.
+    fn from_tuple(tuple: T) -> Self;
+}
+
+/// Expands to the second argument, to repeat it once for every element of the first one.
+macro_rules! replace {
+    ($_from:tt, $to:tt) => {
+        $to
+    };
+}
+
+macro_rules! impl_arrays {
+    ($($len:literal: ($($index:tt)+))+) => {$(
+        impl<T> FromTuple<($(replace!($index, T),)+)> for [T; $len] {
+            #[inline]
+            fn from_tuple(tuple: ($(replace!($index, T),)+)) -> Self {
+                [$(tuple.$index),+]
+            }
+        }
+    )+};
+}
+
+macro_rules! impl_tuples {
+    ($(($($ty:ident)+))+) => {$(
+        impl<$($ty),+> FromTuple<($($ty,)+)> for ($($ty,)+) {
+            #[inline]
+            fn from_tuple(tuple: ($($ty,)+)) -> Self {
+                tuple
+            }
+        }
+    )+};
+}
+
+impl_arrays! {
+    1: (0)
+    2: (0 1)
+    3: (0 1 2)
+    4: (0 1 2 3)
+    5: (0 1 2 3 4)
+    6: (0 1 2 3 4 5)
+    7: (0 1 2 3 4 5 6)
+    8: (0 1 2 3 4 5 6 7)
+    9: (0 1 2 3 4 5 6 7 8)
+    10: (0 1 2 3 4 5 6 7 8 9)
+    11: (0 1 2 3 4 5 6 7 8 9 10)
+    12: (0 1 2 3 4 5 6 7 8 9 10 11)
+    13: (0 1 2 3 4 5 6 7 8 9 10 11 12)
+    14: (0 1 2 3 4 5 6 7 8 9 10 11 12 13)
+    15: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14)
+    16: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15)
+    17: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16)
+    18: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17)
+    19: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18)
+    20: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19)
+    21: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20)
+    22: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21)
+    23: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22)
+    24: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23)
+    25: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24)
+    26: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25)
+    27: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26)
+    28: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27)
+    29: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28)
+    30: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29)
+    31: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30)
+    32: (0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31)
+}
+
+impl_tuples! {
+    (T0)
+    (T0 T1)
+    (T0 T1 T2)
+    (T0 T1 T2 T3)
+    (T0 T1 T2 T3 T4)
+    (T0 T1 T2 T3 T4 T5)
+    (T0 T1 T2 T3 T4 T5 T6)
+    (T0 T1 T2 T3 T4 T5 T6 T7)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14)
+    (T0 T1 T2 T3 T4 T5 T6 T7 T8 T9 T10 T11 T12 T13 T14 T15)
+}
+
+/// Implements the conversion for the types with the constructor taking every element separately.
+#[cfg(feature = "glam")]
+macro_rules! impl_new {
+    ($($ty:ty => $elem:ty, $ctor:ident ($($index:tt)+);)+) => {$(
+        impl FromTuple<($(replace!($index, $elem),)+)> for $ty {
+            #[inline]
+            fn from_tuple(tuple: ($(replace!($index, $elem),)+)) -> Self {
+                <$ty>::$ctor($(tuple.$index),+)
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "glam")]
+impl_new! {
+    glam::Vec2 => f32, new(0 1);
+    glam::Vec3 => f32, new(0 1 2);
+    glam::Vec3A => f32, new(0 1 2);
+    glam::Vec4 => f32, new(0 1 2 3);
+    glam::Quat => f32, from_xyzw(0 1 2 3);
+    glam::DVec2 => f64, new(0 1);
+    glam::DVec3 => f64, new(0 1 2);
+    glam::DVec4 => f64, new(0 1 2 3);
+    glam::DQuat => f64, from_xyzw(0 1 2 3);
+    glam::IVec2 => i32, new(0 1);
+    glam::IVec3 => i32, new(0 1 2);
+    glam::IVec4 => i32, new(0 1 2 3);
+    glam::UVec2 => u32, new(0 1);
+    glam::UVec3 => u32, new(0 1 2);
+    glam::UVec4 => u32, new(0 1 2 3);
+}
+
+/// Implements the conversion for the generic types which can be created from the array.
+#[cfg(feature = "mint")]
+macro_rules! impl_from_array {
+    ($($ty:ident ($($index:tt)+);)+) => {$(
+        impl<T> FromTuple<($(replace!($index, T),)+)> for mint::$ty<T> {
+            #[inline]
+            fn from_tuple(tuple: ($(replace!($index, T),)+)) -> Self {
+                [$(tuple.$index),+].into()
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "mint")]
+impl_from_array! {
+    Vector2(0 1);
+    Vector3(0 1 2);
+    Vector4(0 1 2 3);
+    Point2(0 1);
+    Point3(0 1 2);
+}
+
+/// Implements the conversion for the generic types with the constructor taking every element separately.
+#[cfg(feature = "nalgebra")]
+macro_rules! impl_scalar_new {
+    ($($ty:ident ($($index:tt)+);)+) => {$(
+        impl<T: nalgebra::Scalar> FromTuple<($(replace!($index, T),)+)> for nalgebra::$ty<T> {
+            #[inline]
+            fn from_tuple(tuple: ($(replace!($index, T),)+)) -> Self {
+                Self::new($(tuple.$index),+)
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "nalgebra")]
+impl_scalar_new! {
+    Vector1(0);
+    Vector2(0 1);
+    Vector3(0 1 2);
+    Vector4(0 1 2 3);
+    Point1(0);
+    Point2(0 1);
+    Point3(0 1 2);
+    Point4(0 1 2 3);
+}
@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+
+/// Byte marking the reference to the hoisted value in the captured code.
+///
+/// It can't appear in the generated code otherwise, since the control characters in strings
+/// and chars are always escaped.
+const MARKER: u8 = 0x01;
+
+/// Deduplication of the repeated values and flattening of the deeply nested ones.
+///
+/// Every struct or enum variant, which code is long enough, is replaced by the reference to the binding,
+/// named after the hash of its code and its [shape][crate::path::Path::shape] (so that the result doesn't depend
+/// on the serialization order, which matters for sorted maps). The same code is shared only between the values
+/// of the same shape, since the code of generic types, like `Wrapper { items: Default::default() }`, might be
+/// the same for the different type parameters. When the outermost value is complete, the bindings referenced only once
+/// are inlined back, and the rest are emitted as closures in the block wrapping the value.
+///
+/// With flattening, the values at every `flatten`-th nesting level are hoisted, too, and are kept even if
+/// referenced only once, as the plain `let` bindings.
+pub(crate) struct Dedup {
+    threshold: usize,
+    flatten: Option<usize>,
+    bindings: HashMap<u64, Vec<u8>>,
+    shapes: HashMap<u64, String>,
+    forced: HashSet<u64>,
+    frames: Vec<bool>,
+}
+
+/// Code of the deduplicated value, split into the bindings and the final expression.
+pub(crate) struct Hoisted {
+    pub(crate) bindings: Vec<Vec<u8>>,
+    pub(crate) value: Vec<u8>,
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self {
+            threshold: usize::MAX,
+            flatten: None,
+            bindings: HashMap::new(),
+            shapes: HashMap::new(),
+            forced: HashSet::new(),
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl Dedup {
+    /// Sets the minimal length of code to be deduplicated.
+    pub(crate) fn set_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold;
+    }
+
+    /// Sets the nesting depth of the values to be always hoisted.
+    pub(crate) fn set_flatten(&mut self, depth: usize) {
+        self.flatten = Some(depth.max(1));
+    }
+
+    /// Registers the start of the compound value and returns whether its code should be captured.
+    ///
+    /// The outermost value is always captured, to be processed when it is complete; other values
+    /// are captured only if they could be hoisted.
+    pub(crate) fn begin(&mut self, hoistable: bool) -> bool {
+        let capture = self.frames.is_empty() || hoistable;
+        self.frames.push(capture);
+        capture
+    }
+
+    /// Registers the end of the compound value and returns whether its code was captured.
+    pub(crate) fn end(&mut self) -> bool {
+        self.frames.pop().unwrap_or(false)
+    }
+
+    /// Whether the last completed value was the outermost one.
+    pub(crate) fn is_outermost(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Replaces the code of the value of the given shape at the given depth with the reference to the binding,
+    /// if it is long enough or nested deep enough.
+    pub(crate) fn hoist(&mut self, code: Vec<u8>, shape: String, depth: usize) -> Vec<u8> {
+        let forced = self.flatten.is_some_and(|flatten| depth % flatten == 0);
+        if code.len() < self.threshold && !forced {
+            return code;
+        }
+        let hash = fnv1a(&[&code, &[0], shape.as_bytes()]);
+        match self.bindings.get(&hash) {
+            // Hash collision; very unlikely, but the code must stay correct anyway.
+            Some(existing) if *existing != code || self.shapes[&hash] != shape => return code,
+            Some(_) => {}
+            None => {
+                self.bindings.insert(hash, code);
+                self.shapes.insert(hash, shape);
+            }
+        }
+        if forced {
+            self.forced.insert(hash);
+        }
+        let mut reference = vec![MARKER];
+        reference.extend_from_slice(format!("{:016x}", hash).as_bytes());
+        reference.push(MARKER);
+        reference
+    }
+
+    /// Resolves all the references in the code of the outermost value.
+    ///
+    /// Bindings are numbered in the order of their first use, and each of them is placed after the ones
+    /// it refers to.
+    pub(crate) fn finish(&mut self, code: Vec<u8>) -> Hoisted {
+        let mut uses = HashMap::new();
+        count_uses(&code, &self.bindings, &mut uses);
+        let mut order = Vec::new();
+        self.collect_kept(&code, &uses, &mut HashSet::new(), &mut order);
+        // Bindings used more than once are emitted as closures, the rest - as plain values.
+        let names: HashMap<u64, String> = order
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| match uses[hash] {
+                1 => (*hash, format!("__u{}", index)),
+                _ => (*hash, format!("__u{}()", index)),
+            })
+            .collect();
+
+        let mut bindings = Vec::new();
+        for (index, hash) in order.iter().enumerate() {
+            let mut binding = match uses[hash] {
+                1 => format!("let __u{} = ", index),
+                _ => format!("let __u{} = || ", index),
+            }
+            .into_bytes();
+            self.render(&self.bindings[hash], &names, &mut binding);
+            binding.push(b';');
+            bindings.push(binding);
+        }
+        let mut value = Vec::new();
+        self.render(&code, &names, &mut value);
+        self.bindings.clear();
+        self.shapes.clear();
+        self.forced.clear();
+        Hoisted { bindings, value }
+    }
+
+    /// Lists the bindings which are used more than once or are forced, dependencies first.
+    fn collect_kept(
+        &self,
+        code: &[u8],
+        uses: &HashMap<u64, usize>,
+        visited: &mut HashSet<u64>,
+        order: &mut Vec<u64>,
+    ) {
+        for hash in references(code) {
+            if !visited.insert(hash) {
+                continue;
+            }
+            self.collect_kept(&self.bindings[&hash], uses, visited, order);
+            if uses[&hash] > 1 || self.forced.contains(&hash) {
+                order.push(hash);
+            }
+        }
+    }
+
+    fn render(&self, code: &[u8], names: &HashMap<u64, String>, out: &mut Vec<u8>) {
+        let mut rest = code;
+        while let Some(start) = rest.iter().position(|&byte| byte == MARKER) {
+            out.extend_from_slice(&rest[..start]);
+            let hash = parse_reference(&rest[start + 1..]);
+            match names.get(&hash) {
+                Some(name) => out.extend_from_slice(name.as_bytes()),
+                None => self.render(&self.bindings[&hash], names, out),
+            }
+            rest = &rest[start + 18..];
+        }
+        out.extend_from_slice(rest);
+    }
+}
+
+fn count_uses(code: &[u8], bindings: &HashMap<u64, Vec<u8>>, uses: &mut HashMap<u64, usize>) {
+    for hash in references(code) {
+        let count = uses.entry(hash).or_insert(0);
+        *count += 1;
+        // The binding's own references are counted only once, no matter how many times it is used.
+        if *count == 1 {
+            count_uses(&bindings[&hash], bindings, uses);
+        }
+    }
+}
+
+fn references(code: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    let mut rest = code;
+    std::iter::from_fn(move || {
+        let start = rest.iter().position(|&byte| byte == MARKER)?;
+        let hash = parse_reference(&rest[start + 1..]);
+        rest = &rest[start + 18..];
+        Some(hash)
+    })
+}
+
+/// Parses the hash from the reference, which starts right after the opening marker.
+fn parse_reference(code: &[u8]) -> u64 {
+    let hex = std::str::from_utf8(&code[..16]).expect("reference is always ASCII");
+    u64::from_str_radix(hex, 16).expect("reference is always valid hex")
+}
+
+/// FNV-1a hash of the concatenated parts, which, unlike the `std` one, is guaranteed to be stable.
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
@@ -0,0 +1,25 @@
+//! Problems found by the serializer; see [`check`][crate::check] and
+//! [`Uneval::with_diagnostics`][crate::ser::Uneval::with_diagnostics].
+
+/// How bad the problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The generated code might not compile, depending on the types it is used with.
+    Note,
+    /// The generated code compiles, but probably isn't what was intended, e.g. it changes between builds.
+    Warning,
+    /// The generated code won't compile, or the value can't be serialized at all.
+    Error,
+}
+
+/// Single problem found in the value, with the path to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    /// How bad the problem is.
+    pub severity: Severity,
+    /// Description of the problem, with the way to fix it, if there is one.
+    pub message: String,
+    /// Path to the offending value, in the same notation as the paths in [`UnevalError`][crate::error::UnevalError].
+    pub path: String,
+}
@@ -0,0 +1,99 @@
+//! Incremental emitters for sequences and maps.
+//!
+//! These are useful when the collection is too large to be materialized in memory before serialization,
+//! e.g. when it is produced by iterating over some huge source file.
+
+use crate::error::UnevalError;
+use crate::ser::{SerResult, Uneval};
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::Serialize;
+use std::io::Write;
+
+/// Emitter for the sequence, which elements are provided one by one.
+///
+/// The generated code has the same shape as the one for the serialized [`Vec`]. Since the emitter writes
+/// into the provided writer and gives it back on [`finish`][SeqEmitter::finish], the sequence can be surrounded
+/// by any handwritten code:
+/// ```
+/// use std::io::Write;
+///
+/// let mut out = Vec::new();
+/// write!(out, "Wrapper(").unwrap();
+/// let mut emitter = uneval::SeqEmitter::new(&mut out)?;
+/// for i in 0..3u8 {
+///     emitter.push(&i)?;
+/// }
+/// write!(emitter.finish()?, ")").unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     "Wrapper(vec![0u8,1u8,2u8].into_iter().collect())"
+/// );
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub struct SeqEmitter<W: Write> {
+    uneval: Uneval<W>,
+}
+
+impl<W: Write> SeqEmitter<W> {
+    /// Starts the sequence, writing its opening code to the target.
+    pub fn new(target: W) -> Result<Self, UnevalError> {
+        Self::with_serializer(Uneval::new(target))
+    }
+
+    /// Starts the sequence, using the pre-configured serializer.
+    ///
+    /// The sequence is treated as the top-level value, i.e. the options set for paths are matched
+    /// against the root path. Note that the options which require post-processing of the whole sequence,
+    /// like [sorting][Uneval::with_sorted_seq_at] or [deduplication][Uneval::with_dedup], make the emitter
+    /// keep the generated code in memory until the sequence is finished.
+    pub fn with_serializer(mut uneval: Uneval<W>) -> Result<Self, UnevalError> {
+        uneval.serialize_seq(None)?;
+        Ok(Self { uneval })
+    }
+
+    /// Serializes the next element of the sequence.
+    pub fn push(&mut self, value: &impl Serialize) -> SerResult {
+        (&mut self.uneval).serialize_element(value)
+    }
+
+    /// Finishes the sequence and returns the target writer.
+    pub fn finish(mut self) -> Result<W, UnevalError> {
+        SerializeSeq::end(&mut self.uneval)?;
+        Ok(self.uneval.into_writer())
+    }
+}
+
+/// Emitter for the map, which entries are provided one by one.
+///
+/// The generated code has the same shape as the one for the serialized [`HashMap`][std::collections::HashMap],
+/// except that the entries are never sorted - they're written in the order they are provided.
+/// See [`SeqEmitter`] for details.
+pub struct MapEmitter<W: Write> {
+    uneval: Uneval<W>,
+}
+
+impl<W: Write> MapEmitter<W> {
+    /// Starts the map, writing its opening code to the target.
+    pub fn new(target: W) -> Result<Self, UnevalError> {
+        Self::with_serializer(Uneval::new(target))
+    }
+
+    /// Starts the map, using the pre-configured serializer.
+    ///
+    /// See [`SeqEmitter::with_serializer`] for details.
+    pub fn with_serializer(mut uneval: Uneval<W>) -> Result<Self, UnevalError> {
+        uneval.start_unsorted_map()?;
+        Ok(Self { uneval })
+    }
+
+    /// Serializes the next entry of the map.
+    pub fn entry(&mut self, key: &impl Serialize, value: &impl Serialize) -> SerResult {
+        (&mut self.uneval).serialize_entry(key, value)
+    }
+
+    /// Finishes the map and returns the target writer.
+    pub fn finish(mut self) -> Result<W, UnevalError> {
+        SerializeMap::end(&mut self.uneval)?;
+        Ok(self.uneval.into_writer())
+    }
+}
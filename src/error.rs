@@ -1,16 +1,151 @@
 //! Error returned by Uneval serializer.
 
+use crate::path::Path;
 use serde::ser;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum UnevalError {
-    #[error("IO error while writing code: {0}")]
-    Io(#[from] std::io::Error),
+    #[error("IO error while writing code{}: {source}", location(.path))]
+    Io {
+        source: std::io::Error,
+        path: String,
+    },
+    #[error("Formatter error while writing code{}", location(.path))]
+    Fmt {
+        source: std::fmt::Error,
+        path: String,
+    },
     #[error("Serialization process yielded invalid UTF-8 sequence: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
-    #[error("Unknown error: {0}")]
-    Custom(String),
+    #[error("`{name}` is not a valid Rust identifier (used as {context}){}", location(.path))]
+    InvalidIdentifier {
+        name: String,
+        context: IdentContext,
+        path: String,
+    },
+    #[error("{what}{} can't be emitted in constant context", location(.path))]
+    NotConst { what: &'static str, path: String },
+    #[error(
+        "{what}{} requires allocation, which is not available in `core`-only environment",
+        location(.path)
+    )]
+    RequiresAlloc { what: &'static str, path: String },
+    #[error(
+        "{what}{} requires `std`, which is not available in `no_std` environment",
+        location(.path)
+    )]
+    RequiresStd { what: &'static str, path: String },
+    #[error("{what}{} requires Rust 1.{required}, but the generated code must compile on Rust 1.{target}", location(.path))]
+    Unavailable {
+        what: &'static str,
+        required: u32,
+        target: u32,
+        path: String,
+    },
+    #[error("value of the tagged enum `{name}` at `{path}` doesn't match its representation: expected the tag field with the variant name first, followed by the contents")]
+    InvalidTag { name: &'static str, path: String },
+    #[error("struct{} starts with the field `{tag}`, which is the tag of several internally tagged enums, so the enum of its newtype variant is unknown", location(.path))]
+    AmbiguousTag { tag: &'static str, path: String },
+    #[error("{what}{} can't be emitted as a literal, which is required for keys of static maps and elements of static sets", location(.path))]
+    NotLiteral { what: &'static str, path: String },
+    /// The lexer error is stored as its message, since it can't be sent between threads.
+    #[cfg(feature = "proc-macro")]
+    #[error("Generated code can't be parsed as tokens: {message}")]
+    Parse { message: String },
+    #[cfg(feature = "validate")]
+    #[error("Generated code is not a valid Rust expression: {message}, near `{snippet}`")]
+    InvalidSyntax { message: String, snippet: String },
+    #[error("rustfmt failed to format the generated code: {message}")]
+    Rustfmt { message: String },
+    #[error("{message}{}", location(.path))]
+    Strict { message: String, path: String },
+    #[error("Region `{name}` can't be updated: {reason}")]
+    InvalidRegion { name: String, reason: &'static str },
+    #[error("Unknown error{}: {message}", location(.path))]
+    Custom { message: String, path: String },
+}
+
+impl UnevalError {
+    /// Sets the path to the failing element, unless it is already known.
+    ///
+    /// Since the serializer doesn't leave the nested values on error, its path still points to the failing element
+    /// when the error is propagated, so the path can be attached at any level.
+    pub(crate) fn at(mut self, current: &Path) -> Self {
+        match &mut self {
+            Self::Io { path, .. }
+            | Self::Fmt { path, .. }
+            | Self::InvalidIdentifier { path, .. }
+            | Self::RequiresStd { path, .. }
+            | Self::Custom { path, .. }
+                if path.is_empty() =>
+            {
+                *path = current.to_string();
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+impl From<std::io::Error> for UnevalError {
+    fn from(source: std::io::Error) -> Self {
+        // Formatter errors are passed through `io::Write` by `FmtWriter`.
+        if let Some(source) = source
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<std::fmt::Error>())
+        {
+            return Self::Fmt {
+                source: *source,
+                path: String::new(),
+            };
+        }
+        Self::Io {
+            source,
+            path: String::new(),
+        }
+    }
+}
+
+#[cfg(feature = "proc-macro")]
+impl From<proc_macro2::LexError> for UnevalError {
+    fn from(err: proc_macro2::LexError) -> Self {
+        Self::Parse {
+            message: err.to_string(),
+        }
+    }
+}
+
+fn location(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" at `{}`", path)
+    }
+}
+
+/// The place where the invalid identifier was encountered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentContext {
+    /// Name of the struct or enum.
+    TypeName,
+    /// Name of the enum variant.
+    VariantName,
+    /// Name of the struct field (or struct variant field).
+    FieldName,
+    /// Name of the generated item, e.g. constant.
+    ItemName,
+}
+
+impl std::fmt::Display for IdentContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::TypeName => "type name",
+            Self::VariantName => "variant name",
+            Self::FieldName => "field name",
+            Self::ItemName => "item name",
+        })
+    }
 }
 
 impl ser::Error for UnevalError {
@@ -18,6 +153,9 @@ impl ser::Error for UnevalError {
     where
         T: std::fmt::Display,
     {
-        Self::Custom(msg.to_string())
+        Self::Custom {
+            message: msg.to_string(),
+            path: String::new(),
+        }
     }
 }
@@ -0,0 +1,148 @@
+//! Layout of the generated code; see [`Formatter`].
+
+use std::io::{self, Write};
+
+/// Hooks deciding the whitespace and punctuation between the pieces of the generated code,
+/// similar to `serde_json`'s `Formatter`.
+///
+/// The serializer calls them at the fixed points of every struct, sequence, tuple and map it writes, and the code
+/// between these points is always the same; so any formatter yields the code with the same meaning. Every method
+/// has the default implementation producing the [compact][CompactFormatter] output, so the custom formatter
+/// overrides only the hooks it cares about. The formatter is set with
+/// [`Uneval::with_formatter`][crate::ser::Uneval::with_formatter]:
+/// ```
+/// # use serde::Serialize;
+/// use std::io::{self, Write};
+/// use uneval::format::Formatter;
+///
+/// /// Separates the entries with spaces, keeping everything on one line.
+/// struct Spaced;
+///
+/// impl Formatter for Spaced {
+///     fn begin_entry(&mut self, writer: &mut dyn Write, first: bool, _depth: usize) -> io::Result<()> {
+///         if first { Ok(()) } else { write!(writer, ", ") }
+///     }
+/// }
+///
+/// let mut out = Vec::new();
+/// vec![1u8, 2].serialize(&mut uneval::ser::Uneval::new(&mut out).with_formatter(Spaced))?;
+/// assert_eq!(String::from_utf8(out).unwrap(), "vec![1u8, 2u8].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub trait Formatter {
+    /// Whether the output is split into lines.
+    ///
+    /// The multiline formatters get the inline tuple conversion runtime split into lines too, using [`newline`][Self::newline].
+    fn is_multiline(&self) -> bool {
+        false
+    }
+
+    /// Starts the new line with the given nesting depth; the compact output has no line breaks.
+    fn newline(&mut self, writer: &mut dyn Write, depth: usize) -> io::Result<()> {
+        let _ = (writer, depth);
+        Ok(())
+    }
+
+    /// Writes what precedes the entry of the struct, sequence, tuple or map at the given depth,
+    /// i.e. the separator from the previous entry, if it's not the `first` one.
+    fn begin_entry(&mut self, writer: &mut dyn Write, first: bool, depth: usize) -> io::Result<()> {
+        if !first {
+            write!(writer, ",")?;
+        }
+        self.newline(writer, depth)
+    }
+
+    /// Writes what follows the last entry of the non-empty container, before its closing bracket;
+    /// `depth` is the one of the container itself.
+    fn end_entries(&mut self, writer: &mut dyn Write, depth: usize) -> io::Result<()> {
+        let _ = (writer, depth);
+        Ok(())
+    }
+
+    /// Writes the separator between the field name and its value.
+    fn begin_field_value(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, ": ")
+    }
+
+    /// Writes the separator between the key and the value of the map entry, which is emitted as tuple.
+    fn begin_map_value(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, ",")
+    }
+}
+
+/// The default formatter, writing everything on a single line without extra whitespace.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompactFormatter;
+
+impl Formatter for CompactFormatter {}
+
+/// The formatter putting every entry of every non-empty container on its own line, with the trailing comma,
+/// indented with the given string according to its nesting depth.
+///
+/// This is what [`Uneval::with_indent`][crate::ser::Uneval::with_indent] sets; the other options are set
+/// with [`Uneval::with_formatter`][crate::ser::Uneval::with_formatter]:
+/// ```
+/// # use serde::Serialize;
+/// use uneval::format::PrettyFormatter;
+/// use uneval::ser::Uneval;
+///
+/// let formatter = PrettyFormatter::new("\t").with_trailing_comma(false);
+/// let mut out = Vec::new();
+/// vec![1u8, 2].serialize(&mut Uneval::new(&mut out).with_formatter(formatter))?;
+/// assert_eq!(String::from_utf8(out).unwrap(), "vec![\n\t1u8,\n\t2u8\n].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrettyFormatter {
+    indent: String,
+    trailing_comma: bool,
+}
+
+impl PrettyFormatter {
+    /// Creates the formatter with the given indentation string.
+    pub fn new(indent: impl Into<String>) -> Self {
+        Self {
+            indent: indent.into(),
+            trailing_comma: true,
+        }
+    }
+
+    /// Sets whether the last entry of the container is followed by the comma, as `rustfmt` does;
+    /// it is by default.
+    pub fn with_trailing_comma(mut self, trailing_comma: bool) -> Self {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+}
+
+impl Default for PrettyFormatter {
+    /// Indents with four spaces, like `rustfmt`.
+    fn default() -> Self {
+        Self::new("    ")
+    }
+}
+
+impl Formatter for PrettyFormatter {
+    fn is_multiline(&self) -> bool {
+        true
+    }
+
+    fn newline(&mut self, writer: &mut dyn Write, depth: usize) -> io::Result<()> {
+        writeln!(writer)?;
+        for _ in 0..depth {
+            write!(writer, "{}", self.indent)?;
+        }
+        Ok(())
+    }
+
+    fn end_entries(&mut self, writer: &mut dyn Write, depth: usize) -> io::Result<()> {
+        if self.trailing_comma {
+            write!(writer, ",")?;
+        }
+        self.newline(writer, depth)
+    }
+
+    fn begin_map_value(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, ", ")
+    }
+}
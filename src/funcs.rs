@@ -1,14 +1,168 @@
 //! Convenience functions to be used with Uneval.
 
+use crate::config::{ConstOpts, StaticKind, StaticOpts, UnevalConfig};
 use crate::error::UnevalError;
 use crate::ser::{SerResult, Uneval};
+use crate::sidecar::Sidecars;
 use serde::Serialize;
+use std::io::{BufRead, Write};
 
 /// Write generated Rust code to the provided [`Write`][std::io::Write] implementation.
+///
+/// The code is written in many small pieces, so the target which isn't buffered by itself, like [`File`][std::fs::File],
+/// should be wrapped into [`BufWriter`][std::io::BufWriter], as [`to_file`] does. Don't forget to flush it afterwards,
+/// since the errors occurring on drop are ignored.
 pub fn write(value: impl Serialize, target: impl std::io::Write) -> SerResult {
     value.serialize(&mut Uneval::new(target))
 }
 
+/// Write generated Rust code to the provided [`fmt::Write`][std::fmt::Write] implementation, e.g. to the `String`.
+///
+/// ```
+/// let mut out = String::from("let value: Vec<u8> = ");
+/// uneval::write_fmt(vec![1u8, 2], &mut out)?;
+/// assert_eq!(out, "let value: Vec<u8> = vec![1u8,2u8].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn write_fmt(value: impl Serialize, target: impl std::fmt::Write) -> SerResult {
+    value.serialize(&mut Uneval::from_fmt(target))
+}
+
+/// Write generated Rust code to the provided [`Write`] implementation, returning the statistics
+/// of the serialized value; see [`Report`][crate::Report].
+///
+/// The report includes the [diagnostics][Uneval::with_diagnostics], too.
+pub fn write_with_report(
+    value: impl Serialize,
+    target: impl std::io::Write,
+) -> Result<crate::Report, UnevalError> {
+    let mut uneval = Uneval::new(target).with_diagnostics();
+    value.serialize(&mut uneval)?;
+    Ok(uneval.report())
+}
+
+/// Writes generated Rust code to file, returning the statistics of the serialized value; see [`to_file`]
+/// and [`Report`][crate::Report].
+///
+/// ```no_run
+/// # let value = ();
+/// let report = uneval::to_out_dir_with_report(value, "data.rs")?;
+/// println!("cargo:warning=data.rs: {} bytes, nested {} levels deep", report.bytes, report.max_depth);
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_file_with_report(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+) -> Result<crate::Report, UnevalError> {
+    let mut report = None;
+    write_atomically(target.as_ref(), |writer| {
+        report = Some(write_with_report(value, writer)?);
+        Ok(())
+    })?;
+    Ok(report.expect("the report is set when the file is written"))
+}
+
+/// Convenience wrapper around [`to_file_with_report`], similar to [`to_out_dir`].
+pub fn to_out_dir_with_report(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+) -> Result<crate::Report, UnevalError> {
+    to_file_with_report(value, out_dir_path(file_name)?)
+}
+
+/// Collect the statistics of the value, without writing the generated code anywhere.
+///
+/// This is useful to decide whether the value should be embedded as code at all, or it's too large
+/// and is better deserialized at runtime. See [`Report`][crate::Report] for details.
+///
+/// ```
+/// let stats = uneval::stats(&vec!["first", "second"])?;
+/// assert_eq!((stats.seq_elements, stats.strings, stats.payload_bytes), (2, 2, 11));
+/// assert_eq!(stats.bytes, uneval::to_string(&vec!["first", "second"])?.len());
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn stats(value: impl Serialize) -> Result<crate::Report, UnevalError> {
+    write_with_report(value, std::io::sink())
+}
+
+/// Check the value for the known problems with the generated code, without writing it anywhere.
+///
+/// This runs the serializer with the default configuration, collecting the problems it can spot: floats which
+/// are NaN or infinite, names which aren't valid identifiers, unit structs which might be the empty tuple structs,
+/// and so on - see [`Diagnostic`][crate::Diagnostic]. If the value can't be serialized at all, the error
+/// is the last diagnostic. The empty list means that no problems were found, not that the code will compile -
+/// e.g. the types of the fields can't be checked.
+///
+/// ```
+/// let diagnostics = uneval::check(&(1.0f32, f32::NAN));
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].severity, uneval::Severity::Error);
+/// assert_eq!(diagnostics[0].path, ".1");
+/// ```
+pub fn check(value: &impl Serialize) -> Vec<crate::Diagnostic> {
+    check_with(value, Uneval::new(std::io::sink()))
+}
+
+/// Check the value for the known problems with the generated code, using the pre-configured serializer.
+///
+/// Some problems depend on the configuration, e.g. unit structs are not reported when they are
+/// [marked as tuple structs][Uneval::with_empty_tuple_struct]. See [`check`] for details.
+pub fn check_with(value: &impl Serialize, uneval: Uneval<std::io::Sink>) -> Vec<crate::Diagnostic> {
+    let mut uneval = uneval.with_dry_run();
+    let result = value.serialize(&mut uneval);
+    let mut diagnostics = uneval.take_diagnostics();
+    if let Err(err) = result {
+        diagnostics.push(crate::Diagnostic {
+            severity: crate::Severity::Error,
+            message: err.to_string(),
+            path: uneval.current_path(),
+        });
+    }
+    diagnostics
+}
+
+/// Write Rust code for the data provided by the [`Deserializer`][serde::Deserializer], without the typed value.
+///
+/// The data is forwarded from deserializer to serializer as is, so the generated code follows the
+/// [data model](https://serde.rs/data-model.html) of the input format. For self-describing formats like JSON,
+/// this means that there are no structs and enums: objects become maps with string keys, arrays become
+/// sequences, and numbers get the types chosen by the format - in case of JSON, `u64`, `i64` or `f64`.
+/// So the target type should be built from these pieces, e.g. `HashMap<String, Vec<f64>>`. Note that
+/// `null` becomes `()`, and other values are not wrapped in `Some`, so optional values can't be represented.
+/// Formats which do carry the type names, like RON, can produce structs and enums as well.
+///
+/// Errors returned by the deserializer are reported as [`UnevalError::Custom`].
+///
+/// ```
+/// let json = r#"{"primes": [2, 3, 5], "empty": []}"#;
+/// let mut out = Vec::new();
+/// uneval::transcode(&mut serde_json::Deserializer::from_str(json), &mut out)?;
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     r#"vec![("primes".into(),vec![2u64,3u64,5u64].into_iter().collect()),("empty".into(),Default::default())].into_iter().collect()"#
+/// );
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+#[cfg(feature = "transcode")]
+pub fn transcode<'de>(
+    deserializer: impl serde::Deserializer<'de>,
+    target: impl Write,
+) -> SerResult {
+    transcode_with(deserializer, Uneval::new(target))
+}
+
+/// Write Rust code for the data provided by the [`Deserializer`][serde::Deserializer], using the
+/// pre-configured serializer.
+///
+/// See [`transcode`] for details.
+#[cfg(feature = "transcode")]
+pub fn transcode_with<'de, W: Write>(
+    deserializer: impl serde::Deserializer<'de>,
+    mut uneval: Uneval<W>,
+) -> SerResult {
+    serde_transcode::transcode(deserializer, &mut uneval)
+}
+
 /// Writes generated Rust code to file.
 ///
 /// This is probably the most common way to use `uneval`. When Cargo runs your crate's build task,
@@ -30,15 +184,445 @@ pub fn write(value: impl Serialize, target: impl std::io::Write) -> SerResult {
 /// ```
 ///
 /// [include]: https://doc.rust-lang.org/stable/std/macro.include.html
+///
+/// The code is written to the temporary file in the same directory first, which is then renamed to the target.
+/// So, if the serialization fails or the build script is killed midway, the target file is either left
+/// intact or not created at all, and never contains the partially-written code.
+///
+/// Missing parent directories are created, e.g. `gen/data` for `gen/data/config.rs`. This holds for every function
+/// writing the files, not only for this one.
 pub fn to_file(value: impl Serialize, target: impl AsRef<std::path::Path>) -> SerResult {
-    value.serialize(&mut Uneval::new(std::fs::File::create(target)?))
+    write_atomically(target.as_ref(), |writer| {
+        value.serialize(&mut Uneval::new(writer))
+    })
 }
 
 /// Convenience wrapper around [`to_file`].
 ///
 /// This function finds out where the output directory is by looking at `OUT_DIR` environment variable
-/// and creates the file with the provided name there.
-pub fn to_out_dir(value: impl Serialize, file_name: impl AsRef<str>) -> SerResult {
+/// and creates the file with the provided name there, returning its full path. The name might be nested,
+/// e.g. `data/tables.rs`, in which case the intermediate directories are created, too.
+///
+/// The file can then be included with [`include_out_dir!`][crate::include_out_dir!], using the same name:
+/// ```ignore
+/// let value: Tables = uneval::include_out_dir!("data/tables.rs");
+/// ```
+pub fn to_out_dir(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file(value, &path)?;
+    Ok(path)
+}
+
+/// Writes generated Rust code to file, using the given configuration.
+///
+/// See [`to_file`] for details. The code is buffered in memory before being written, since the serializer
+/// can't write into the temporary file directly.
+/// ```no_run
+/// use uneval::config::{IntFormat, UnevalConfig};
+///
+/// let config = UnevalConfig { int_format: IntFormat::Hex, ..UnevalConfig::default() };
+/// uneval::to_file_with(vec![0xFFu8], "masks.rs", &config)?;
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_file_with(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    config: &UnevalConfig,
+) -> SerResult {
+    let mut uneval = Uneval::with_config(Vec::new(), config);
+    value.serialize(&mut uneval)?;
+    let code = uneval.into_writer();
+    write_atomically(target.as_ref(), |writer| Ok(writer.write_all(&code)?))
+}
+
+/// Convenience wrapper around [`to_file_with`], similar to [`to_out_dir`].
+pub fn to_out_dir_with(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    config: &UnevalConfig,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_with(value, &path, config)?;
+    Ok(path)
+}
+
+/// Writes generated Rust code for the top-level sequence or map into several files, to reduce the memory
+/// usage of the compiler.
+///
+/// The elements (or the map entries) are grouped into chunks of the given size, and every chunk is written as
+/// the `vec!` into the file `{base_name}_{index}.rs` in the `dir`. The main file `{base_name}.rs` in the same
+/// directory joins them with `include!`, and should be included as usual:
+/// ```ignore
+/// let value: Vec<Item> = include!(concat!(env!(OUT_DIR), "/items.rs"));
+/// ```
+/// The value can be collected into anything implementing [`FromIterator`] for the elements, e.g. any sequence
+/// or map, but not into the array or tuple. Returns the number of chunk files, which is at least one.
+///
+/// Every element is serialized by its own instance of [`Uneval`] with the default options. Map entries
+/// are sorted by their code before being split, like the ones of the maps inside the elements; for the
+/// top-level sets, use [`to_files_chunked_sorted`]. The chunk files left from the previous runs, which had
+/// more chunks, are removed. The main file iterates over the array of chunks by value, which requires
+/// the 2021 edition or later.
+pub fn to_files_chunked(
+    value: impl Serialize,
+    dir: impl AsRef<std::path::Path>,
+    base_name: &str,
+    size: crate::config::ChunkSize,
+) -> Result<usize, UnevalError> {
+    let mut splitter = crate::chunks::Splitter::new(dir.as_ref().into(), base_name, size);
+    value.serialize(&mut splitter)
+}
+
+/// Writes generated Rust code for the top-level sequence into several files, sorting the elements first;
+/// see [`to_files_chunked`].
+///
+/// This is for the sequences which order doesn't matter and might be random, like `HashSet`, so that
+/// the same value always yields the same files.
+pub fn to_files_chunked_sorted(
+    value: impl Serialize,
+    dir: impl AsRef<std::path::Path>,
+    base_name: &str,
+    size: crate::config::ChunkSize,
+) -> Result<usize, UnevalError> {
+    let mut splitter =
+        crate::chunks::Splitter::new(dir.as_ref().into(), base_name, size).with_sorted_seq();
+    value.serialize(&mut splitter)
+}
+
+/// Convenience wrapper around [`to_files_chunked`], writing the files into the `OUT_DIR`.
+pub fn to_out_dir_chunked(
+    value: impl Serialize,
+    base_name: &str,
+    size: crate::config::ChunkSize,
+) -> Result<usize, UnevalError> {
+    to_files_chunked(value, out_dir_path("")?, base_name, size)
+}
+
+/// Writes generated Rust code to file, but only if it differs from the code already there.
+///
+/// Since the file is not touched when nothing has changed, its modification time is preserved,
+/// and Cargo won't rebuild the crates depending on it. Returns `true` if the file was (re)written.
+///
+/// The code is generated in memory first; the existing file, however, is never loaded as a whole,
+/// only compared chunk by chunk.
+pub fn to_file_cached(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+) -> Result<bool, UnevalError> {
+    to_file_cached_with(value, target, Uneval::new(Vec::new()))
+}
+
+/// Convenience wrapper around [`to_file_cached`], similar to [`to_out_dir`].
+pub fn to_out_dir_cached(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+) -> Result<bool, UnevalError> {
+    to_file_cached(value, out_dir_path(file_name)?)
+}
+
+/// Writes generated Rust code to file, if it differs from the code already there, using the pre-configured
+/// serializer; see [`to_file_cached`].
+pub fn to_file_cached_with(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    mut uneval: Uneval<Vec<u8>>,
+) -> Result<bool, UnevalError> {
+    value.serialize(&mut uneval)?;
+    let out = uneval.into_writer();
+    if same_contents(target.as_ref(), &out)? {
+        return Ok(false);
+    }
+    write_atomically(target.as_ref(), |writer| Ok(writer.write_all(&out)?))?;
+    Ok(true)
+}
+
+/// Convenience wrapper around [`to_file_cached_with`], similar to [`to_out_dir`].
+pub fn to_out_dir_cached_with(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    uneval: Uneval<Vec<u8>>,
+) -> Result<bool, UnevalError> {
+    to_file_cached_with(value, out_dir_path(file_name)?, uneval)
+}
+
+/// Replaces the region of the existing source file with the generated Rust code.
+///
+/// This is intended for the generated code checked into the repository, where the build script can't be used.
+/// The region is delimited by the marker comments on their own lines, e.g. for the region named `table`:
+/// ```ignore
+/// pub fn table() -> Vec<u32> {
+///     // uneval:begin table
+///     vec![1u32,2u32].into_iter().collect()
+///     // uneval:end table
+/// }
+/// ```
+/// The closing marker can also omit the name, i.e. be just `// uneval:end`; in this case, it is the first such
+/// marker after the opening one.
+/// Everything between the markers is replaced with the generated expression on its own line, indented like the
+/// opening marker; the trailing comma or semicolon of the old contents, if any, is kept. The rest of the file
+/// is left intact, and the generated code gets the same line endings as the opening marker.
+/// Like with [`to_file_cached`], the file is not touched if the region is already up to date;
+/// returns `true` if it was rewritten.
+///
+/// Missing, duplicated or misordered markers are reported as [`UnevalError::InvalidRegion`].
+pub fn to_file_region(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    region: &str,
+) -> Result<bool, UnevalError> {
+    let target = target.as_ref();
+    let source = std::fs::read_to_string(target)?;
+    let code = to_string(value)?;
+    let updated = replace_region(&source, region, &code)?;
+    if updated == source {
+        return Ok(false);
+    }
+    write_atomically(target, |writer| Ok(writer.write_all(updated.as_bytes())?))?;
+    Ok(true)
+}
+
+fn replace_region(source: &str, name: &str, code: &str) -> Result<String, UnevalError> {
+    let error = |reason| UnevalError::InvalidRegion {
+        name: name.into(),
+        reason,
+    };
+    let begin_marker = format!("// uneval:begin {}", name);
+    let end_marker = format!("// uneval:end {}", name);
+    // Both markers are recorded as the byte range of the whole line, including the line ending.
+    let mut begin = None;
+    let mut end = None;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let range = offset..offset + line.len();
+        offset += line.len();
+        let marker = if line.trim() == begin_marker {
+            &mut begin
+        } else if line.trim() == end_marker {
+            &mut end
+        } else if line.trim() == "// uneval:end" && begin.is_some() && end.is_none() {
+            // Unnamed end markers before the region and after it belong to other regions.
+            &mut end
+        } else {
+            continue;
+        };
+        if marker.replace(range).is_some() {
+            return Err(error("the marker is duplicated"));
+        }
+    }
+    let begin = begin.ok_or_else(|| error("the begin marker is missing"))?;
+    let end = end.ok_or_else(|| error("the end marker is missing"))?;
+    if end.start < begin.end {
+        return Err(error("the end marker precedes the begin marker"));
+    }
+
+    let marker_line = &source[begin.clone()];
+    let indent = &marker_line[..marker_line.len() - marker_line.trim_start().len()];
+    let newline = if marker_line.ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let old = source[begin.end..end.start].trim_end();
+    let terminator = match old.chars().last() {
+        Some(last @ (',' | ';')) => last.to_string(),
+        _ => String::new(),
+    };
+    Ok(format!(
+        "{}{}{}{}{}{}",
+        &source[..begin.end],
+        indent,
+        code.replace('\n', newline),
+        terminator,
+        newline,
+        &source[end.start..]
+    ))
+}
+
+/// Writes generated Rust code to file, moving the large strings and byte sequences into separate files.
+///
+/// Every string or byte sequence (e.g. `Vec<u8>`) of at least `threshold` bytes is written into the file
+/// next to the target, named after it and the hash of the contents - e.g. `data.rs.3c1f8e0a9b2d4c57.bin`
+/// or `data.rs.9e07d1a2b4c6f813.txt` for `data.rs` - and the generated
+/// code refers to it via [`include_bytes!`] or [`include_str!`]. This keeps the generated code small, so that
+/// compiling it doesn't take too much time and memory. The paths are relative to the generated file, so the
+/// sidecars must be kept together with it.
+///
+/// Since the names depend only on the contents, the same value always yields the same files, even if it
+/// contains e.g. `HashMap` with random iteration order, and the ones which didn't change are not rewritten. Sidecars left from the previous runs which are
+/// not needed anymore are removed.
+pub fn to_file_with_sidecars(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    threshold: usize,
+) -> SerResult {
+    let target = target.as_ref();
+    write_with_sidecars(value, target, Sidecars::new(target, threshold)?)
+}
+
+/// Writes generated Rust code to file with sidecars, using the pre-configured serializer.
+///
+/// See [`to_file_with_sidecars`] for details. This allows, in particular, to move the numeric sequences into
+/// binary sidecars with [`Uneval::with_numeric_sidecar_at`]. The code is buffered in memory before being written.
+pub fn to_file_with_sidecars_with(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    threshold: usize,
+    uneval: Uneval<Vec<u8>>,
+) -> SerResult {
+    let target = target.as_ref();
+    write_with_sidecars_with(value, target, Sidecars::new(target, threshold)?, uneval)
+}
+
+fn write_with_sidecars_with(
+    value: impl Serialize,
+    target: &std::path::Path,
+    sidecars: Sidecars,
+    uneval: Uneval<Vec<u8>>,
+) -> SerResult {
+    let mut uneval = uneval.with_sidecars(sidecars);
+    value.serialize(&mut uneval)?;
+    let sidecars = uneval.take_sidecars();
+    let code = uneval.into_writer();
+    write_atomically(target, |writer| Ok(writer.write_all(&code)?))?;
+    match sidecars {
+        Some(sidecars) => sidecars.cleanup(),
+        None => Ok(()),
+    }
+}
+
+/// Convenience wrapper around [`to_file_with_sidecars`], similar to [`to_out_dir`].
+pub fn to_out_dir_with_sidecars(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    threshold: usize,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_with_sidecars(value, &path, threshold)?;
+    Ok(path)
+}
+
+/// Writes generated Rust code to file, moving the large strings and byte sequences into compressed separate files.
+///
+/// This works like [`to_file_with_sidecars`], but the sidecars for the owned values, i.e. the sequences
+/// and the strings emitted as `String`, are compressed with the given codec, and the generated code decompresses
+/// them at runtime - e.g. `data.rs.3c1f8e0a9b2d4c57.bin.deflate`. This reduces the size of both the sources and the binary,
+/// for the price of decompression on every evaluation of the generated code, so it is best used with
+/// [`to_static`] and similar functions, which evaluate it only once.
+///
+/// The crate using the generated code must depend on [`flate2`](https://docs.rs/flate2). Blobs which don't
+/// get smaller when compressed, and the ones which must be borrowed (e.g. in constant context),
+/// are written to the sidecars as is.
+#[cfg(feature = "compress")]
+pub fn to_file_compressed(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    threshold: usize,
+    codec: crate::config::Codec,
+) -> SerResult {
+    let target = target.as_ref();
+    write_with_sidecars(
+        value,
+        target,
+        Sidecars::new(target, threshold)?.with_codec(codec),
+    )
+}
+
+/// Writes generated Rust code to file with compressed sidecars, using the pre-configured serializer;
+/// see [`to_file_compressed`] and [`to_file_with_sidecars_with`].
+///
+/// Since decompression requires `std`, the owned blobs can't be compressed for the other
+/// [environments][crate::config::Env]; this is reported as [`RequiresStd`][UnevalError::RequiresStd] error.
+#[cfg(feature = "compress")]
+pub fn to_file_compressed_with(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    threshold: usize,
+    codec: crate::config::Codec,
+    uneval: Uneval<Vec<u8>>,
+) -> SerResult {
+    let target = target.as_ref();
+    let sidecars = Sidecars::new(target, threshold)?.with_codec(codec);
+    write_with_sidecars_with(value, target, sidecars, uneval)
+}
+
+/// Convenience wrapper around [`to_file_compressed`], similar to [`to_out_dir`].
+#[cfg(feature = "compress")]
+pub fn to_out_dir_compressed(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    threshold: usize,
+    codec: crate::config::Codec,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_compressed(value, &path, threshold, codec)?;
+    Ok(path)
+}
+
+fn write_with_sidecars(
+    value: impl Serialize,
+    target: &std::path::Path,
+    sidecars: Sidecars,
+) -> SerResult {
+    let mut sidecars = Some(sidecars);
+    write_atomically(target, |writer| {
+        let mut uneval = Uneval::new(writer).with_sidecars(sidecars.take().unwrap());
+        value.serialize(&mut uneval)?;
+        sidecars = uneval.take_sidecars();
+        Ok(())
+    })?;
+    match sidecars {
+        Some(sidecars) => sidecars.cleanup(),
+        None => Ok(()),
+    }
+}
+
+/// Writes the file through the temporary one, replacing the target only on success.
+///
+/// Note that [`std::fs::rename`] replaces the existing file on every platform, including Windows.
+pub(crate) fn write_atomically(
+    target: &std::path::Path,
+    write: impl FnOnce(&mut std::io::BufWriter<std::fs::File>) -> SerResult,
+) -> SerResult {
+    /// Distinguishes the temporary files of the same process, if several threads write the same target.
+    static TEMP_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "no file name"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    let temp = target.with_file_name(temp_name);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let result = std::fs::File::create(&temp)
+        .map_err(UnevalError::from)
+        .and_then(|file| {
+            // The buffer is flushed explicitly, so the error occurring at this last step is reported, too.
+            let mut writer = std::io::BufWriter::new(file);
+            write(&mut writer)?;
+            writer.flush()?;
+            Ok(())
+        })
+        .and_then(|_| Ok(std::fs::rename(&temp, target)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Path to the file in the output directory, creating the intermediate directories if the name is nested,
+/// e.g. `data/tables.rs`.
+pub(crate) fn out_dir_path(file_name: impl AsRef<str>) -> Result<std::path::PathBuf, UnevalError> {
     let path: std::path::PathBuf = [
         std::env::var("OUT_DIR")
             .expect("OUT_DIR not set, check if you're running this from the build script"),
@@ -46,12 +630,631 @@ pub fn to_out_dir(value: impl Serialize, file_name: impl AsRef<str>) -> SerResul
     ]
     .iter()
     .collect();
-    value.serialize(&mut Uneval::new(std::fs::File::create(path)?))
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(path)
+}
+
+pub(crate) fn same_contents(path: &std::path::Path, expected: &[u8]) -> Result<bool, UnevalError> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+    if file.metadata()?.len() != expected.len() as u64 {
+        return Ok(false);
+    }
+    let mut reader = std::io::BufReader::new(file);
+    let mut rest = expected;
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            return Ok(rest.is_empty());
+        }
+        let len = chunk.len();
+        if len > rest.len() || chunk != &rest[..len] {
+            return Ok(false);
+        }
+        rest = &rest[len..];
+        reader.consume(len);
+    }
+}
+
+/// Obtain string with the expression annotated with the type of the value,
+/// e.g. `{ let __uneval_value: crate::Table = ...; __uneval_value }`.
+///
+/// The type can't be known to the serializer, so it must be provided by the caller, with the paths resolvable
+/// at the place the code is included. The output is still a single expression, but the annotation flows
+/// into it, so that the parts which constrain nothing (e.g. the empty collections emitted as `Default::default()`) can be inferred even when
+/// the expression is used without the annotated binding, like `include!("table.rs").rows.len()`;
+/// the type errors are reported at the annotation, too.
+///
+/// Unlike the bare expression, the binding can have attributes, so the ones from the [`Header`][crate::config::Header]
+/// (e.g. `#[rustfmt::skip]` and `#[allow(...)]`) are applied to it, when it is set with [`write_typed`].
+pub fn to_string_typed(value: impl Serialize, type_name: &str) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    write_typed(value, type_name, Uneval::new(&mut out))?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Writes the expression annotated with the type of the value to file; see [`to_string_typed`].
+pub fn to_file_typed(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    type_name: &str,
+) -> SerResult {
+    write_atomically(target.as_ref(), |writer| {
+        write_typed(value, type_name, Uneval::new(writer))
+    })
+}
+
+/// Convenience wrapper around [`to_file_typed`], similar to [`to_out_dir`].
+pub fn to_out_dir_typed(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    type_name: &str,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_typed(value, &path, type_name)?;
+    Ok(path)
+}
+
+/// Writes the expression annotated with the type of the value with the provided serializer;
+/// see [`to_string_typed`].
+pub fn write_typed<W: Write>(
+    value: impl Serialize,
+    type_name: &str,
+    mut uneval: Uneval<W>,
+) -> SerResult {
+    uneval.write_raw("{ ")?;
+    uneval.write_item_attributes(&[])?;
+    uneval.write_raw(&format!("let __uneval_value: {} = ", type_name))?;
+    value.serialize(&mut uneval)?;
+    uneval.write_raw("; __uneval_value }")
+}
+
+/// Obtain string with the `const` item holding the value, e.g. `pub const NAME: path::To::Type = ...;`.
+///
+/// Unlike the expressions generated by other functions, this item should be included at module scope.
+/// The value is emitted as a constant expression, i.e. strings are borrowed and tuples are emitted as arrays;
+/// see [`Uneval::with_const_context`] for details and restrictions. Tuples which are not arrays must be
+/// configured with [`TupleMode::Tuple`][crate::config::TupleMode::Tuple], which requires using [`write_const`]
+/// with the custom serializer.
+///
+/// Values which can't be constructed in constant context, e.g. vectors or maps, are reported
+/// as [`NotConst`][UnevalError::NotConst] error instead of generating the item which wouldn't compile:
+/// ```
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Limits {
+///     name: &'static str,
+///     max: [u16; 2],
+/// }
+///
+/// let limits = Limits { name: "default", max: [80, 25] };
+/// assert_eq!(
+///     uneval::to_const(&limits, "LIMITS", "Limits")?,
+///     "pub const LIMITS: Limits = Limits {name: \"default\",max: [80u16,25u16]};\n"
+/// );
+/// let err = uneval::to_const(vec![1u8], "BYTES", "Vec<u8>").unwrap_err();
+/// assert_eq!(err.to_string(), "sequence can't be emitted in constant context");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_const(value: impl Serialize, name: &str, type_name: &str) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    write_const(value, name, type_name, Uneval::new(&mut out))?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Writes the `const` item holding the value to file; see [`to_const`].
+pub fn to_file_const(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    name: &str,
+    type_name: &str,
+) -> SerResult {
+    write_atomically(target.as_ref(), |writer| {
+        write_const(value, name, type_name, Uneval::new(writer))
+    })
+}
+
+/// Convenience wrapper around [`to_file_const`], similar to [`to_out_dir`].
+pub fn to_out_dir_const(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    name: &str,
+    type_name: &str,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_const(value, &path, name, type_name)?;
+    Ok(path)
+}
+
+/// Writes the `const` item holding the value with the provided serializer; see [`to_const`].
+///
+/// The serializer is switched to the constant context, other options are kept as is.
+pub fn write_const<W: Write>(
+    value: impl Serialize,
+    name: &str,
+    type_name: &str,
+    mut uneval: Uneval<W>,
+) -> SerResult {
+    const_item(value, ConstOpts::new(name, type_name), &mut uneval)
+}
+
+pub(crate) fn const_item<W: Write>(
+    value: impl Serialize,
+    opts: ConstOpts<'_>,
+    uneval: &mut Uneval<W>,
+) -> SerResult {
+    let prefix = match opts.visibility {
+        "" => "const".to_string(),
+        visibility => format!("{} const", visibility),
+    };
+    uneval.write_item_start(&prefix, opts.name, opts.ty, opts.attributes)?;
+    uneval.write_item_value(opts.ty, |uneval| const_value(value, uneval))?;
+    uneval.write_item_end()
+}
+
+fn const_value<W: Write>(value: impl Serialize, uneval: &mut Uneval<W>) -> SerResult {
+    uneval.set_const_context(true);
+    let result = value.serialize(&mut *uneval);
+    uneval.set_const_context(false);
+    result
+}
+
+/// Obtain string with the lazily-initialized `static` item holding the value, e.g.
+/// `pub static DATA: std::sync::LazyLock<crate::Table> = std::sync::LazyLock::new(|| ...);`.
+///
+/// This is useful for the values which can't be constructed in constant context, like maps or vectors;
+/// the value is constructed on the first access. Like [`to_const`], this item should be included at module scope.
+///
+/// With [`accessor`][StaticOpts::accessor] set, the `static` is hidden inside the function returning
+/// `&'static` reference to the value, e.g. `pub fn data() -> &'static Table { static DATA: ...; &DATA }`.
+///
+/// With [`StaticKind::Plain`], the item is the plain `static` initialized with the constant expression instead,
+/// so that the value is stored in the binary as is:
+/// ```
+/// use uneval::config::{SeqMode, StaticKind, StaticOpts};
+/// use uneval::ser::Uneval;
+///
+/// let opts = StaticOpts { kind: StaticKind::Plain, ..StaticOpts::new("PRIMES", "&[u16]") };
+/// let mut out = Vec::new();
+/// uneval::write_static(vec![2u16, 3, 5], opts, Uneval::new(&mut out).with_seq_mode(SeqMode::Slice))?;
+/// assert_eq!(String::from_utf8(out).unwrap(), "pub static PRIMES: &[u16] = &[2u16,3u16,5u16];\n");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_static(value: impl Serialize, opts: StaticOpts<'_>) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    write_static(value, opts, Uneval::new(&mut out))?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Writes the `static` item holding the value to file; see [`to_static`].
+pub fn to_file_static(
+    value: impl Serialize,
+    target: impl AsRef<std::path::Path>,
+    opts: StaticOpts<'_>,
+) -> SerResult {
+    write_atomically(target.as_ref(), |writer| {
+        write_static(value, opts, Uneval::new(writer))
+    })
+}
+
+/// Convenience wrapper around [`to_file_static`], similar to [`to_out_dir`].
+pub fn to_out_dir_static(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+    opts: StaticOpts<'_>,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_static(value, &path, opts)?;
+    Ok(path)
+}
+
+/// Writes the `static` item holding the value with the provided serializer; see [`to_static`].
+pub fn write_static<W: Write>(
+    value: impl Serialize,
+    opts: StaticOpts<'_>,
+    mut uneval: Uneval<W>,
+) -> SerResult {
+    static_item(value, opts, &mut uneval)
+}
+
+pub(crate) fn static_item<W: Write>(
+    value: impl Serialize,
+    opts: StaticOpts<'_>,
+    uneval: &mut Uneval<W>,
+) -> SerResult {
+    if opts.kind == StaticKind::Lazy {
+        uneval.require_rust("`LazyLock`", 80)?;
+    }
+    let with_visibility = |item: &str| match opts.visibility {
+        "" => item.to_string(),
+        visibility => format!("{} {}", visibility, item),
+    };
+    let type_name = match opts.kind.path() {
+        Some(lazy) => format!("{}<{}>", lazy, opts.ty),
+        None => opts.ty.to_string(),
+    };
+    match opts.accessor {
+        Some(accessor) => {
+            let prefix = with_visibility("fn");
+            uneval.write_accessor_start(&prefix, accessor, opts.ty, opts.attributes)?;
+            uneval.write_item_head("static", opts.name, &type_name)?;
+        }
+        None => {
+            let prefix = with_visibility("static");
+            uneval.write_item_start(&prefix, opts.name, &type_name, opts.attributes)?;
+        }
+    }
+    uneval.write_item_value(opts.ty, |uneval| match opts.kind.path() {
+        Some(lazy) => {
+            uneval.write_raw(&format!("{}::new(|| ", lazy))?;
+            value.serialize(&mut *uneval)?;
+            uneval.write_raw(")")
+        }
+        None => const_value(value, uneval),
+    })?;
+    match opts.accessor {
+        Some(_) => uneval.write_accessor_end(opts.name),
+        None => uneval.write_item_end(),
+    }
 }
 
 /// Obtain string with generated Rust code.
 pub fn to_string(value: impl Serialize) -> Result<String, UnevalError> {
+    let mut out = String::new();
+    write_fmt(value, &mut out)?;
+    Ok(out)
+}
+
+/// Obtain generated Rust code as bytes, e.g. to write them somewhere else.
+///
+/// This is the same code as the one returned by [`to_string`], but without checking that it is valid UTF-8.
+pub fn to_vec(value: impl Serialize) -> Result<Vec<u8>, UnevalError> {
+    let mut out = Vec::new();
+    write(value, &mut out)?;
+    Ok(out)
+}
+
+/// Obtain string with generated Rust code, using the given configuration.
+pub fn to_string_with(value: impl Serialize, config: &UnevalConfig) -> Result<String, UnevalError> {
+    let mut uneval = Uneval::with_config(Vec::new(), config);
+    value.serialize(&mut uneval)?;
+    Ok(String::from_utf8(uneval.into_writer())?)
+}
+
+/// Obtain token stream with generated Rust code, e.g. to embed the value into the output of procedural macro.
+///
+/// Generated tokens get the [call-site][proc_macro2::Span::call_site] span. The stream is a single expression,
+/// so it can be interpolated into `quote!` as is:
+/// ```ignore
+/// let table = uneval::to_tokens(&table)?;
+/// Ok(quote! {
+///     pub fn table() -> Table {
+///         #table
+///     }
+/// })
+/// ```
+#[cfg(feature = "proc-macro")]
+pub fn to_tokens(value: impl Serialize) -> Result<proc_macro2::TokenStream, UnevalError> {
+    to_tokens_with(value, Uneval::new(Vec::new()))
+}
+
+/// Obtain token stream with generated Rust code, using the pre-configured serializer.
+///
+/// See [`to_tokens`] for details.
+#[cfg(feature = "proc-macro")]
+pub fn to_tokens_with(
+    value: impl Serialize,
+    mut uneval: Uneval<Vec<u8>>,
+) -> Result<proc_macro2::TokenStream, UnevalError> {
+    value.serialize(&mut uneval)?;
+    let code = String::from_utf8(uneval.into_writer())?;
+    Ok(code.parse()?)
+}
+
+/// Obtain string with generated Rust code, checking that it is syntactically valid.
+///
+/// See [`validate`] for details.
+#[cfg(feature = "validate")]
+pub fn to_string_checked(value: impl Serialize) -> Result<String, UnevalError> {
+    let code = to_string(value)?;
+    validate(&code)?;
+    Ok(code)
+}
+
+/// Writes generated Rust code to file, checking that it is syntactically valid.
+///
+/// The code is generated in memory first, and the file is not touched if the check fails.
+/// See [`validate`] for details.
+#[cfg(feature = "validate")]
+pub fn to_file_checked(value: impl Serialize, target: impl AsRef<std::path::Path>) -> SerResult {
+    let code = to_string(value)?;
+    validate(&code)?;
+    write_atomically(target.as_ref(), |writer| {
+        Ok(writer.write_all(code.as_bytes())?)
+    })
+}
+
+/// Checks that the generated code can be parsed as Rust expression.
+///
+/// This catches only the syntax errors, not the type errors - these will still surface only when
+/// the generated code is compiled. The returned [`InvalidSyntax`][UnevalError::InvalidSyntax] error
+/// contains the part of code around the place where parsing failed.
+#[cfg(feature = "validate")]
+pub fn validate(code: &str) -> SerResult {
+    const CONTEXT: usize = 40;
+
+    let err = match syn::parse_str::<syn::Expr>(code) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    let start = err.span().start();
+    // Errors at the end of input have no location; lines are 1-based, columns are counted in chars.
+    let offset = match start.line {
+        0 => code.len(),
+        line => code
+            .split_inclusive('\n')
+            .enumerate()
+            .find(|&(index, _)| index + 1 == line)
+            .map_or(code.len(), |(_, text)| {
+                let line_start = text.as_ptr() as usize - code.as_ptr() as usize;
+                line_start
+                    + text
+                        .chars()
+                        .take(start.column)
+                        .map(char::len_utf8)
+                        .sum::<usize>()
+            }),
+    };
+    let snippet_start = (0..=offset.saturating_sub(CONTEXT))
+        .rev()
+        .find(|&index| code.is_char_boundary(index))
+        .unwrap_or(0);
+    let snippet_end = (offset + CONTEXT..code.len())
+        .find(|&index| code.is_char_boundary(index))
+        .unwrap_or(code.len());
+    Err(UnevalError::InvalidSyntax {
+        message: err.to_string(),
+        snippet: code[snippet_start..snippet_end].to_string(),
+    })
+}
+
+/// Reformats the generated Rust expression with [`prettyplease`](https://docs.rs/prettyplease),
+/// the way `rustfmt` would, but without requiring the `rustfmt` binary.
+///
+/// The code which can't be parsed is reported in the same way as by [`validate`]. Note that the comments
+/// are dropped, since they are not a part of the syntax tree, and that the contents of the macro calls
+/// are formatted only partially - e.g. the structs inside `vec!` are kept on one line.
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Point {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// let code = uneval::to_string(Point { x: 1.0, y: 2.0 })?;
+/// assert_eq!(code, "Point {x: 1f32,y: 2f32}");
+/// assert_eq!(uneval::format_expr(&code)?, "Point { x: 1f32, y: 2f32 }");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+#[cfg(feature = "prettyplease")]
+pub fn format_expr(code: &str) -> Result<String, UnevalError> {
+    validate(code)?;
+    // Only the items can be formatted, so the expression is put into the function and then taken back.
+    let file = syn::parse_str::<syn::File>(&wrap_in_fn(code))
+        .expect("the valid expression is the valid function body");
+    Ok(unwrap_fn_body(&prettyplease::unparse(&file))
+        .expect("the function is formatted with the body on separate lines"))
+}
+
+/// Writes generated Rust code to file, reformatted with [`format_expr`].
+///
+/// The code is generated in memory first, and the file is not touched if it can't be parsed.
+#[cfg(feature = "prettyplease")]
+pub fn to_file_formatted(value: impl Serialize, target: impl AsRef<std::path::Path>) -> SerResult {
+    let code = format_expr(&to_string(value)?)?;
+    write_atomically(target.as_ref(), |writer| {
+        Ok(writer.write_all(code.as_bytes())?)
+    })
+}
+
+/// Like [`to_out_dir`], but the code is reformatted with [`format_expr`], so that the generated files
+/// are readable and diffable.
+#[cfg(feature = "prettyplease")]
+pub fn to_out_dir_formatted(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_formatted(value, &path)?;
+    Ok(path)
+}
+
+/// Reformats the generated Rust expression with the `rustfmt` binary, as `rustfmt --edition 2021` would.
+///
+/// The binary is taken from the `RUSTFMT` environment variable, if it is set, or found in `PATH` otherwise.
+/// If it can't be found, the code is returned as is, so that the build doesn't fail just because `rustfmt`
+/// is not installed; if it rejects the code, the error is reported as [`UnevalError::Rustfmt`].
+///
+/// ```no_run
+/// let code = uneval::to_string(vec![1u8, 2u8])?;
+/// assert_eq!(uneval::rustfmt(&code)?, "vec![1u8, 2u8].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn rustfmt(code: &str) -> Result<String, UnevalError> {
+    use std::process::{Command, Stdio};
+
+    let binary = std::env::var_os("RUSTFMT").unwrap_or_else(|| "rustfmt".into());
+    let child = Command::new(binary)
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(code.to_string()),
+        Err(err) => return Err(err.into()),
+    };
+    // Like `format_expr`, rustfmt formats only the items, so the expression is wrapped into the function.
+    // The input is written from the separate thread, so that neither side blocks on the full pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = wrap_in_fn(code);
+    let output = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output();
+        // If rustfmt exits early, writing fails with the broken pipe, and its own error is more useful.
+        let _ = writer.join();
+        output
+    })?;
+    if !output.status.success() {
+        return Err(UnevalError::Rustfmt {
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    unwrap_fn_body(&String::from_utf8(output.stdout)?).ok_or_else(|| UnevalError::Rustfmt {
+        message: "the output is not the formatted function".into(),
+    })
+}
+
+/// Writes generated Rust code to file, reformatted with [`rustfmt`].
+///
+/// The code is generated in memory first, and the file is not touched if `rustfmt` rejects it.
+/// If `rustfmt` is not installed, the code is written unformatted.
+pub fn to_file_rustfmt(value: impl Serialize, target: impl AsRef<std::path::Path>) -> SerResult {
+    let code = rustfmt(&to_string(value)?)?;
+    write_atomically(target.as_ref(), |writer| {
+        Ok(writer.write_all(code.as_bytes())?)
+    })
+}
+
+/// Convenience wrapper around [`to_file_rustfmt`], similar to [`to_out_dir`].
+pub fn to_out_dir_rustfmt(
+    value: impl Serialize,
+    file_name: impl AsRef<str>,
+) -> Result<std::path::PathBuf, UnevalError> {
+    let path = out_dir_path(file_name)?;
+    to_file_rustfmt(value, &path)?;
+    Ok(path)
+}
+
+/// Wraps the expression into the function, so that it can be passed to the formatter.
+fn wrap_in_fn(code: &str) -> String {
+    format!("fn __uneval() {{\n{}\n}}\n", code)
+}
+
+/// Takes the expression back from the formatted function, see [`wrap_in_fn`].
+fn unwrap_fn_body(formatted: &str) -> Option<String> {
+    /// The indentation of the function body, which holds the expression.
+    const INDENT: &str = "    ";
+
+    let body = formatted
+        .trim_end()
+        .strip_prefix("fn __uneval() {\n")?
+        .strip_suffix('}')?;
+    let mut out = String::with_capacity(body.len());
+    for line in body.lines() {
+        out.push_str(line.strip_prefix(INDENT).unwrap_or(line));
+        out.push('\n');
+    }
+    // The expression is written without the trailing newline, like the unformatted one.
+    out.truncate(out.trim_end().len());
+    Some(out)
+}
+
+/// Write generated Rust code for the sequence of values, serializing its elements in parallel.
+///
+/// If there are at least `threshold` values, they are split into chunks, which are serialized into separate buffers
+/// on the [`rayon`](https://docs.rs/rayon) thread pool and then written in order. The result is exactly the same
+/// as the one of [`write()`], i.e. `vec![...].into_iter().collect()`, but is obtained much faster for large sequences
+/// of complex values. Shorter sequences are serialized as usual.
+///
+/// Every element is serialized by its own instance of [`Uneval`] with the default options, so the buffers for all
+/// chunks are kept in memory until they are written.
+#[cfg(feature = "rayon")]
+pub fn write_par<T: Serialize + Sync>(
+    values: &[T],
+    mut target: impl Write,
+    threshold: usize,
+) -> SerResult {
+    use rayon::prelude::*;
+
+    if values.is_empty() || values.len() < threshold {
+        return write(values, target);
+    }
+    // Several chunks per thread, so that the threads which got the simpler values don't stay idle.
+    let chunk_len = values.len().div_ceil(rayon::current_num_threads() * 4);
+    let chunks = values
+        .par_chunks(chunk_len)
+        .enumerate()
+        .map(|(chunk_index, chunk)| {
+            let mut out = Vec::new();
+            for (index, value) in chunk.iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                Uneval::new(&mut out)
+                    .serialize_element_at(chunk_index * chunk_len + index, value)?;
+            }
+            Ok(out)
+        })
+        .collect::<Result<Vec<_>, UnevalError>>()?;
+    let mode = crate::config::SeqMode::default();
+    write!(target, "{}", mode.open(crate::config::Env::default()))?;
+    for (index, chunk) in chunks.iter().enumerate() {
+        if index > 0 {
+            target.write_all(b",")?;
+        }
+        target.write_all(chunk)?;
+    }
+    write!(target, "{}", mode.close())?;
+    Ok(())
+}
+
+/// Obtain string with generated Rust code for the sequence of values, serializing them in parallel;
+/// see [`write_par`].
+#[cfg(feature = "rayon")]
+pub fn to_string_par<T: Serialize + Sync>(
+    values: &[T],
+    threshold: usize,
+) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    write_par(values, &mut out, threshold)?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Writes generated Rust code for the sequence of values to file, serializing them in parallel;
+/// see [`write_par`] and [`to_file`].
+#[cfg(feature = "rayon")]
+pub fn to_file_par<T: Serialize + Sync>(
+    values: &[T],
+    target: impl AsRef<std::path::Path>,
+    threshold: usize,
+) -> SerResult {
+    write_atomically(target.as_ref(), |writer| {
+        write_par(values, writer, threshold)
+    })
+}
+
+/// Obtain string with generated Rust code, pretty-printed with four-space indentation.
+///
+/// See [`Uneval::with_indent`] for details. For the other indentation, pass the configuration with
+/// [`indent`][UnevalConfig::indent] to [`to_string_with`], or set the [formatter][Uneval::with_formatter]:
+/// ```
+/// use uneval::config::UnevalConfig;
+///
+/// let config = UnevalConfig { indent: Some("  ".into()), ..UnevalConfig::default() };
+/// let out = uneval::to_string_with(vec![1u8], &config)?;
+/// assert_eq!(out, "vec![\n  1u8,\n].into_iter().collect()");
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_string_pretty(value: impl Serialize) -> Result<String, UnevalError> {
     let mut out = Vec::new();
-    value.serialize(&mut Uneval::new(&mut out))?;
+    value.serialize(&mut Uneval::new(&mut out).with_indent("    "))?;
     Ok(String::from_utf8(out)?)
 }
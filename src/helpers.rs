@@ -1,35 +1,60 @@
+use crate::config::IntFormat;
 use crate::ser::SerResult;
 use std::io::Write;
 
-pub(crate) fn tuple_converter(output: impl Write, len: usize) -> SerResult {
+/// Writes the tuple conversion runtime for the given length.
+///
+/// `visibility` is applied to the items used from outside, i.e. it is empty when the runtime is inline
+/// and `pub` when it is shared in the module. If `trait_path` is set, the conversion is bound by that trait,
+/// instead of the one defined by the runtime itself.
+pub(crate) fn tuple_converter(
+    output: impl Write,
+    len: usize,
+    visibility: &str,
+    trait_path: Option<&str>,
+) -> SerResult {
     if len > 0 {
-        non_zero_size(output, len)
+        non_zero_size(output, len, visibility, trait_path)
     } else {
-        zero_size(output)
+        zero_size(output, visibility)
     }
 }
 
-fn zero_size(mut output: impl Write) -> SerResult {
+fn zero_size(mut output: impl Write, visibility: &str) -> SerResult {
     write!(
         output,
         "
         #[inline]
-        fn convert<T>(_: ()) -> [T; 0] {{
+        {visibility}fn convert<T>(_: ()) -> [T; 0] {{
             []
         }}
-    "
+    ",
+        visibility = visibility
     )?;
     Ok(())
 }
 
-fn non_zero_size(mut output: impl Write, len: usize) -> SerResult {
+fn non_zero_size(
+    mut output: impl Write,
+    len: usize,
+    visibility: &str,
+    trait_path: Option<&str>,
+) -> SerResult {
+    let types = (0..len)
+        .map(|index| format!("T{}", index))
+        .collect::<Vec<_>>()
+        .join(",");
+    if let Some(trait_path) = trait_path {
+        return converter_fn(output, &types, visibility, trait_path);
+    }
     write!(
         output,
         "
-        trait FromTuple<T>: Sized {{
+        {visibility}trait FromTuple<T>: Sized {{
             fn from_tuple(tuple: T) -> Self;
         }}
-    "
+    ",
+        visibility = visibility
     )?;
     let array = format!("[T; {}]", len);
     let tuple = format!("({})", (0..len).map(|_| "T,").collect::<String>());
@@ -54,10 +79,6 @@ fn non_zero_size(mut output: impl Write, len: usize) -> SerResult {
         tuple = tuple,
         mapping = mapping
     )?;
-    let types = (0..len)
-        .map(|index| format!("T{}", index))
-        .collect::<Vec<_>>()
-        .join(",");
     write!(
         output,
         "
@@ -67,13 +88,192 @@ fn non_zero_size(mut output: impl Write, len: usize) -> SerResult {
                     tuple
                 }}
             }}
+        ",
+        types = types,
+    )?;
+    converter_fn(output, &types, visibility, "FromTuple")
+}
 
+fn converter_fn(
+    mut output: impl Write,
+    types: &str,
+    visibility: &str,
+    trait_path: &str,
+) -> SerResult {
+    write!(
+        output,
+        "
             #[inline]
-            fn convert<{types}, Out: FromTuple<({types},)>>(tuple: ({types},)) -> Out {{
+            {visibility}fn convert<{types}, Out: {trait_path}<({types},)>>(tuple: ({types},)) -> Out {{
                 Out::from_tuple(tuple)
             }}
         ",
-        types = types
+        types = types,
+        visibility = visibility,
+        trait_path = trait_path
     )?;
     Ok(())
 }
+
+/// Integer types, which can be written in any [`IntFormat`].
+pub(crate) trait Integer: Copy {
+    /// Splits the value into sign (`true` for negative values) and absolute value.
+    fn sign_magnitude(self) -> (bool, u128);
+    fn write_decimal(self, output: impl Write, suffix: &str) -> SerResult;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {$(
+        impl Integer for $ty {
+            fn sign_magnitude(self) -> (bool, u128) {
+                (self < 0, self.unsigned_abs() as u128)
+            }
+
+            fn write_decimal(self, output: impl Write, suffix: &str) -> SerResult {
+                write_decimal(output, self, suffix)
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8, i16, i32, i64, i128);
+
+macro_rules! impl_unsigned {
+    ($($ty:ty),*) => {$(
+        impl Integer for $ty {
+            fn sign_magnitude(self) -> (bool, u128) {
+                (false, self as u128)
+            }
+
+            fn write_decimal(self, output: impl Write, suffix: &str) -> SerResult {
+                write_decimal(output, self, suffix)
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, u128);
+
+/// Writes the integer literal in the given format, with the provided type suffix.
+pub(crate) fn write_integer(
+    mut output: impl Write,
+    value: impl Integer,
+    format: IntFormat,
+    suffix: &str,
+) -> SerResult {
+    let (negative, magnitude) = value.sign_magnitude();
+    let (prefix, digits) = match format {
+        IntFormat::Decimal => return value.write_decimal(output, suffix),
+        IntFormat::Hex | IntFormat::GroupedHex => ("0x", format!("{:X}", magnitude)),
+        IntFormat::Binary | IntFormat::GroupedBinary => ("0b", format!("{:b}", magnitude)),
+    };
+    if negative {
+        output.write_all(b"-")?;
+    }
+    output.write_all(prefix.as_bytes())?;
+    if matches!(format, IntFormat::GroupedHex | IntFormat::GroupedBinary) {
+        // Groups are counted from the least significant digit, so the first one may be shorter.
+        let first = match digits.len() % 4 {
+            0 => 4,
+            len => len,
+        };
+        output.write_all(&digits.as_bytes()[..first])?;
+        for group in digits.as_bytes()[first..].chunks(4) {
+            output.write_all(b"_")?;
+            output.write_all(group)?;
+        }
+    } else {
+        output.write_all(digits.as_bytes())?;
+    }
+    output.write_all(suffix.as_bytes())?;
+    Ok(())
+}
+
+/// Writes the decimal integer literal with the provided type suffix.
+#[cfg(feature = "fast-fmt")]
+fn write_decimal(mut output: impl Write, value: impl itoa::Integer, suffix: &str) -> SerResult {
+    output.write_all(itoa::Buffer::new().format(value).as_bytes())?;
+    output.write_all(suffix.as_bytes())?;
+    Ok(())
+}
+
+/// Writes the decimal integer literal with the provided type suffix.
+#[cfg(not(feature = "fast-fmt"))]
+fn write_decimal(mut output: impl Write, value: impl std::fmt::Display, suffix: &str) -> SerResult {
+    write!(output, "{}{}", value, suffix)?;
+    Ok(())
+}
+
+/// Writes the floating-point literal with the provided type suffix.
+///
+/// `ryu` always writes the fractional part, unlike `Display`; it is stripped for integral values,
+/// so that the output doesn't depend on the enabled features in the most common case.
+/// Non-finite values are formatted the same way by both.
+#[cfg(feature = "fast-fmt")]
+pub(crate) fn write_float(
+    mut output: impl Write,
+    value: impl ryu::Float,
+    suffix: &str,
+) -> SerResult {
+    let mut buffer = ryu::Buffer::new();
+    let formatted = buffer.format(value);
+    let formatted = formatted.strip_suffix(".0").unwrap_or(formatted);
+    output.write_all(formatted.as_bytes())?;
+    output.write_all(suffix.as_bytes())?;
+    Ok(())
+}
+
+/// Writes the floating-point literal with the provided type suffix.
+#[cfg(not(feature = "fast-fmt"))]
+pub(crate) fn write_float(
+    mut output: impl Write,
+    value: impl std::fmt::Display,
+    suffix: &str,
+) -> SerResult {
+    write!(output, "{}{}", value, suffix)?;
+    Ok(())
+}
+
+/// Splits the generated runtime code into lines, paired with their nesting levels.
+///
+/// This is used by pretty-printer to indent the runtime the same way as everything else.
+pub(crate) fn reindent(code: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut level = 0;
+    code.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(move |line| {
+            if line.starts_with('}') {
+                level -= 1;
+            }
+            let current = level;
+            if line.ends_with('{') {
+                level += 1;
+            }
+            (current, line)
+        })
+}
+
+/// Makes the string literal for the value, choosing the raw one if it is shorter than the escaped one.
+///
+/// Raw literals are not used for strings with control characters, since these can't be written in them
+/// unambiguously (e.g. `\r`) or would make the generated code hard to read.
+pub(crate) fn str_literal(value: &str) -> String {
+    let escaped = value.escape_default().collect::<String>();
+    if escaped.len() == value.len() || value.chars().any(char::is_control) {
+        return format!("\"{}\"", escaped);
+    }
+    // The literal is closed by the quote followed by the same number of hashes, as the ones after `r`,
+    // so this number must be greater than any run of hashes following the quote in the value.
+    let hashes = value
+        .match_indices('"')
+        .map(|(index, _)| value[index + 1..].chars().take_while(|&c| c == '#').count() + 1)
+        .max()
+        .unwrap_or(0);
+    if value.len() + 3 + 2 * hashes < escaped.len() + 2 {
+        let hashes = "#".repeat(hashes);
+        format!("r{}\"{}\"{}", hashes, value, hashes)
+    } else {
+        format!("\"{}\"", escaped)
+    }
+}
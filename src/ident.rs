@@ -0,0 +1,74 @@
+use crate::config::Edition;
+use crate::error::{IdentContext, UnevalError};
+use crate::ser::SerResult;
+use std::io::Write;
+
+/// Keywords which can't be used as identifiers directly, but can be used as raw identifiers.
+///
+/// This includes both strict keywords and the ones reserved for future use, in every edition.
+const KEYWORDS: &[&str] = &[
+    // strict keywords
+    "as", "break", "const", "continue", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    // reserved keywords
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+    "virtual", "yield",
+];
+
+/// Keywords introduced by the later editions, with the first edition where they are keywords.
+const EDITION_KEYWORDS: &[(&str, Edition)] = &[
+    ("async", Edition::Rust2018),
+    ("await", Edition::Rust2018),
+    ("dyn", Edition::Rust2018),
+    ("try", Edition::Rust2018),
+    ("gen", Edition::Rust2024),
+];
+
+fn is_keyword(name: &str, edition: Edition) -> bool {
+    KEYWORDS.contains(&name)
+        || EDITION_KEYWORDS
+            .iter()
+            .any(|&(keyword, since)| keyword == name && edition >= since)
+}
+
+/// Keywords which can't be used as identifiers at all, even in raw form.
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Checks whether the name follows the Unicode rules for Rust identifiers.
+///
+/// Keywords are not rejected here, since most of them can be escaped.
+fn is_xid_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some('_') => name.len() > 1 && chars.all(unicode_ident::is_xid_continue),
+        Some(first) => {
+            unicode_ident::is_xid_start(first) && chars.all(unicode_ident::is_xid_continue)
+        }
+        None => false,
+    }
+}
+
+/// Writes the name of field, struct or variant, escaping it as raw identifier if necessary.
+///
+/// Returns an error if the name can't be represented as identifier at all.
+pub(crate) fn write_ident(
+    mut output: impl Write,
+    name: &str,
+    context: IdentContext,
+    edition: Edition,
+) -> SerResult {
+    if !is_xid_ident(name) || NON_RAW_KEYWORDS.contains(&name) {
+        return Err(UnevalError::InvalidIdentifier {
+            name: name.into(),
+            context,
+            path: String::new(),
+        });
+    }
+    if is_keyword(name, edition) {
+        write!(output, "r#{}", name)?;
+    } else {
+        write!(output, "{}", name)?;
+    }
+    Ok(())
+}
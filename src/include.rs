@@ -0,0 +1,24 @@
+//! Including the generated files at runtime; available without the `serialize` feature.
+
+/// Includes the file generated in the output directory, e.g. by [`to_out_dir`][crate::funcs::to_out_dir].
+///
+/// This is the shorthand for `include!(concat!(env!("OUT_DIR"), "/", file_name))`, so it can be used
+/// both as expression and, for the generated items (e.g. [`Module`][crate::Module]), in item position:
+/// ```ignore
+/// let value: Tables = uneval::include_out_dir!("data/tables.rs");
+/// ```
+/// If the files are generated into the other directory, the environment variable holding its path can be given
+/// explicitly, e.g. the one set by the build script with `cargo:rustc-env=GENERATED_DIR=...`:
+/// ```ignore
+/// let value: Tables = uneval::include_out_dir!(env = "GENERATED_DIR", "tables.rs");
+/// ```
+#[macro_export]
+macro_rules! include_out_dir {
+    // Braces allow the items to be expanded without the trailing semicolon.
+    ($file_name:literal $(,)?) => {
+        $crate::include_out_dir! { env = "OUT_DIR", $file_name }
+    };
+    (env = $var:literal, $file_name:literal $(,)?) => {
+        include! { concat!(env!($var), "/", $file_name) }
+    };
+}
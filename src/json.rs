@@ -0,0 +1,312 @@
+//! Emitting values as [`serde_json::json!`](https://docs.rs/serde_json/latest/serde_json/macro.json.html)
+//! invocations.
+//!
+//! `serde_json::Value` is serialized as plain numbers, strings, sequences and maps, so the usual code
+//! would build some other type instead. Inside the `json!` macro, on the other hand, these pieces are
+//! written in JSON syntax and converted to the `Value`. The generated code requires `serde_json`
+//! as dependency of the crate using it.
+//!
+//! Numbers are written without suffixes, unless they don't fit into `i32`, which is the type inferred for
+//! the unsuffixed integer literals. Non-finite floats become `null`, as they do in `serde_json` itself.
+
+use crate::error::UnevalError;
+use crate::ser::SerResult;
+use serde::ser::{self, Serialize};
+use std::io::Write;
+
+/// Obtain string with the `serde_json::json!` invocation, yielding the `serde_json::Value` equal to the value.
+///
+/// The value is expected to be the `serde_json::Value` itself, or anything which serializes to the same pieces,
+/// i.e. to numbers, strings, booleans, units, options, sequences and maps with string keys.
+/// Other values (e.g. structs) are rejected with [`UnevalError::Custom`].
+///
+/// ```
+/// let value = serde_json::json!({"name": "uneval", "tags": [1, null, 2.5]});
+/// assert_eq!(
+///     uneval::json::to_json_expr(&value)?,
+///     r#"serde_json::json!({"name": "uneval","tags": [1,null,2.5]})"#
+/// );
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub fn to_json_expr(value: impl Serialize) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    write_json(&mut out, &value)?;
+    Ok(String::from_utf8(out)?)
+}
+
+pub(crate) fn write_json<T: ?Sized + Serialize>(out: &mut impl Write, value: &T) -> SerResult {
+    write!(out, "serde_json::json!(")?;
+    value.serialize(JsonMacro { out })?;
+    write!(out, ")")?;
+    Ok(())
+}
+
+/// Serializer writing the value in the JSON syntax accepted by `json!`.
+struct JsonMacro<'a, W: Write> {
+    out: &'a mut W,
+}
+
+impl<W: Write> JsonMacro<'_, W> {
+    fn int(self, v: i128, suffix: &str) -> SerResult {
+        if i32::try_from(v).is_ok() {
+            write!(self.out, "{}", v)?;
+        } else {
+            write!(self.out, "{}{}", v, suffix)?;
+        }
+        Ok(())
+    }
+
+    fn float(self, v: f64) -> SerResult {
+        if v.is_finite() {
+            write!(self.out, "{:?}", v)?;
+        } else {
+            write!(self.out, "null")?;
+        }
+        Ok(())
+    }
+
+    fn unsupported(what: &str) -> UnevalError {
+        ser::Error::custom(format!(
+            "{} can't be emitted inside `serde_json::json!`",
+            what
+        ))
+    }
+}
+
+/// Sequence or map inside the `json!` invocation.
+struct Compound<'a, W: Write> {
+    out: &'a mut W,
+    first: bool,
+}
+
+impl<W: Write> Compound<'_, W> {
+    fn comma(&mut self) -> SerResult {
+        if !std::mem::take(&mut self.first) {
+            write!(self.out, ",")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for JsonMacro<'a, W> {
+    type Ok = ();
+    type Error = UnevalError;
+
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = ser::Impossible<(), UnevalError>;
+    type SerializeTupleVariant = ser::Impossible<(), UnevalError>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = ser::Impossible<(), UnevalError>;
+    type SerializeStructVariant = ser::Impossible<(), UnevalError>;
+
+    fn serialize_bool(self, v: bool) -> SerResult {
+        write!(self.out, "{}", v)?;
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> SerResult {
+        self.int(v.into(), "i8")
+    }
+    fn serialize_i16(self, v: i16) -> SerResult {
+        self.int(v.into(), "i16")
+    }
+    fn serialize_i32(self, v: i32) -> SerResult {
+        self.int(v.into(), "i32")
+    }
+    fn serialize_i64(self, v: i64) -> SerResult {
+        self.int(v.into(), "i64")
+    }
+    fn serialize_i128(self, v: i128) -> SerResult {
+        self.int(v, "i128")
+    }
+    fn serialize_u8(self, v: u8) -> SerResult {
+        self.int(v.into(), "u8")
+    }
+    fn serialize_u16(self, v: u16) -> SerResult {
+        self.int(v.into(), "u16")
+    }
+    fn serialize_u32(self, v: u32) -> SerResult {
+        self.int(v.into(), "u32")
+    }
+    fn serialize_u64(self, v: u64) -> SerResult {
+        self.int(v.into(), "u64")
+    }
+    fn serialize_u128(self, v: u128) -> SerResult {
+        match i128::try_from(v) {
+            Ok(v) => self.int(v, "u128"),
+            Err(_) => {
+                write!(self.out, "{}u128", v)?;
+                Ok(())
+            }
+        }
+    }
+    fn serialize_f32(self, v: f32) -> SerResult {
+        // `serde_json` stores every float as `f64`, so the widened value is written to keep it exact.
+        self.float(v.into())
+    }
+    fn serialize_f64(self, v: f64) -> SerResult {
+        self.float(v)
+    }
+    fn serialize_char(self, v: char) -> SerResult {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+    fn serialize_str(self, v: &str) -> SerResult {
+        write!(self.out, "{}", crate::helpers::str_literal(v))?;
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> SerResult {
+        ser::Serializer::collect_seq(self, v)
+    }
+    fn serialize_none(self) -> SerResult {
+        self.serialize_unit()
+    }
+    fn serialize_some<T>(self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> SerResult {
+        write!(self.out, "null")?;
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> SerResult {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> SerResult {
+        Err(Self::unsupported("enum variant"))
+    }
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Self::unsupported("enum variant"))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, UnevalError> {
+        write!(self.out, "[")?;
+        Ok(Compound {
+            out: self.out,
+            first: true,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, UnevalError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, UnevalError> {
+        Err(Self::unsupported("tuple struct"))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, UnevalError> {
+        Err(Self::unsupported("enum variant"))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, UnevalError> {
+        write!(self.out, "{{")?;
+        Ok(Compound {
+            out: self.out,
+            first: true,
+        })
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, UnevalError> {
+        Err(Self::unsupported("struct"))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, UnevalError> {
+        Err(Self::unsupported("enum variant"))
+    }
+}
+
+impl<W: Write> ser::SerializeSeq for Compound<'_, W> {
+    type Ok = ();
+    type Error = UnevalError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        self.comma()?;
+        value.serialize(JsonMacro { out: self.out })
+    }
+
+    fn end(self) -> SerResult {
+        write!(self.out, "]")?;
+        Ok(())
+    }
+}
+
+impl<W: Write> ser::SerializeTuple for Compound<'_, W> {
+    type Ok = ();
+    type Error = UnevalError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> SerResult {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<W: Write> ser::SerializeMap for Compound<'_, W> {
+    type Ok = ();
+    type Error = UnevalError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        self.comma()?;
+        key.serialize(JsonMacro { out: self.out })?;
+        write!(self.out, ": ")?;
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> SerResult
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(JsonMacro { out: self.out })
+    }
+
+    fn end(self) -> SerResult {
+        write!(self.out, "}}")?;
+        Ok(())
+    }
+}
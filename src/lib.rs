@@ -15,6 +15,97 @@
 //! ```ignore
 //! let value = include!(concat!(env!(OUT_DIR), "/file_name.rs"));
 //! ```
+//! If `uneval` is the regular dependency too (it needs no default features for that, see below), the same
+//! can be written as `uneval::include_out_dir!("file_name.rs")` - see [`include_out_dir!`].
+//!
+//! The generated code is self-contained: the tuple conversion "runtime" (see below) is emitted together
+//! with the value, so by default `uneval` (and, with it, `serde`) is only needed as the build dependency:
+//! ```toml
+//! [build-dependencies]
+//! uneval = "0.2"
+//! ```
+//!
+//! Everything but the [`convert`] module and the [`include_out_dir!`] macro is behind the default `serialize`
+//! feature, which also brings `serde` and the other dependencies of the serializer. So, if `uneval` is needed
+//! at runtime too, e.g. for the code generated with the [shared tuple trait][ser::Uneval::with_tuple_trait],
+//! the regular dependency can go without it, leaving the build dependency with the default features:
+//! ```toml
+//! [dependencies]
+//! uneval = { version = "0.2", default-features = false }
+//! ```
+//!
+//! The shape of the generated code (e.g. the way to build sequences and maps, the integer literals or
+//! the paths to the types) can be configured with [`UnevalConfig`][config::UnevalConfig], which is passed
+//! to [`to_file_with`], [`to_out_dir_with`] or [`to_string_with`]; the options for the specific paths or types
+//! are set with the `with_*` methods of [`Uneval`][ser::Uneval].
+//!
+//! If the value can be constructed in constant context, it can also be emitted as the `const` item,
+//! to be included at module scope - see [`to_const`]. Other values can be emitted as the lazily-initialized
+//! `static` item, e.g. `pub static DATA: std::sync::LazyLock<Data> = std::sync::LazyLock::new(|| ...);` -
+//! see [`to_static`].
+//! Several such items can be collected into one generated module - see [`Module`]; large modules can be split
+//! into several files, included by the single main one - see [`Module::to_files_chunked`].
+//! If the type of the value can't be inferred where it is included, the expression can be annotated with it -
+//! see [`to_string_typed`].
+//! Large sequences and maps can be emitted element by element, without collecting them first -
+//! see [`SeqEmitter`] and [`MapEmitter`].
+//! Very large sequences and maps can be split into several files, so that the compiler doesn't have to
+//! process the whole value at once - see [`to_files_chunked`].
+//! The companion `uneval-macros` crate does the same at macro expansion, with `embed!("data.json" as Type)`,
+//! so that no build script is needed.
+//! Besides `io::Write`, the code can be written into any `fmt::Write` implementation, e.g. appended to the
+//! existing `String` - see [`write_fmt`] and [`Uneval::from_fmt`][ser::Uneval::from_fmt].
+//!
+//! With `proc-macro` feature, the code can also be obtained as `proc_macro2::TokenStream` with `to_tokens`,
+//! to be embedded directly into the output of procedural macro.
+//!
+//! With `validate` feature, the generated code can be checked to be syntactically valid before it is written,
+//! using `to_string_checked`, `to_file_checked` or `validate`.
+//! With `prettyplease` feature, it can also be reformatted the way `rustfmt` would, without requiring the `rustfmt`
+//! binary - see `format_expr`, `to_file_formatted` and `to_out_dir_formatted`. If `rustfmt` is installed, it can be
+//! used instead, so that the checked-in code matches the rest of the repository - see [`rustfmt`][funcs::rustfmt]
+//! and [`to_file_rustfmt`].
+//!
+//! To find the problems with the generated code before it is compiled, e.g. to fail the build script early,
+//! use [`check`] or [`check_with`]. The same problems can be collected while the code is written, too - see
+//! [`Uneval::with_diagnostics`][ser::Uneval::with_diagnostics], or turned into errors - see
+//! [`Uneval::with_strict`][ser::Uneval::with_strict].
+//!
+//! To find out what makes the generated code large, use [`write_with_report`], [`to_file_with_report`]
+//! or [`Uneval::report`][ser::Uneval::report]; [`stats`] gathers the same statistics without writing the code.
+//!
+//! By default, the generated code is as compact as possible. To make it readable (e.g. for debugging),
+//! use [`to_string_pretty`] or configure the serializer with [`Uneval::with_indent`][ser::Uneval::with_indent].
+//! Any other layout can be provided by the custom [`Formatter`][format::Formatter].
+//! With `json` feature, `serde_json::Value` can be emitted as the `serde_json::json!` invocation - see
+//! `json::to_json_expr` and `Uneval::with_json_at`.
+//!
+//! With `once_cell` feature, the `static` items can be initialized with `once_cell::sync::Lazy` instead of
+//! `std::sync::LazyLock`, for the toolchains which don't have the latter - see `config::StaticKind::OnceCell`.
+//!
+//! With `compress` feature, large blobs can be moved into compressed files next to the generated code -
+//! see `to_file_compressed`.
+//!
+//! With `postcard` or `bincode` feature, the parts of the value which can't be emitted as code (e.g. the types
+//! with private fields) can be embedded as binary blobs, deserialized when the generated code runs - see
+//! `Uneval::with_fallback_at`. The generated code then requires the codec crate and `serde` at runtime.
+//!
+//! With `fast-fmt` feature, numbers are formatted with [`itoa`](https://docs.rs/itoa) and [`ryu`](https://docs.rs/ryu)
+//! instead of `core::fmt`, which is noticeably faster for large numeric datasets. Floats with large or small exponents
+//! may be written differently in this case, but they're still the same values.
+//!
+//! With `glam`, `mint` or `nalgebra` feature, the vector types of the corresponding library can be created
+//! by the tuple conversion runtime - see [`convert::FromTuple`].
+//!
+//! With `transcode` feature, data in self-describing formats, like JSON, can be converted to the code directly,
+//! without deserializing into the typed value first - see `transcode`.
+//!
+//! With `rayon` feature, large sequences of values can be serialized in parallel - see `write_par`.
+//!
+//! With `testing` feature, the generated code for the values of your own types can be checked to compile
+//! and yield the original value - see `testing::assert_embeds`.
+//!
+//! Files which are checked in can be marked as generated with [`Uneval::with_header`][ser::Uneval::with_header].
 //!
 //! ## How does it work?
 //!
@@ -55,6 +146,10 @@
 //! let _: String = "string value".into();
 //! ```
 //!
+//! Other string-like types, like `&'static str` or `Cow<'static, str>`, need the different code; it can be chosen
+//! with [`StrMode`][config::StrMode], globally or for the specific fields. Strings with many quotes or backslashes
+//! are written as raw literals, e.g. `r"C:\dir"`, when this makes them shorter.
+//!
 //! Byte strings are handled as byte sequences, [as recommended by Serde itself][::serde::Serializer::serialize_bytes],
 //! and so we'll discuss them [below](#vec-like-types-sequences).
 //!
@@ -71,12 +166,22 @@
 //! let _: TupleStruct = TupleStruct((), None, Some(1u8));
 //! ```
 //!
+//! Empty tuple structs (`struct Empty();`) are emitted correctly when `Serialize` is derived, but
+//! a custom implementation may report them as unit structs. In this case, the serializer must be told
+//! about them explicitly, with [`Uneval::with_empty_tuple_struct`][ser::Uneval::with_empty_tuple_struct]
+//! (or [`Uneval::with_empty_tuple_variant`][ser::Uneval::with_empty_tuple_variant] for enum variants).
+//!
 //! ### Vec-like types (sequences)
 //!
 //! `Vec`-like structures are constructed using the temporary `Vec`. We assume that every such type will
 //! implement [`FromIterator`][std::iter::FromIterator], so we emit the call to `vec!` macro,
 //! serialize the data and finalize the emit with call to `into_iter().collect()`.
 //! This is not exactly zero-cost, but it seems that this is the minimal.
+//! When the target type is known, this can be changed with [`SeqMode`][config::SeqMode] - e.g. to emit
+//! the plain `vec![...]` for `Vec`, `vec![...].into_boxed_slice()` for `Box<[T]>`, or the allocation-free
+//! `&[...]` for `&'static [T]` and `Cow::Borrowed(&[...])` for `Cow<'static, [T]>` - either globally,
+//! with [`Uneval::with_seq_mode`][ser::Uneval::with_seq_mode], or for the specific path,
+//! with [`Uneval::with_seq_mode_at`][ser::Uneval::with_seq_mode_at].
 //!
 //! Example:
 //! ```
@@ -106,13 +211,18 @@
 //! In general, here's what being generated:
 //! - A `FromTuple<T>` trait with `from_tuple(input: T) -> Self` associated function.
 //! - Two implementations: `impl<T> FromTuple<(T,...,T,)> for [T; N]` and
-//! `impl<T1, ... TN> FromTuple<(T1,...TN,)> for (T1,...TN,)`.
+//!   `impl<T1, ... TN> FromTuple<(T1,...TN,)> for (T1,...TN,)`.
 //! - Function `convert<T1, ... TN, Out: FromTuple<(T1,...TN,)>>(tuple: (T1,...TN,)) -> Out`,
-//! which simply calls `Out::from_tuple(tuple)`.
+//!   which simply calls `Out::from_tuple(tuple)`.
 //!
 //! Then, the value itself is created by the call to `convert`, with tuple of serialized values as argument.
 //! Depending on whether the target expects the array or tuple, `convert` will select one particular implementation.
 //!
+//! Note that this runtime is emitted inline, in the block wrapping every tuple, and, unless it is bound
+//! by the [shared trait](#other-types), never refers to `uneval` itself.
+//! So the generated code doesn't depend on how (or whether) the consuming crate can access `uneval` -
+//! e.g. it works the same when `uneval` is only reachable through a re-export from some other build-time crate.
+//!
 //! Example:
 //! ```
 //! let tuple: (i32, f32, String) = {
@@ -189,6 +299,23 @@
 //! };
 //! ```
 //!
+//! #### Other types
+//! The runtime can be made to use [`convert::FromTuple`] instead of its own trait, with
+//! [`Uneval::with_tuple_trait`][ser::Uneval::with_tuple_trait], so that the tuples can be converted into
+//! any type implementing it, e.g. `glam::Vec3`. In this case, the generated code depends on `uneval`;
+//! the regular dependency only needs the [`convert`] module, so it can go without the default features
+//! (see [Usage](#usage)).
+//!
+//! #### Plain arrays
+//! When it is known that some value is an array, the runtime can be avoided altogether by switching
+//! to [`TupleMode::Array`][config::TupleMode::Array] - either for some specific path, with
+//! [`Uneval::with_tuple_mode_at`][ser::Uneval::with_tuple_mode_at], or for some specific length, with
+//! [`Uneval::with_tuple_mode_for_len`][ser::Uneval::with_tuple_mode_for_len]. It can be enabled globally, too,
+//! but in this case every real tuple in the value will break.
+//! ```
+//! let arr: [u8; 3] = [1u8, 2u8, 3u8];
+//! ```
+//!
 //! ### Maps
 //!
 //! Since Rust doesn't have the notion of map literals, we can't construct one directly. However, standard map-like
@@ -204,6 +331,15 @@
 //! ].into_iter().collect();
 //! ```
 //!
+//! If the map type is known, `HashMap::from([...])` can be emitted instead - see [`MapMode`][config::MapMode].
+//! With `phf` feature, the maps can also be emitted as static [`phf`](https://docs.rs/phf) maps, which need no
+//! initialization at all; this is done automatically for the items declared as `phf::Map`.
+//!
+//! The entries are emitted in the order they are serialized. For the maps with random iteration order, like
+//! [`HashMap`], they can be sorted by their generated code with
+//! [`Uneval::with_sorted_maps`][ser::Uneval::with_sorted_maps], so that the same map always yields the same output;
+//! the sets can be sorted too, with [`Uneval::with_sorted_seq_at`][ser::Uneval::with_sorted_seq_at].
+//!
 //! [`HashMap`]: std::collections::HashMap
 //! [`BTreeMap`]: std::collections::BTreeMap
 //!
@@ -211,6 +347,10 @@
 //!
 //! Last but not the least, this case is relatively simple. Emitted code is simply the struct construction -
 //! i.e. the struct name, the curly braces and a list of pairs of the form `{field name}: {serialized value}`.
+//! Names which happen to be Rust keywords (e.g. the field `r#type`) are emitted as raw identifiers
+//! (which ones are keywords depends on the edition, see [`Uneval::with_edition`][ser::Uneval::with_edition]);
+//! names which can't be identifiers at all (e.g. `max-retries`, or the keywords `self`, `Self`, `super`
+//! and `crate`, which can't be raw) yield an [`InvalidIdentifier`][error::UnevalError::InvalidIdentifier] error.
 //!
 //! Example:
 //! ```
@@ -225,21 +365,106 @@
 //! ## Limitations
 //! There are some cases when `uneval` will be unable to generate valid code. Namely:
 //! 1. Since Serde doesn't provide us the full path to the type in question (and in most cases it's simply unable to),
-//! all the structs and enums used during value construction must be in scope.
-//! As a consequence, all of them must have distinct names - otherwise, there will be name clashes.
+//!    all the structs and enums used during value construction must be in scope.
+//!    As a consequence, all of them must have distinct names - otherwise, there will be name clashes.
+//!    To avoid this, the full paths can be registered with [`Uneval::with_type_path`][ser::Uneval::with_type_path],
+//!    e.g. with the [`paths!`] macro or with `#[derive(UnevalPath)]` from the `uneval-macros` crate.
 //! 2. This serializer is intended for use with derived implementation. It may return bogus results
-//! when used with customized `Serialize`.
+//!    when used with customized `Serialize`.
 //! 3. It is impossible to consume code for the type with private fields outside from the module it is defined in.
-//! In fact, to be able to use this type with `uneval`, you'll have to distribute two copies of your crate,
-//! one of which would only export the definition with derived `Serialize` to be used by serializer
-//! during the build-time of the second copy. (Isn't this a bit too complex?)
+//!    In fact, to be able to use this type with `uneval`, you'll have to distribute two copies of your crate,
+//!    one of which would only export the definition with derived `Serialize` to be used by serializer
+//!    during the build-time of the second copy. (Isn't this a bit too complex?)
+//! 4. Enums with `#[serde(tag = "...")]` are serialized as structs named after the enum, so they must be registered
+//!    with [`Uneval::with_tagged_enum`][ser::Uneval::with_tagged_enum]; untagged enums lose the variant name entirely
+//!    and need [`Uneval::with_override_at`][ser::Uneval::with_override_at].
+//! 5. Structs with `#[serde(flatten)]` field are serialized as maps; they can be partially restored
+//!    with [`Uneval::with_map_as_struct`][ser::Uneval::with_map_as_struct].
 //!
 //! [include]: https://doc.rust-lang.org/stable/std/macro.include.html
 
+#[cfg(feature = "serialize")]
+mod chunks;
+#[cfg(feature = "serialize")]
+mod dedup;
+#[cfg(feature = "serialize")]
 mod helpers;
+#[cfg(feature = "serialize")]
+mod ident;
+mod include;
+#[cfg(feature = "serialize")]
+mod output;
+#[cfg(feature = "serialize")]
+mod path;
+#[cfg(feature = "serialize")]
+mod paths;
+#[cfg(feature = "serialize")]
+mod sidecar;
+#[cfg(feature = "serialize")]
+mod tagged;
 
+#[cfg(feature = "serialize")]
+pub mod backend;
+#[cfg(feature = "serialize")]
+pub mod config;
+pub mod convert;
+#[cfg(feature = "serialize")]
+pub mod diagnostic;
+#[cfg(feature = "serialize")]
+pub mod emitter;
+#[cfg(feature = "serialize")]
 pub mod error;
+#[cfg(feature = "serialize")]
+pub mod format;
+#[cfg(feature = "serialize")]
 pub mod funcs;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "serialize")]
+pub mod module;
+#[cfg(feature = "serialize")]
+pub mod report;
+#[cfg(feature = "serialize")]
 pub mod ser;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "serialize")]
+pub use diagnostic::{Diagnostic, Severity};
+#[cfg(feature = "serialize")]
+pub use emitter::{MapEmitter, SeqEmitter};
+#[cfg(feature = "serialize")]
+pub use funcs::{
+    check, check_with, rustfmt, stats, to_const, to_file, to_file_cached, to_file_cached_with,
+    to_file_const, to_file_region, to_file_rustfmt, to_file_static, to_file_typed, to_file_with,
+    to_file_with_report, to_file_with_sidecars, to_file_with_sidecars_with, to_files_chunked,
+    to_files_chunked_sorted, to_out_dir, to_out_dir_cached, to_out_dir_cached_with,
+    to_out_dir_chunked, to_out_dir_const, to_out_dir_rustfmt, to_out_dir_static, to_out_dir_typed,
+    to_out_dir_with, to_out_dir_with_report, to_out_dir_with_sidecars, to_static, to_string,
+    to_string_pretty, to_string_typed, to_string_with, to_vec, write, write_const, write_fmt,
+    write_static, write_typed, write_with_report,
+};
+#[cfg(feature = "serialize")]
+pub use module::Module;
+#[cfg(feature = "serialize")]
+pub use paths::TypePath;
+#[cfg(feature = "serialize")]
+pub use report::Report;
+
+#[cfg(feature = "proc-macro")]
+pub use funcs::{to_tokens, to_tokens_with};
+
+#[cfg(feature = "validate")]
+pub use funcs::{to_file_checked, to_string_checked, validate};
+
+#[cfg(feature = "prettyplease")]
+pub use funcs::{format_expr, to_file_formatted, to_out_dir_formatted};
+
+#[cfg(feature = "compress")]
+pub use funcs::{to_file_compressed, to_file_compressed_with, to_out_dir_compressed};
+
+#[cfg(feature = "transcode")]
+pub use funcs::{transcode, transcode_with};
 
-pub use funcs::{to_file, to_out_dir, to_string, write};
+#[cfg(feature = "rayon")]
+pub use funcs::{to_file_par, to_string_par, write_par};
@@ -0,0 +1,302 @@
+//! Generation of the module with several named values.
+
+use crate::config::{ChunkSize, ConstOpts, Header, StaticOpts};
+use crate::error::UnevalError;
+use crate::funcs::{const_item, static_item, write_atomically};
+use crate::ser::{SerResult, Uneval};
+use serde::Serialize;
+use std::io::Write;
+
+/// Builder of the module, i.e. the file with several `const` or `static` items.
+///
+/// Compared to generating every value separately, the tuple conversion runtime is emitted only once
+/// for every tuple length, at the start of the module, and shared by all the items.
+///
+/// The generated module should be included at module scope, either with [`include!`] or with the `#[path]`
+/// attribute on the `mod` item:
+/// ```no_run
+/// # #[derive(serde::Serialize)] struct Country { name: String }
+/// # let countries = vec![Country { name: "Narnia".into() }];
+/// let mut module = uneval::Module::new();
+/// module
+///     .add_static("COUNTRIES", "Vec<Country>", &countries)?
+///     .add_const("VERSION", "[u8; 2]", (1u8, 2u8))?;
+/// module.to_out_dir("tables.rs")?;
+/// # Ok::<(), uneval::error::UnevalError>(())
+/// ```
+pub struct Module {
+    items: Uneval<Vec<u8>>,
+    /// Offsets of the ends of the items in the generated code.
+    item_ends: Vec<usize>,
+    header: Option<Header>,
+    imports: Vec<String>,
+}
+
+impl Default for Module {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Module {
+    /// Creates the empty module.
+    pub fn new() -> Self {
+        Self {
+            items: Uneval::new(Vec::new()).with_shared_tuples(),
+            item_ends: Vec::new(),
+            header: None,
+            imports: Vec::new(),
+        }
+    }
+
+    /// Adds the header to the module; see [`Uneval::with_header`].
+    ///
+    /// This must be called before adding the items, since the attributes are applied to the items as they are added.
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.items = self.items.with_item_attributes(header.attributes());
+        self.header = Some(header);
+        self
+    }
+
+    /// Adds the `use` declaration at the top of the module, e.g. for `crate::model::*`, so that the types
+    /// used by the items don't have to be imported where the module is included:
+    /// ```
+    /// let mut module = uneval::Module::new().with_import("crate::model::*");
+    /// module.add_const("ORIGIN", "Point", (0u8, 0u8))?;
+    /// assert!(module.into_string()?.starts_with("use crate::model::*;\n"));
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    ///
+    /// Since the module is included into the existing one, the imports become part of it, and the names
+    /// imported explicitly (i.e. not with the glob) must not clash with the ones declared there.
+    pub fn with_import(mut self, path: impl Into<String>) -> Self {
+        self.imports.push(path.into());
+        self
+    }
+
+    /// Sets the value returned by `is_human_readable` for all the items; see [`Uneval::with_human_readable`].
+    pub fn with_human_readable(mut self, human_readable: bool) -> Self {
+        self.items = self.items.with_human_readable(human_readable);
+        self
+    }
+
+    /// Sets the oldest Rust version which must compile the module; see [`Uneval::with_min_rust_version`].
+    pub fn with_min_rust_version(mut self, minor: u32) -> Self {
+        self.items = self.items.with_min_rust_version(minor);
+        self
+    }
+
+    /// Makes the shared tuple conversion runtime use the existing trait; see [`Uneval::with_tuple_trait`].
+    ///
+    /// This must be called before adding the items, like [`with_header`][Module::with_header].
+    pub fn with_tuple_trait(mut self, path: impl Into<String>) -> Self {
+        self.items = self.items.with_tuple_trait(path);
+        self
+    }
+
+    /// Emits the tuple struct with the given name as the tuple; see [`Uneval::with_tuple_struct_as_tuple`].
+    pub fn with_tuple_struct_as_tuple(mut self, name: impl Into<String>) -> Self {
+        self.items = self.items.with_tuple_struct_as_tuple(name);
+        self
+    }
+
+    /// Adds the `pub const` item with the value; see [`to_const`][crate::funcs::to_const].
+    ///
+    /// Returns the module itself, so that the items can be added in chain.
+    pub fn add_const(
+        &mut self,
+        name: &str,
+        type_name: &str,
+        value: impl Serialize,
+    ) -> Result<&mut Self, UnevalError> {
+        self.add_const_with(ConstOpts::new(name, type_name), value)
+    }
+
+    /// Adds the `const` item with the value, with the given visibility:
+    /// ```
+    /// use uneval::config::ConstOpts;
+    ///
+    /// let mut module = uneval::Module::new();
+    /// module.add_const_with(ConstOpts { visibility: "pub(crate)", ..ConstOpts::new("LIMIT", "u32") }, 10u32)?;
+    /// assert_eq!(module.into_string()?, "pub(crate) const LIMIT: u32 = 10u32;\n");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn add_const_with(
+        &mut self,
+        opts: ConstOpts<'_>,
+        value: impl Serialize,
+    ) -> Result<&mut Self, UnevalError> {
+        self.add_item(|items| const_item(value, opts, items))
+    }
+
+    /// Adds the `pub static` item with the value, initialized with [`LazyLock`][std::sync::LazyLock].
+    pub fn add_static(
+        &mut self,
+        name: &str,
+        type_name: &str,
+        value: impl Serialize,
+    ) -> Result<&mut Self, UnevalError> {
+        self.add_static_with(StaticOpts::new(name, type_name), value)
+    }
+
+    /// Adds the `static` item with the value, e.g. with the given visibility; see [`to_static`][crate::funcs::to_static].
+    pub fn add_static_with(
+        &mut self,
+        opts: StaticOpts<'_>,
+        value: impl Serialize,
+    ) -> Result<&mut Self, UnevalError> {
+        self.add_item(|items| static_item(value, opts, items))
+    }
+
+    /// Writes the module to the provided [`Write`] implementation.
+    pub fn write(self, mut target: impl Write) -> SerResult {
+        let parts = self.into_parts()?;
+        target.write_all(&parts.runtime)?;
+        target.write_all(&parts.items)?;
+        Ok(())
+    }
+
+    /// Writes the module into several files, to reduce the memory usage of the compiler, similar to
+    /// [`to_files_chunked`][crate::funcs::to_files_chunked].
+    ///
+    /// The items are grouped into chunks of the given size, and every chunk is written into the file
+    /// `{base_name}_{index}.rs` in the `dir`. The main file `{base_name}.rs` in the same directory holds the header
+    /// and the shared tuple conversion runtime, and includes the chunks with `include!`, so that all the items
+    /// are available at the scope where only the main file is included:
+    /// ```ignore
+    /// include!(concat!(env!("OUT_DIR"), "/tables.rs"));
+    /// ```
+    /// Returns the number of chunk files. The items are never split, so with [`ChunkSize::Bytes`],
+    /// the chunk with a single large item can be larger than requested.
+    pub fn to_files_chunked(
+        self,
+        dir: impl AsRef<std::path::Path>,
+        base_name: &str,
+        size: ChunkSize,
+    ) -> Result<usize, UnevalError> {
+        let dir = dir.as_ref();
+        let comment = self
+            .header
+            .as_ref()
+            .map(Header::comment)
+            .unwrap_or_default();
+        let Parts {
+            runtime,
+            items,
+            item_ends,
+        } = self.into_parts()?;
+        let mut chunks = Vec::new();
+        let (mut start, mut end, mut len) = (0, 0, 0);
+        for item_end in item_ends {
+            let full = match size {
+                ChunkSize::Elements(max) => len >= max.max(1),
+                ChunkSize::Bytes(max) => len > 0 && item_end - start > max,
+            };
+            if full {
+                chunks.push(&items[start..end]);
+                (start, len) = (end, 0);
+            }
+            (end, len) = (item_end, len + 1);
+        }
+        if len > 0 {
+            chunks.push(&items[start..end]);
+        }
+        for (index, chunk) in chunks.iter().enumerate() {
+            let target = dir.join(format!("{}_{}.rs", base_name, index));
+            write_atomically(&target, |writer| {
+                writer.write_all(comment.as_bytes())?;
+                Ok(writer.write_all(chunk)?)
+            })?;
+        }
+        write_atomically(&dir.join(format!("{}.rs", base_name)), |writer| {
+            writer.write_all(&runtime)?;
+            for index in 0..chunks.len() {
+                writeln!(writer, "include!(\"{}_{}.rs\");", base_name, index)?;
+            }
+            Ok(())
+        })?;
+        Ok(chunks.len())
+    }
+
+    /// Convenience wrapper around [`to_files_chunked`][Module::to_files_chunked], writing the files
+    /// into the `OUT_DIR`.
+    pub fn to_out_dir_chunked(
+        self,
+        base_name: &str,
+        size: ChunkSize,
+    ) -> Result<usize, UnevalError> {
+        self.to_files_chunked(crate::funcs::out_dir_path("")?, base_name, size)
+    }
+
+    /// Writes the module to file; see [`to_file`][crate::funcs::to_file].
+    pub fn to_file(self, target: impl AsRef<std::path::Path>) -> SerResult {
+        write_atomically(target.as_ref(), |writer| self.write(writer))
+    }
+
+    /// Writes the module to file in `OUT_DIR`; see [`to_out_dir`][crate::funcs::to_out_dir].
+    pub fn to_out_dir(self, file_name: impl AsRef<str>) -> Result<std::path::PathBuf, UnevalError> {
+        let path = crate::funcs::out_dir_path(file_name)?;
+        self.to_file(&path)?;
+        Ok(path)
+    }
+
+    /// Obtains the string with the module code.
+    pub fn into_string(self) -> Result<String, UnevalError> {
+        let mut out = Vec::new();
+        self.write(&mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    /// Writes the item, recording where it ends; if it fails, its partial code is dropped, so that neither
+    /// [`write`][Module::write] nor [`to_files_chunked`][Module::to_files_chunked] emit it.
+    fn add_item(
+        &mut self,
+        write: impl FnOnce(&mut Uneval<Vec<u8>>) -> SerResult,
+    ) -> Result<&mut Self, UnevalError> {
+        match write(&mut self.items) {
+            Ok(()) => {
+                self.item_ends.push(self.items.written());
+                Ok(self)
+            }
+            Err(err) => {
+                let end = self.item_ends.last().copied().unwrap_or(0);
+                self.items.discard_after(end);
+                Err(err)
+            }
+        }
+    }
+
+    fn into_parts(mut self) -> Result<Parts, UnevalError> {
+        let tuple_trait = self.items.tuple_trait().map(str::to_owned);
+        let lens = self.items.take_shared_tuples();
+        let mut runtime = Vec::new();
+        if let Some(header) = &self.header {
+            runtime.write_all(header.comment().as_bytes())?;
+        }
+        for import in &self.imports {
+            writeln!(runtime, "use {};", import)?;
+        }
+        for len in lens {
+            for attribute in self.header.iter().flat_map(Header::attributes) {
+                writeln!(runtime, "{}", attribute)?;
+            }
+            write!(runtime, "mod __uneval_tuple_{} {{", len)?;
+            crate::helpers::tuple_converter(&mut runtime, len, "pub ", tuple_trait.as_deref())?;
+            writeln!(runtime, "}}")?;
+        }
+        Ok(Parts {
+            runtime,
+            items: self.items.into_writer(),
+            item_ends: self.item_ends,
+        })
+    }
+}
+
+/// Generated code of the module, split to be written either into one file or into several ones.
+struct Parts {
+    /// Code before the items, i.e. the header comment, the imports and the shared tuple conversion runtime.
+    runtime: Vec<u8>,
+    items: Vec<u8>,
+    /// Offsets of the ends of the items in their code.
+    item_ends: Vec<usize>,
+}
@@ -0,0 +1,144 @@
+use std::fmt;
+use std::io::{self, Write};
+
+/// Writer used by the serializer, which can temporarily redirect the output into memory.
+///
+/// This is used when some part of the generated code must be post-processed before being written,
+/// e.g. when map entries are sorted.
+pub(crate) struct Output<W: Write> {
+    inner: W,
+    captures: Vec<Vec<u8>>,
+    /// Code written before anything else goes into the inner writer, e.g. the header comment.
+    prefix: Vec<u8>,
+    /// Number of bytes written into the inner writer.
+    written: usize,
+}
+
+impl<W: Write> Output<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            captures: Vec::new(),
+            prefix: Vec::new(),
+            written: 0,
+        }
+    }
+
+    pub(crate) fn set_prefix(&mut self, prefix: impl Into<Vec<u8>>) {
+        self.prefix = prefix.into();
+    }
+
+    fn write_prefix(&mut self) -> io::Result<()> {
+        if !self.prefix.is_empty() {
+            let prefix = std::mem::take(&mut self.prefix);
+            self.inner.write_all(&prefix)?;
+            self.written += prefix.len();
+        }
+        Ok(())
+    }
+
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Starts redirecting the output into the new buffer.
+    ///
+    /// Captures can be nested; in this case, the innermost one receives the output.
+    pub(crate) fn capture(&mut self) {
+        self.captures.push(Vec::new());
+    }
+
+    /// Current position of the output, which changes whenever something is written or a new capture is started.
+    pub(crate) fn position(&self) -> (usize, usize) {
+        (
+            self.captures.len(),
+            self.captures.last().map_or(0, Vec::len),
+        )
+    }
+
+    /// Stops the innermost capture and returns everything written into it.
+    pub(crate) fn release(&mut self) -> Vec<u8> {
+        self.captures.pop().unwrap_or_default()
+    }
+}
+
+impl Output<Vec<u8>> {
+    /// Drops everything written after the given number of bytes, including the unfinished captures.
+    pub(crate) fn truncate(&mut self, written: usize) {
+        self.inner.truncate(written);
+        self.captures.clear();
+        self.written = written;
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.captures.last_mut() {
+            Some(capture) => capture.write(buf),
+            None => {
+                self.write_prefix()?;
+                let written = self.inner.write(buf)?;
+                self.written += written;
+                Ok(written)
+            }
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self.captures.last_mut() {
+            Some(capture) => capture.write_all(buf),
+            None => {
+                self.write_prefix()?;
+                self.inner.write_all(buf)?;
+                self.written += buf.len();
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Adapter, which allows [`Uneval`][crate::ser::Uneval] to write into [`fmt::Write`] implementations,
+/// e.g. directly into the `String`.
+///
+/// Created by [`Uneval::from_fmt`][crate::ser::Uneval::from_fmt].
+pub struct FmtWriter<F: fmt::Write> {
+    inner: F,
+}
+
+impl<F: fmt::Write> FmtWriter<F> {
+    /// Wraps the [`fmt::Write`] implementation.
+    pub fn new(inner: F) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped target, e.g. the `String` with the generated code.
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: fmt::Write> Write for FmtWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    /// Serializer always writes the complete UTF-8 sequences, so every chunk can be passed on as is.
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let code = std::str::from_utf8(buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        self.inner.write_str(code).map_err(io::Error::other)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
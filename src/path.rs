@@ -0,0 +1,312 @@
+//! Tracking of the current position inside the serialized value.
+//!
+//! Path is written in the Rust-like notation: `Root.field[3].0[key]`, where `Root` is the name
+//! of the outermost struct or enum (if any), `.field` and `.0` are struct fields and tuple positions,
+//! `[3]` is the sequence element and `[key]` is the map entry.
+
+use std::fmt;
+
+/// Single step from the container to its element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Segment {
+    /// Named struct field.
+    Field(&'static str),
+    /// Positional field of the tuple or tuple struct.
+    Position(usize),
+    /// Element of the sequence.
+    Index(usize),
+    /// Map entry; the key is stored only if it is printable.
+    Key(Option<String>),
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(name) => write!(f, ".{}", name),
+            Segment::Position(index) => write!(f, ".{}", index),
+            Segment::Index(index) => write!(f, "[{}]", index),
+            Segment::Key(Some(key)) => write!(f, "[{}]", key),
+            Segment::Key(None) => write!(f, "[..]"),
+        }
+    }
+}
+
+/// Name of the outermost value, if it has one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Root {
+    name: &'static str,
+    variant: Option<&'static str>,
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    segment: Option<Segment>,
+    next: usize,
+    /// Name of the value at the current segment, if it has one.
+    name: Option<Root>,
+}
+
+/// Current position of the serializer.
+#[derive(Debug, Default)]
+pub(crate) struct Path {
+    root: Option<Root>,
+    frames: Vec<Frame>,
+}
+
+impl Path {
+    /// Registers the name of the value being serialized, if it is the outermost one.
+    ///
+    /// The names of the nested values are not shown, but are kept for [`shape`][Path::shape].
+    pub(crate) fn name(&mut self, name: &'static str, variant: Option<&'static str>) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.name = Some(Root { name, variant });
+        } else if self.root.is_none() {
+            self.root = Some(Root { name, variant });
+        }
+    }
+
+    /// Forgets the name of the outermost value, if it was already serialized completely.
+    pub(crate) fn unname(&mut self) {
+        if self.frames.is_empty() {
+            self.root = None;
+        }
+    }
+
+    /// Whether this is the outermost unnamed value, i.e. the one matched by the empty pattern.
+    pub(crate) fn is_top(&self) -> bool {
+        self.root.is_none() && self.frames.is_empty()
+    }
+
+    /// Enters the container.
+    pub(crate) fn enter(&mut self) {
+        self.frames.push(Frame::default());
+    }
+
+    /// Leaves the container.
+    pub(crate) fn leave(&mut self) {
+        self.frames.pop();
+        self.unname();
+    }
+
+    fn set(&mut self, segment: Segment) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.segment = Some(segment);
+            frame.name = None;
+        }
+    }
+
+    /// Moves to the named field of the current container.
+    pub(crate) fn field(&mut self, name: &'static str) {
+        self.set(Segment::Field(name));
+    }
+
+    /// Moves to the next positional field of the current tuple-like container.
+    pub(crate) fn next_position(&mut self) {
+        let index = self.next_index();
+        self.set(Segment::Position(index));
+    }
+
+    /// Moves to the next element of the current sequence.
+    pub(crate) fn next_element(&mut self) {
+        let index = self.next_index();
+        self.set(Segment::Index(index));
+    }
+
+    /// Moves to the element of the current sequence with the given index, skipping the ones before it.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn element(&mut self, index: usize) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.next = index + 1;
+        }
+        self.set(Segment::Index(index));
+    }
+
+    /// Moves to the map entry with the given key.
+    pub(crate) fn key(&mut self, key: Option<String>) {
+        self.set(Segment::Key(key));
+    }
+
+    fn next_index(&mut self) -> usize {
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.next += 1;
+                frame.next - 1
+            }
+            None => 0,
+        }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.frames
+            .iter()
+            .filter_map(|frame| frame.segment.as_ref())
+    }
+}
+
+impl Path {
+    /// Position of the value without the sequence indices and map keys, but with the names of all the structs
+    /// and enum variants containing it, i.e. everything which determines its type.
+    pub(crate) fn shape(&self) -> String {
+        let mut shape = String::new();
+        let root = self.root.as_ref().map(|root| (None, Some(root)));
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| (frame.segment.as_ref(), frame.name.as_ref()));
+        for (segment, name) in root.into_iter().chain(frames) {
+            match segment {
+                Some(Segment::Index(_) | Segment::Key(_)) => shape.push_str("[]"),
+                Some(segment) => shape.push_str(&segment.to_string()),
+                None => {}
+            }
+            if let Some(Root { name, variant }) = name {
+                shape.push('<');
+                shape.push_str(name);
+                if let Some(variant) = variant {
+                    shape.push_str("::");
+                    shape.push_str(variant);
+                }
+                shape.push('>');
+            }
+        }
+        shape
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(root) = &self.root {
+            f.write_str(root.name)?;
+            if let Some(variant) = root.variant {
+                write!(f, "::{}", variant)?;
+            }
+        }
+        self.segments().try_for_each(|segment| segment.fmt(f))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternRoot {
+    Any,
+    Name(String),
+    Variant(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternSegment {
+    Field(String),
+    AnyField,
+    Item(String),
+    AnyItem,
+}
+
+impl PatternSegment {
+    fn matches(&self, segment: &Segment) -> bool {
+        match (self, segment) {
+            (PatternSegment::AnyField, Segment::Field(_) | Segment::Position(_)) => true,
+            (PatternSegment::Field(name), Segment::Field(field)) => name == field,
+            (PatternSegment::Field(name), Segment::Position(index)) => *name == index.to_string(),
+            (PatternSegment::AnyItem, Segment::Index(_) | Segment::Key(_)) => true,
+            (PatternSegment::Item(item), Segment::Index(index)) => *item == index.to_string(),
+            (PatternSegment::Item(item), Segment::Key(Some(key))) => item == key,
+            _ => false,
+        }
+    }
+}
+
+/// Pattern selecting some positions inside the serialized value.
+///
+/// It uses the same notation as the path itself, with `*` serving as a wildcard for any
+/// root name (`*.field`), any field (`Root.*`) or any element or map entry (`Root.items[*]`).
+/// Pattern without the root name (e.g. `[*].field`) matches only the values without name, like sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pattern {
+    root: Option<PatternRoot>,
+    segments: Vec<PatternSegment>,
+}
+
+impl Pattern {
+    /// Parses the pattern. This never fails: anything which is not a `.` or `[...]` is treated as a name.
+    pub(crate) fn parse(pattern: &str) -> Self {
+        let split = pattern.find(['.', '[']).unwrap_or(pattern.len());
+        let (root, mut rest) = pattern.split_at(split);
+        let root = match root {
+            "" => None,
+            "*" => Some(PatternRoot::Any),
+            _ => Some(match root.split_once("::") {
+                Some((name, variant)) => PatternRoot::Variant(name.into(), variant.into()),
+                None => PatternRoot::Name(root.into()),
+            }),
+        };
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(item) = rest.strip_prefix('[') {
+                let end = item.find(']').unwrap_or(item.len());
+                segments.push(match &item[..end] {
+                    "*" => PatternSegment::AnyItem,
+                    key => PatternSegment::Item(key.into()),
+                });
+                rest = item.get(end + 1..).unwrap_or("");
+            } else {
+                let field = rest.strip_prefix('.').unwrap_or(rest);
+                let end = field.find(['.', '[']).unwrap_or(field.len());
+                segments.push(match &field[..end] {
+                    "*" => PatternSegment::AnyField,
+                    name => PatternSegment::Field(name.into()),
+                });
+                rest = &field[end..];
+            }
+        }
+        Self { root, segments }
+    }
+
+    /// Checks whether the pattern matches the given path exactly.
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        let root_matches = match (&self.root, &path.root) {
+            (None, None) => true,
+            (Some(PatternRoot::Any), _) => true,
+            (Some(PatternRoot::Name(name)), Some(root)) => name == root.name,
+            (Some(PatternRoot::Variant(name, variant)), Some(root)) => {
+                name == root.name && Some(variant.as_str()) == root.variant
+            }
+            _ => false,
+        };
+        root_matches
+            && path.segments().count() == self.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(path.segments())
+                .all(|(pattern, segment)| pattern.matches(segment))
+    }
+}
+
+/// Set of per-path settings.
+///
+/// If several patterns match the same path, the one added last wins.
+#[derive(Debug, Clone)]
+pub(crate) struct PathMap<T> {
+    entries: Vec<(Pattern, T)>,
+}
+
+impl<T> Default for PathMap<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> PathMap<T> {
+    pub(crate) fn insert(&mut self, pattern: &str, value: T) {
+        self.entries.push((Pattern::parse(pattern), value));
+    }
+
+    pub(crate) fn get(&self, path: &Path) -> Option<&T> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(path))
+            .map(|(_, value)| value)
+    }
+}
@@ -0,0 +1,56 @@
+//! Full paths to the types, which serde doesn't provide; see [`Uneval::with_type_path`][crate::ser::Uneval::with_type_path].
+
+/// Type which knows its own path, to be registered with
+/// [`Uneval::with_type_path_of`][crate::ser::Uneval::with_type_path_of].
+///
+/// This is usually implemented by `#[derive(UnevalPath)]` from the `uneval-macros` crate.
+pub trait TypePath {
+    /// Name of the type, as it is seen by the serializer, i.e. taking `#[serde(rename)]` into account.
+    const NAME: &'static str;
+    /// Name of the type in Rust code.
+    const IDENT: &'static str;
+    /// Path to the module containing the type, as returned by [`module_path!`].
+    const MODULE: &'static str;
+
+    /// Path to the type relative to the crate root, e.g. `crate::model::Point`.
+    ///
+    /// The first segment of [`MODULE`][TypePath::MODULE], i.e. the name of the crate where the type is defined,
+    /// is replaced with `crate`, so that the path is valid when the type definition is shared between
+    /// the build script and the crate itself, e.g. with `#[path]` attribute.
+    fn path() -> String {
+        match Self::MODULE.split_once("::") {
+            Some((_, module)) => format!("crate::{}::{}", module, Self::IDENT),
+            None => format!("crate::{}", Self::IDENT),
+        }
+    }
+}
+
+/// Builds the list of type paths for [`Uneval::with_type_paths`][crate::ser::Uneval::with_type_paths].
+///
+/// Every type is identified by the last segment of its path, or by the name after `as`, which should be used
+/// for the types renamed with `#[serde(rename)]`:
+/// ```
+/// let paths = uneval::paths![crate::model::Point, crate::render::Point as RenderPoint];
+/// assert_eq!(
+///     paths,
+///     [("Point", "crate::model::Point"), ("RenderPoint", "crate::render::Point")]
+/// );
+/// ```
+#[macro_export]
+macro_rules! paths {
+    ($($first:ident $(:: $rest:ident)* $(as $name:ident)?),* $(,)?) => {
+        [$((
+            $crate::paths!(@name $first $(:: $rest)* $(as $name)?),
+            concat!(stringify!($first) $(, "::", stringify!($rest))*),
+        )),*]
+    };
+    (@name $($segment:ident)::+ as $name:ident) => {
+        stringify!($name)
+    };
+    (@name $last:ident) => {
+        stringify!($last)
+    };
+    (@name $first:ident $(:: $rest:ident)+) => {
+        $crate::paths!(@name $($rest)::+)
+    };
+}
@@ -0,0 +1,50 @@
+//! Statistics of the serialized value; see [`stats`][crate::stats] and [`Uneval::report`][crate::ser::Uneval::report].
+
+use std::collections::BTreeSet;
+
+/// Statistics gathered during serialization, e.g. to find out why the generated code is so large.
+///
+/// Every value is counted when it is serialized, so the values which are emitted several times
+/// (e.g. by the overrides) or not emitted at all (e.g. the deduplicated ones) are counted as is.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+    /// Length of the generated code, in bytes.
+    pub bytes: usize,
+    /// Number of structs of any kind, i.e. the named, tuple, newtype and unit ones.
+    pub structs: usize,
+    /// Number of enum variants of any kind.
+    pub variants: usize,
+    /// Number of elements in all sequences.
+    pub seq_elements: usize,
+    /// Number of entries in all maps.
+    pub map_entries: usize,
+    /// Number of strings, including the map keys.
+    pub strings: usize,
+    /// Number of integers and floating-point numbers.
+    pub numbers: usize,
+    /// Maximum nesting level of sequences, maps, tuples, structs and enum variants with fields.
+    pub max_depth: usize,
+    /// Total length of all strings and byte strings, in bytes.
+    pub payload_bytes: usize,
+    /// Length of the largest string or byte string, in bytes, with the path to it.
+    pub largest_payload: Option<(usize, String)>,
+    /// Lengths of all the tuples, i.e. the conversion runtimes which might be needed.
+    pub tuple_arities: BTreeSet<usize>,
+    /// Problems with the generated code, if they are collected -
+    /// see [`Uneval::with_diagnostics`][crate::ser::Uneval::with_diagnostics].
+    pub diagnostics: Vec<crate::Diagnostic>,
+}
+
+impl Report {
+    pub(crate) fn payload(&mut self, len: usize, path: impl FnOnce() -> String) {
+        self.payload_bytes += len;
+        if self
+            .largest_payload
+            .as_ref()
+            .is_none_or(|(largest, _)| len > *largest)
+        {
+            self.largest_payload = Some((len, path()));
+        }
+    }
+}
@@ -1,48 +1,2121 @@
 //! Implementation of the Uneval serializer.
 
-use crate::error::UnevalError;
+use crate::backend::{DefaultEmitter, Emitter};
+use crate::config::{
+    Constructor, Edition, EnumTag, Env, Header, IntFormat, MapMode, SeqMode, StrMode, TupleMode,
+    UnevalConfig,
+};
+use crate::dedup::Dedup;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error::{IdentContext, UnevalError};
+use crate::format::{CompactFormatter, Formatter, PrettyFormatter};
+pub use crate::output::FmtWriter;
+use crate::output::Output;
+use crate::path::{Path, PathMap};
+use crate::report::Report;
+use crate::sidecar::{Number, Sidecars};
 use serde::ser;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::Write;
 
 pub(crate) type SerResult = Result<(), UnevalError>;
 
+/// The way to finish the struct being serialized.
+enum StructEnd {
+    /// Struct literal, i.e. `Name {...}`.
+    Literal,
+    /// Struct literal with the rest of fields taken from `Default`, i.e. `Name {..., ..Default::default()}`.
+    Filled,
+    /// Constructor call, with the code closing it.
+    Call(String),
+    /// Enum with the tag, emitted as the variant found in the tag field.
+    Tagged {
+        name: &'static str,
+        tag: EnumTag,
+        variant: Option<String>,
+        /// Whether the braces for the fields of the internally tagged variant were opened.
+        opened: bool,
+    },
+}
+
+/// Function replacing the generated code of the value.
+type Override = Box<dyn Fn(&str) -> String>;
+type ProgressHook = Box<dyn FnMut(&Report)>;
+
+/// Entries of the map which might be emitted as the struct: key labels and the code of the values.
+type MapEntries = Vec<(Option<String>, Vec<u8>)>;
+
+/// The kind of expression which is expected at the current position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Any expression.
+    Runtime,
+    /// Constant expression, e.g. value in the static map.
+    Const,
+    /// Literal, e.g. key in the static map.
+    Literal,
+}
+
+/// State of the sequence which might be moved into the sidecar file.
+enum Blob {
+    /// Sidecars are disabled, or the sequence can't be moved there.
+    Off,
+    /// All the elements so far are bytes, and the sequence code is being captured.
+    Bytes(Vec<u8>),
+    /// All the elements so far are numbers of the same type (if any), stored as little-endian bytes,
+    /// and the sequence code is being captured.
+    Numbers(Option<Number>, Vec<u8>),
+}
+
 /// Main serializer implementation.
 ///
 /// Users are usually encouraged to use [`to_out_dir`][crate::funcs::to_out_dir] or, in special cases,
 /// [`to_file`][crate::funcs::to_file], [`write`][crate::funcs::write] or [`to_string`][crate::funcs::to_string].
+///
+/// # Customizing the generated code
+///
+/// The code emitted for every kind of Serde value is chosen by the options of the serializer, which depend
+/// on the traversal state - the path, the nesting and whether the value must be constant - and by the [`Emitter`],
+/// which turns the chosen options into code. The options are grouped by what they replace:
+/// - the collection code, with [`SeqMode`], [`MapMode`], [`TupleMode`] and [`StrMode`], globally or for the paths
+///   (e.g. [`SeqMode::Slice`] and [`with_const_context`][Self::with_const_context] for const-friendly code);
+/// - the struct code, with [`Constructor`] and [`with_type_path`][Self::with_type_path];
+/// - the code for any single value, with [`with_override_at`][Self::with_override_at];
+/// - the tuple conversion runtime, with [`with_tuple_trait`][Self::with_tuple_trait];
+/// - the code opening and closing collections, strings and literal tuples, with [`Emitter`];
+/// - the layout, with [`Formatter`].
+///
+/// The output in other forms is built on top of the same code: token trees with `to_tokens`, and statics with
+/// [`to_static`][crate::funcs::to_static].
 pub struct Uneval<W: Write> {
-    writer: W,
-    inside: bool,
+    writer: Output<W>,
+    /// Whether anything was written at the nesting level, i.e. whether the next entry needs the comma;
+    /// the last flag is for the current level.
+    levels: Vec<bool>,
+    empty_tuple_structs: HashSet<String>,
+    empty_tuple_variants: HashSet<(String, String)>,
+    fill_defaults: bool,
+    fill_defaults_for: HashSet<String>,
+    constructors: HashMap<String, Constructor>,
+    structs: Vec<StructEnd>,
+    /// For every struct being written: whether it might be the contents of the newtype variant of the internally
+    /// tagged enum (`Some(false)`, with its opening captured until the first field is known), or it is such contents
+    /// (`Some(true)`, so the variant must be closed after it).
+    internal_newtypes: Vec<Option<bool>>,
+    tagged_enums: HashMap<String, EnumTag>,
+    /// Variants of the adjacently tagged enums, as `Enum::Variant`, which are always emitted as newtype ones.
+    newtype_variants: HashSet<String>,
+    /// Variant of the adjacently tagged enum whose contents are expected at the given output position.
+    variant_content: Option<(String, (usize, usize))>,
+    tuple_mode: TupleMode,
+    tuple_modes_by_len: HashMap<usize, TupleMode>,
+    tuple_modes_at: PathMap<TupleMode>,
+    tuples: Vec<(TupleMode, usize)>,
+    seq_mode: SeqMode,
+    seq_modes_at: PathMap<SeqMode>,
+    seqs: Vec<SeqMode>,
+    map_mode: MapMode,
+    map_modes_at: PathMap<MapMode>,
+    /// Modes of the top-level sequence or map, detected from the type of the item being written.
+    item_modes: (Option<SeqMode>, Option<MapMode>),
+    collect_hints_at: PathMap<String>,
+    maps: Vec<MapMode>,
+    map_structs: Vec<(String, Vec<String>)>,
+    map_entries: Vec<Option<MapEntries>>,
+    str_mode: StrMode,
+    str_modes_at: PathMap<StrMode>,
+    int_format: IntFormat,
+    int_formats_at: PathMap<IntFormat>,
+    sort_maps: bool,
+    empty_as_default: bool,
+    /// Opening code of the sequences and maps which have no entries yet, written only with the first one.
+    pending_opens: Vec<Option<String>>,
+    sorted_seqs_at: PathMap<()>,
+    numeric_sidecars_at: PathMap<()>,
+    overrides_at: PathMap<Override>,
+    into_all: bool,
+    into_at: PathMap<()>,
+    /// Output position of the value which is converted with `.into()`, unless it is the `Option`.
+    pending_into: Option<(usize, usize)>,
+    /// Pending conversions of the outer values, restored when the nested ones are written.
+    outer_into: Vec<Option<(usize, usize)>>,
+    /// Output position right after the value whose type is inferred from its place, e.g. `"...".into()`.
+    inferred_end: Option<(usize, usize)>,
+    #[cfg(feature = "json")]
+    json_at: PathMap<()>,
+    #[cfg(any(feature = "postcard", feature = "bincode"))]
+    fallbacks_at: PathMap<crate::config::FallbackCodec>,
+    sorted: Vec<Option<Vec<Vec<u8>>>>,
+    context: Context,
+    contexts: Vec<Context>,
+    sidecars: Option<Sidecars>,
+    blobs: Vec<Blob>,
+    dedup: Option<Dedup>,
+    shared_tuples: Option<BTreeSet<usize>>,
+    tuple_trait: Option<String>,
+    tuple_structs_as_tuples: HashSet<String>,
+    /// Whether every tuple struct being serialized is emitted as tuple.
+    tuple_structs: Vec<bool>,
+    key_label: Option<(usize, Option<String>)>,
+    human_readable: bool,
+    env: Env,
+    edition: Edition,
+    /// The minor version of the oldest Rust 1.x which must compile the generated code.
+    rust_version: Option<u32>,
+    item_attributes: Vec<String>,
+    formatter: Box<dyn Formatter>,
+    emitter: Box<dyn Emitter>,
+    depth: usize,
+    path: Path,
+    report: Report,
+    type_paths: HashMap<String, String>,
+    type_paths_at: PathMap<String>,
+    diagnostics: Option<Vec<Diagnostic>>,
+    /// The least severity of the diagnostics which are turned into errors.
+    strict: Option<Severity>,
+    /// Whether the value is only checked, so that the invalid identifiers are reported as diagnostics.
+    dry_run: bool,
+    /// The hook to be called after every given number of elements and entries.
+    progress: Option<(usize, ProgressHook)>,
+}
+
+impl<F: std::fmt::Write> Uneval<FmtWriter<F>> {
+    /// Creates the serializer writing into the [`fmt::Write`][std::fmt::Write] implementation instead of
+    /// the [`io::Write`][std::io::Write] one, e.g. into the existing `String`.
+    ///
+    /// All the configuration methods are available as usual, and the target can be taken back with
+    /// [`into_writer`][Uneval::into_writer] and [`FmtWriter::into_inner`]:
+    /// ```
+    /// use serde::Serialize;
+    /// use uneval::{config::IntFormat, ser::Uneval};
+    ///
+    /// let mut uneval = Uneval::from_fmt(String::from("const MASK: u8 = ")).with_int_format(IntFormat::Hex);
+    /// 0xF0u8.serialize(&mut uneval)?;
+    /// uneval.write_raw(";")?;
+    /// assert_eq!(uneval.into_writer().into_inner(), "const MASK: u8 = 0xF0u8;");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn from_fmt(target: F) -> Self {
+        Self::new(FmtWriter::new(target))
+    }
 }
 
 impl<W: Write> Uneval<W> {
+    /// Creates the serializer writing the code into the given target, with the default configuration.
+    ///
+    /// The serializer can be used for several values in turn, e.g. to write them into one stream as parts
+    /// of some larger code; [`write_raw`][Uneval::write_raw] adds the code between them, and
+    /// [`into_writer`][Uneval::into_writer] returns the target at the end:
+    /// ```
+    /// use serde::Serialize;
+    /// use uneval::ser::Uneval;
+    ///
+    /// let mut uneval = Uneval::new(Vec::new());
+    /// uneval.write_raw("let ids: Vec<u8> = ")?;
+    /// vec![1u8, 2].serialize(&mut uneval)?;
+    /// uneval.write_raw(";\nlet name: String = ")?;
+    /// "uneval".serialize(&mut uneval)?;
+    /// uneval.write_raw(";")?;
+    /// assert_eq!(
+    ///     String::from_utf8(uneval.into_writer()).unwrap(),
+    ///     "let ids: Vec<u8> = vec![1u8,2u8].into_iter().collect();\nlet name: String = \"uneval\".into();"
+    /// );
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
     pub fn new(target: W) -> Self {
         Self {
-            writer: target,
-            inside: false,
+            writer: Output::new(target),
+            levels: vec![false],
+            empty_tuple_structs: HashSet::new(),
+            empty_tuple_variants: HashSet::new(),
+            fill_defaults: false,
+            fill_defaults_for: HashSet::new(),
+            constructors: Constructor::builtins()
+                .map(|(name, constructor)| (name.to_string(), constructor))
+                .collect(),
+            structs: Vec::new(),
+            internal_newtypes: Vec::new(),
+            tagged_enums: HashMap::new(),
+            newtype_variants: HashSet::new(),
+            variant_content: None,
+            tuple_mode: TupleMode::default(),
+            tuple_modes_by_len: HashMap::new(),
+            tuple_modes_at: PathMap::default(),
+            tuples: Vec::new(),
+            seq_mode: SeqMode::default(),
+            seq_modes_at: PathMap::default(),
+            seqs: Vec::new(),
+            map_mode: MapMode::default(),
+            map_modes_at: PathMap::default(),
+            item_modes: (None, None),
+            collect_hints_at: PathMap::default(),
+            maps: Vec::new(),
+            map_structs: Vec::new(),
+            map_entries: Vec::new(),
+            str_mode: StrMode::default(),
+            str_modes_at: PathMap::default(),
+            int_format: IntFormat::default(),
+            int_formats_at: PathMap::default(),
+            sort_maps: false,
+            empty_as_default: true,
+            pending_opens: Vec::new(),
+            sorted_seqs_at: PathMap::default(),
+            numeric_sidecars_at: PathMap::default(),
+            overrides_at: PathMap::default(),
+            into_all: false,
+            into_at: PathMap::default(),
+            pending_into: None,
+            outer_into: Vec::new(),
+            inferred_end: None,
+            #[cfg(feature = "json")]
+            json_at: PathMap::default(),
+            #[cfg(any(feature = "postcard", feature = "bincode"))]
+            fallbacks_at: PathMap::default(),
+            sorted: Vec::new(),
+            context: Context::Runtime,
+            contexts: Vec::new(),
+            sidecars: None,
+            blobs: Vec::new(),
+            dedup: None,
+            shared_tuples: None,
+            tuple_trait: None,
+            tuple_structs_as_tuples: HashSet::new(),
+            tuple_structs: Vec::new(),
+            key_label: None,
+            human_readable: true,
+            env: Env::default(),
+            edition: Edition::default(),
+            rust_version: None,
+            item_attributes: Vec::new(),
+            formatter: Box::new(CompactFormatter),
+            emitter: Box::new(DefaultEmitter),
+            depth: 0,
+            path: Path::default(),
+            report: Report::default(),
+            type_paths: HashMap::new(),
+            type_paths_at: PathMap::default(),
+            diagnostics: None,
+            strict: None,
+            dry_run: false,
+            progress: None,
+        }
+    }
+
+    /// Creates the serializer writing the code into the given target, with the given global options.
+    ///
+    /// The options for the specific paths or types can be added with the `with_*` methods afterwards:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::config::{SeqMode, UnevalConfig};
+    /// use uneval::ser::Uneval;
+    ///
+    /// let config = UnevalConfig { seq_mode: SeqMode::PlainVec, ..UnevalConfig::default() };
+    /// let mut out = Vec::new();
+    /// vec![vec![1u8]].serialize(&mut Uneval::with_config(&mut out, &config).with_seq_mode_at("*[*]", SeqMode::Slice))?;
+    /// assert_eq!(String::from_utf8(out).unwrap(), "vec![&[1u8]]");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_config(target: W, config: &UnevalConfig) -> Self {
+        let mut uneval = Self::new(target)
+            .with_tuple_mode(config.tuple_mode)
+            .with_seq_mode(config.seq_mode)
+            .with_map_mode(config.map_mode.clone())
+            .with_str_mode(config.str_mode)
+            .with_int_format(config.int_format)
+            .with_type_paths(config.type_paths.clone())
+            .with_into(config.into)
+            .with_sorted_maps(config.sorted_maps)
+            .with_empty_as_default(config.empty_as_default)
+            .with_fill_defaults(config.fill_defaults)
+            .with_human_readable(config.human_readable)
+            .with_env(config.env)
+            .with_edition(config.edition);
+        if let Some(path) = &config.tuple_trait {
+            uneval = uneval.with_tuple_trait(path.clone());
+        }
+        if config.const_context {
+            uneval = uneval.with_const_context();
+        }
+        if let Some(minor) = config.min_rust_version {
+            uneval = uneval.with_min_rust_version(minor);
+        }
+        if let Some(indent) = &config.indent {
+            uneval = uneval.with_indent(indent.clone());
+        }
+        if let Some(header) = &config.header {
+            uneval = uneval.with_header(header.clone());
+        }
+        uneval
+    }
+
+    /// Marks the struct with the given name as an empty tuple struct, i.e. `Name()`.
+    ///
+    /// Serde can't distinguish such structs from unit ones when `Serialize` is implemented
+    /// via [`serialize_unit_struct`][ser::Serializer::serialize_unit_struct], so by default
+    /// they are emitted as `Name`, which doesn't compile for `struct Name();`.
+    pub fn with_empty_tuple_struct(mut self, name: impl Into<String>) -> Self {
+        self.empty_tuple_structs.insert(name.into());
+        self
+    }
+
+    /// Marks the enum variant as an empty tuple variant, i.e. `Enum::Variant()`.
+    ///
+    /// This is the same as [`with_empty_tuple_struct`][Uneval::with_empty_tuple_struct],
+    /// but for variants serialized with [`serialize_unit_variant`][ser::Serializer::serialize_unit_variant].
+    pub fn with_empty_tuple_variant(
+        mut self,
+        name: impl Into<String>,
+        variant: impl Into<String>,
+    ) -> Self {
+        self.empty_tuple_variants
+            .insert((name.into(), variant.into()));
+        self
+    }
+
+    /// Makes every struct end with `..Default::default()`, so that the fields skipped during serialization
+    /// (e.g. with `#[serde(skip)]` or `#[serde(skip_serializing_if = "...")]`) are filled with defaults.
+    ///
+    /// This requires the structs to implement [`Default`] and can't be used in constant context.
+    /// Struct variants of enums are not affected, since they can't be built from the base value.
+    pub fn with_fill_defaults(mut self, fill: bool) -> Self {
+        self.fill_defaults = fill;
+        self
+    }
+
+    /// Makes the struct with the given name end with `..Default::default()`.
+    ///
+    /// See [`with_fill_defaults`][Uneval::with_fill_defaults] for details.
+    pub fn with_fill_defaults_for(mut self, name: impl Into<String>) -> Self {
+        self.fill_defaults_for.insert(name.into());
+        self
+    }
+
+    /// Emits the struct or enum with the given name by its full path, e.g. `crate::model::Point`,
+    /// so that it doesn't need to be in scope where the code is included.
+    ///
+    /// The name is the one seen by the serializer, i.e. taking `#[serde(rename)]` into account. Since this is all
+    /// the serializer knows about the type, the different types with the same name can be told apart only by
+    /// their location in the value - see [`with_type_path_at`][Uneval::with_type_path_at].
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct Point { x: u8 }
+    ///
+    /// let mut out = Vec::new();
+    /// Point { x: 1 }.serialize(
+    ///     &mut uneval::ser::Uneval::new(&mut out).with_type_path("Point", "crate::model::Point"),
+    /// )?;
+    /// assert_eq!(String::from_utf8(out).unwrap(), "crate::model::Point {x: 1u8}");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_type_path(mut self, name: impl Into<String>, path: impl Into<String>) -> Self {
+        self.type_paths.insert(name.into(), path.into());
+        self
+    }
+
+    /// Registers several type paths at once, e.g. the ones built by [`paths!`][crate::paths!].
+    ///
+    /// See [`with_type_path`][Uneval::with_type_path] for details.
+    pub fn with_type_paths<N: Into<String>, P: Into<String>>(
+        mut self,
+        paths: impl IntoIterator<Item = (N, P)>,
+    ) -> Self {
+        self.type_paths.extend(
+            paths
+                .into_iter()
+                .map(|(name, path)| (name.into(), path.into())),
+        );
+        self
+    }
+
+    /// Registers the path of the type implementing [`TypePath`][crate::TypePath], e.g. with
+    /// `#[derive(UnevalPath)]` from the `uneval-macros` crate.
+    ///
+    /// See [`with_type_path`][Uneval::with_type_path] for details.
+    pub fn with_type_path_of<T: crate::TypePath>(self) -> Self {
+        self.with_type_path(T::NAME, T::path())
+    }
+
+    /// Emits the struct or enum at the given path in the value by the given type path.
+    ///
+    /// This takes precedence over [`with_type_path`][Uneval::with_type_path], so it can be used for the types
+    /// which have the same name as the registered ones. See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at]
+    /// for the path syntax.
+    pub fn with_type_path_at(mut self, path: &str, type_path: impl Into<String>) -> Self {
+        self.type_paths_at.insert(path, type_path.into());
+        self
+    }
+
+    /// Emits the map as the struct with the given name, if it has all of the given fields as its keys.
+    ///
+    /// This is intended for the structs with `#[serde(flatten)]` field, which are serialized as maps, with the fields
+    /// of the flattened value mixed with the struct's own ones. Since the flattened value can't be reconstructed,
+    /// the entries with other keys are dropped, and the rest of the struct is taken from `Default`,
+    /// as with [`with_fill_defaults`][Uneval::with_fill_defaults] - e.g. `Config {name: ..., retries: ...,
+    /// ..Default::default()}`. So the struct must implement [`Default`], and the flattened value must be equal
+    /// to the default one to be preserved. Every dropped entry is reported as the warning, so that
+    /// [`with_strict`][Uneval::with_strict] can reject them.
+    ///
+    /// The hints are checked in order they were added, and the first one with all the fields present is used.
+    /// Maps matching no hint are emitted as usual.
+    pub fn with_map_as_struct(mut self, name: impl Into<String>, fields: &[&str]) -> Self {
+        self.map_structs.push((
+            name.into(),
+            fields.iter().map(|field| field.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Emits the struct with the given name as the constructor call, instead of the struct literal.
+    ///
+    /// Constructors for some standard library types, which can't be emitted as struct literals since their fields
+    /// are private, are registered by default: [`Duration`][std::time::Duration],
+    /// [`SystemTime`][std::time::SystemTime] and [`RangeInclusive`][std::ops::RangeInclusive].
+    /// Note that the `NonZero*` integers are serialized as plain numbers, without any name,
+    /// so they must be handled with [`with_override_at`][Uneval::with_override_at] instead.
+    pub fn with_constructor(mut self, name: impl Into<String>, constructor: Constructor) -> Self {
+        self.constructors.insert(name.into(), constructor);
+        self
+    }
+
+    /// Emits the struct with the given name as the struct literal, even if it has the built-in constructor.
+    ///
+    /// This is necessary if some struct of your own has the same name as one of the types listed
+    /// in [`with_constructor`][Uneval::with_constructor].
+    pub fn with_struct_literal(mut self, name: &str) -> Self {
+        self.constructors.remove(name);
+        self
+    }
+
+    /// Emits the enum with the given name according to its tagged representation, i.e. `#[serde(tag = "...")]`.
+    ///
+    /// Serde serializes such enums as structs named after the enum, with the variant name stored in the tag field,
+    /// so without this option the generated code would construct the nonexistent struct. See [`EnumTag`]
+    /// for the supported representations.
+    ///
+    /// Untagged enums, i.e. `#[serde(untagged)]`, are serialized as the variant contents only, so the serializer
+    /// can't tell them apart from other values; use [`with_override_at`][Uneval::with_override_at] to add
+    /// the variant path to their code, e.g. `|code| code.replacen("Shape", "Shape::Circle", 1)` for the struct
+    /// variant, which is serialized as a struct named after the enum.
+    pub fn with_tagged_enum(mut self, name: impl Into<String>, tag: EnumTag) -> Self {
+        self.tagged_enums.insert(name.into(), tag);
+        self
+    }
+
+    /// Emits the variant of the adjacently tagged enum as the newtype one, e.g. `Shape::Circle(Circle {...})`.
+    ///
+    /// The contents of the newtype variant holding the tuple, or the struct with the same name as the variant,
+    /// are serialized exactly like the ones of the tuple or struct variant, so by default they are emitted
+    /// as the latter, e.g. `Shape::Circle {...}`. This option marks the variant as the newtype one instead:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::{config::EnumTag, ser::Uneval};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Circle {
+    ///     radius: u32,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// #[serde(tag = "t", content = "c")]
+    /// enum Shape {
+    ///     Circle(Circle),
+    /// }
+    ///
+    /// let tag = EnumTag::Adjacent { tag: "t".into(), content: "c".into() };
+    /// let mut out = Vec::new();
+    /// let mut uneval = Uneval::new(&mut out)
+    ///     .with_tagged_enum("Shape", tag)
+    ///     .with_newtype_variant("Shape", "Circle");
+    /// Shape::Circle(Circle { radius: 3 }).serialize(&mut uneval)?;
+    /// assert_eq!(String::from_utf8(out).unwrap(), "Shape::Circle(Circle {radius: 3u32})");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_newtype_variant(mut self, name: &str, variant: &str) -> Self {
+        self.newtype_variants
+            .insert(format!("{}::{}", name, variant));
+        self
+    }
+
+    /// Sets the value returned by [`is_human_readable`][ser::Serializer::is_human_readable], which is `true` by default.
+    ///
+    /// Many types choose their representation based on this value. The human-readable one is usually a string,
+    /// which can't be converted back to the original type with `into` - for these, consider
+    /// [`StrMode::Parse`]. The compact one is usually built of numbers, sequences
+    /// and tuples, e.g. [`Ipv4Addr`][std::net::Ipv4Addr] is serialized as the array of octets, and can be turned
+    /// into the value with [`with_override_at`][Uneval::with_override_at], e.g. by wrapping the array
+    /// into `Ipv4Addr::from`. Which one is easier to use depends on the type.
+    ///
+    /// The functions without the serializer argument, like [`to_string`][crate::to_string] or
+    /// [`to_file`][crate::to_file], always use the human-readable representation; to change it, pass the
+    /// [`UnevalConfig`] to their `_with` counterparts, e.g. [`to_string_with`][crate::to_string_with]
+    /// or [`to_file_with`][crate::to_file_with], or use [`Module::with_human_readable`][crate::Module::with_human_readable]:
+    /// ```
+    /// use uneval::config::{TupleMode, UnevalConfig};
+    ///
+    /// let addr: std::net::Ipv4Addr = "10.0.0.1".parse().unwrap();
+    /// assert_eq!(uneval::to_string(addr)?, "\"10.0.0.1\".into()");
+    /// let config = UnevalConfig {
+    ///     human_readable: false,
+    ///     tuple_mode: TupleMode::Array,
+    ///     ..UnevalConfig::default()
+    /// };
+    /// assert_eq!(uneval::to_string_with(addr, &config)?, "[10u8,0u8,0u8,1u8]");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+
+    /// Sets the environment where the generated code will be used, e.g. `#![no_std]` crate.
+    ///
+    /// See [`Env`] for details.
+    pub fn with_env(mut self, env: Env) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Sets the edition of the crate where the generated code will be used, so that only the names which are
+    /// keywords in it are written as raw identifiers:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::config::{Edition, UnevalConfig};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Task {
+    ///     r#async: bool,
+    /// }
+    ///
+    /// let task = Task { r#async: true };
+    /// assert_eq!(uneval::to_string(&task)?, "Task {r#async: true}");
+    /// let config = UnevalConfig { edition: Edition::Rust2015, ..UnevalConfig::default() };
+    /// let code = uneval::to_string_with(&task, &config)?;
+    /// assert_eq!(code, "Task {async: true}");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    /// By default, the code is generated for the latest edition. See [`Edition`] for details.
+    pub fn with_edition(mut self, edition: Edition) -> Self {
+        self.edition = edition;
+        self
+    }
+
+    /// Sets the oldest Rust version which must compile the generated code, as the minor version of Rust 1.x,
+    /// so that only the constructions available on it are emitted.
+    ///
+    /// The newer constructions are replaced by the older ones where possible: [`SeqMode::FromArray`]
+    /// (Rust 1.44) falls back to [`SeqMode::PlainVec`], and [`MapMode::FromArray`] (Rust 1.56) falls back to
+    /// [`MapMode::Collect`], and the [numeric sidecars][Self::with_numeric_sidecar_at] (Rust 1.34) are not
+    /// created, leaving the numbers inline. Everything else is reported as [`Unavailable`][UnevalError::Unavailable] error,
+    /// i.e. 128-bit integers (Rust 1.26) and the statics initialized with [`StaticKind::Lazy`] (Rust 1.80;
+    /// with `once_cell` feature, `StaticKind::OnceCell` can be used instead):
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use uneval::config::{MapMode, UnevalConfig};
+    ///
+    /// let map = BTreeMap::from([(1u8, 2u8)]);
+    /// let config = UnevalConfig {
+    ///     map_mode: MapMode::FromArray { type_name: "BTreeMap".into() },
+    ///     min_rust_version: Some(51),
+    ///     ..UnevalConfig::default()
+    /// };
+    /// assert_eq!(uneval::to_string_with(&map, &config)?, "vec![(1u8,2u8)].into_iter().collect()");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    ///
+    /// [`StaticKind::Lazy`]: crate::config::StaticKind::Lazy
+    pub fn with_min_rust_version(mut self, minor: u32) -> Self {
+        self.rust_version = Some(minor);
+        self
+    }
+
+    /// Whether the construction stabilized in the given Rust 1.x can be used in the generated code.
+    fn rust_has(&self, minor: u32) -> bool {
+        self.rust_version.is_none_or(|target| target >= minor)
+    }
+
+    /// Fails if the construction stabilized in the given Rust 1.x can't be used in the generated code.
+    pub(crate) fn require_rust(&self, what: &'static str, minor: u32) -> SerResult {
+        match self.rust_version {
+            Some(target) if target < minor => Err(UnevalError::Unavailable {
+                what,
+                required: minor,
+                target,
+                path: self.path.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds the header to the generated code: the comment before it, and the attributes on the generated items.
+    ///
+    /// The comment is placed before the code, so the generated expression can still be [`include!`]d.
+    /// See [`Header`] for details.
+    pub fn with_header(mut self, header: Header) -> Self {
+        self.writer.set_prefix(header.comment());
+        self.with_item_attributes(header.attributes())
+    }
+
+    pub(crate) fn with_item_attributes(mut self, attributes: &[String]) -> Self {
+        self.item_attributes = attributes.to_vec();
+        self
+    }
+
+    /// Sets the way to emit tuples and arrays, unless overridden for the specific length or path.
+    pub fn with_tuple_mode(mut self, mode: TupleMode) -> Self {
+        self.tuple_mode = mode;
+        self
+    }
+
+    /// Sets the way to emit tuples and arrays of the given length, unless overridden for the specific path.
+    pub fn with_tuple_mode_for_len(mut self, len: usize, mode: TupleMode) -> Self {
+        self.tuple_modes_by_len.insert(len, mode);
+        self
+    }
+
+    /// Sets the way to emit tuples and arrays at the given path, e.g. `Asset.position` or `Mesh.vertices[*]`.
+    ///
+    /// Path consists of the name of outermost struct or enum (or `*` to match any), followed by the field names
+    /// (`.field`, or `.0` for tuple elements), sequence indices (`[1]`) and map keys (`[key]`);
+    /// `*` can be used in place of any field name, index or key.
+    pub fn with_tuple_mode_at(mut self, path: &str, mode: TupleMode) -> Self {
+        self.tuple_modes_at.insert(path, mode);
+        self
+    }
+
+    /// Makes the tuple conversion runtime use the existing `FromTuple`-like trait at the given path,
+    /// e.g. `::uneval::convert::FromTuple`, instead of defining its own.
+    ///
+    /// This allows the tuples to be converted into any type implementing the trait, not only into arrays
+    /// and tuples - see [`FromTuple`][crate::convert::FromTuple]. The path should be absolute,
+    /// since the runtime is emitted in its own module when the value is part of [`Module`][crate::Module].
+    pub fn with_tuple_trait(mut self, path: impl Into<String>) -> Self {
+        self.tuple_trait = Some(path.into());
+        self
+    }
+
+    /// Emits the tuple struct with the given name as the tuple, according to the [tuple mode][TupleMode].
+    ///
+    /// This is meant for the types which are serialized as tuple structs, but can't be constructed
+    /// with the tuple struct syntax, since their fields are named or private - e.g. `glam::Vec3`,
+    /// which can then be created by the conversion runtime with [`with_tuple_trait`][Uneval::with_tuple_trait].
+    pub fn with_tuple_struct_as_tuple(mut self, name: impl Into<String>) -> Self {
+        self.tuple_structs_as_tuples.insert(name.into());
+        self
+    }
+
+    /// Sets the way to emit sequences, unless overridden for the specific path.
+    ///
+    /// This applies to byte sequences, too.
+    pub fn with_seq_mode(mut self, mode: SeqMode) -> Self {
+        self.seq_mode = mode;
+        self
+    }
+
+    /// Sets the way to emit sequences at the given path.
+    ///
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_seq_mode_at(mut self, path: &str, mode: SeqMode) -> Self {
+        self.seq_modes_at.insert(path, mode);
+        self
+    }
+
+    /// Sets the way to emit maps, unless overridden for the specific path.
+    pub fn with_map_mode(mut self, mode: MapMode) -> Self {
+        self.map_mode = mode;
+        self
+    }
+
+    /// Sets the way to emit maps at the given path.
+    ///
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_map_mode_at(mut self, path: &str, mode: MapMode) -> Self {
+        self.map_modes_at.insert(path, mode);
+        self
+    }
+
+    /// Emits the sequence or map at the given path with the type hint, i.e. `.collect::<type_name>()`
+    /// instead of `.collect()`, e.g. for the field of the generic struct, which type isn't known otherwise.
+    ///
+    /// The hint is used only by [`SeqMode::VecCollect`] and [`MapMode::Collect`], and the collected value
+    /// isn't converted afterwards, so the hint must be the exact type of the field.
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct Index<C> {
+    ///     entries: C,
+    /// }
+    ///
+    /// let mut out = Vec::new();
+    /// Index { entries: vec![1u8] }.serialize(
+    ///     &mut uneval::ser::Uneval::new(&mut out).with_collect_hint_at("Index.entries", "BTreeSet<u8>"),
+    /// )?;
+    /// assert_eq!(
+    ///     String::from_utf8(out)?,
+    ///     "Index {entries: vec![1u8].into_iter().collect::<BTreeSet<u8>>()}"
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_collect_hint_at(mut self, path: &str, type_name: impl Into<String>) -> Self {
+        self.collect_hints_at.insert(path, type_name.into());
+        self
+    }
+
+    /// Sets the way to emit strings, unless overridden for the specific path.
+    pub fn with_str_mode(mut self, mode: StrMode) -> Self {
+        self.str_mode = mode;
+        self
+    }
+
+    /// Sets the way to emit strings at the given path.
+    ///
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax. For example, the struct
+    /// with both owned and borrowed strings needs [`StrMode::Borrowed`] only for the latter:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::{config::StrMode, ser::Uneval};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Command {
+    ///     name: &'static str,
+    ///     help: String,
+    /// }
+    ///
+    /// let command = Command { name: "run", help: "Runs the task".into() };
+    /// let mut out = Vec::new();
+    /// command.serialize(&mut Uneval::new(&mut out).with_str_mode_at("Command.name", StrMode::Borrowed))?;
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "Command {name: \"run\",help: \"Runs the task\".into()}"
+    /// );
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_str_mode_at(mut self, path: &str, mode: StrMode) -> Self {
+        self.str_modes_at.insert(path, mode);
+        self
+    }
+
+    /// Sets the way to write integers, unless overridden for the specific path.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::config::IntFormat;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Register {
+    ///     offset: u16,
+    ///     mask: u32,
+    /// }
+    ///
+    /// let mut out = Vec::new();
+    /// let register = Register { offset: 16, mask: 0xD000_0000 };
+    /// register.serialize(
+    ///     &mut uneval::ser::Uneval::new(&mut out).with_int_format_at("Register.mask", IntFormat::GroupedHex),
+    /// )?;
+    /// assert_eq!(String::from_utf8(out).unwrap(), "Register {offset: 16u16,mask: 0xD000_0000u32}");
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_int_format(mut self, format: IntFormat) -> Self {
+        self.int_format = format;
+        self
+    }
+
+    /// Sets the way to write integers at the given path.
+    ///
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_int_format_at(mut self, path: &str, format: IntFormat) -> Self {
+        self.int_formats_at.insert(path, format);
+        self
+    }
+
+    /// Sets whether the map entries should be sorted (by their generated code) before being written.
+    ///
+    /// This is disabled by default, so that the entries are emitted in the order they are serialized, which is
+    /// kept by the ordered maps like `BTreeMap` and the maps preserving insertion order, like `IndexMap`.
+    /// Enable it for the maps with random iteration order, like `HashMap`, so that they always yield the same code:
+    /// ```
+    /// use std::collections::HashMap;
+    /// use uneval::config::UnevalConfig;
+    ///
+    /// let map: HashMap<_, _> = (0..10u8).map(|i| (i, ())).collect();
+    /// let config = UnevalConfig { sorted_maps: true, ..UnevalConfig::default() };
+    /// let code = uneval::to_string_with(&map, &config)?;
+    /// assert!(code.starts_with("vec![(0u8,()),(1u8,()),"));
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    ///
+    /// Note that the sorted map is buffered in memory before being written, and the keys are compared
+    /// as code, i.e. `10u8` goes before `2u8`.
+    pub fn with_sorted_maps(mut self, sort: bool) -> Self {
+        self.sort_maps = sort;
+        self
+    }
+
+    /// Sets whether the empty sequences and maps should be emitted as `Default::default()`.
+    ///
+    /// This is enabled by default, since `vec![].into_iter().collect()` is both noisy and often can't be inferred
+    /// in the generic context, e.g. inside `Some(...)`. With the [collect hint][Uneval::with_collect_hint_at],
+    /// the hinted type is used explicitly, e.g. `<Vec<u8>>::default()`. Disable this for the collections which
+    /// don't implement `Default`. Slices and static sets and maps are never affected.
+    pub fn with_empty_as_default(mut self, enabled: bool) -> Self {
+        self.empty_as_default = enabled;
+        self
+    }
+
+    /// Emits the value at the given path as the `serde_json::json!` invocation, e.g. for the `serde_json::Value` fields.
+    ///
+    /// See [`to_json_expr`][crate::json::to_json_expr] for details, and [`with_tuple_mode_at`][Uneval::with_tuple_mode_at]
+    /// for the path syntax.
+    #[cfg(feature = "json")]
+    pub fn with_json_at(mut self, path: &str) -> Self {
+        self.json_at.insert(path, ());
+        self
+    }
+
+    /// Embeds the value at the given path as the binary blob, which is deserialized when the generated code runs.
+    ///
+    /// This is the fallback for the parts of the value which can't be emitted as code, e.g. the types with
+    /// private fields or the ones which serialize themselves as strings. The value is serialized with the `codec`
+    /// instead of `Uneval`, and the generated code becomes e.g. `::postcard::from_bytes(b"...").unwrap()` - or,
+    /// if the sidecars are enabled and the blob is large enough, `::postcard::from_bytes(include_bytes!("...")).unwrap()`.
+    /// The rest of the value is emitted as usual, so only this part pays the deserialization cost.
+    ///
+    /// The generated code requires the codec crate and `serde` as the runtime dependencies, and the type
+    /// at this path must implement `Deserialize`; see [`FallbackCodec`][crate::config::FallbackCodec].
+    /// The blob can't be deserialized in constant context, so the path must not be inside the `const` item
+    /// or the slice. See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    #[cfg(any(feature = "postcard", feature = "bincode"))]
+    pub fn with_fallback_at(mut self, path: &str, codec: crate::config::FallbackCodec) -> Self {
+        self.fallbacks_at.insert(path, codec);
+        self
+    }
+
+    /// Enables sorting of the sequence elements at the given path, e.g. for the `HashSet` fields.
+    ///
+    /// Unlike maps, which are sorted [all at once][Uneval::with_sorted_maps], sequences are sorted only at the
+    /// given paths, since for most of them the order matters. See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_sorted_seq_at(mut self, path: &str) -> Self {
+        self.sorted_seqs_at.insert(path, ());
+        self
+    }
+
+    /// Replaces the code of the value at the given path with the result of `emit`, which gets the code
+    /// generated for this value.
+    ///
+    /// This is useful for the types which serialize themselves as something which can't be used
+    /// to construct them directly, e.g. `NonZeroU32` serialized as plain integer:
+    /// ```
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     ports: Vec<std::num::NonZeroU16>,
+    /// }
+    ///
+    /// let mut out = Vec::new();
+    /// let config = Config { ports: vec![std::num::NonZeroU16::new(80).unwrap()] };
+    /// config.serialize(
+    ///     &mut uneval::ser::Uneval::new(&mut out)
+    ///         .with_override_at("Config.ports[*]", |code| format!("NonZeroU16::new({}).unwrap()", code)),
+    /// )?;
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "Config {ports: vec![NonZeroU16::new(80u16).unwrap()].into_iter().collect()}"
+    /// );
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    ///
+    /// Overrides apply to struct fields, tuple positions, sequence elements and map values, but not to map keys.
+    /// See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_override_at(mut self, path: &str, emit: impl Fn(&str) -> String + 'static) -> Self {
+        self.overrides_at.insert(path, Box::new(emit));
+        self
+    }
+
+    /// Appends `.into()` to every nested value, so that it is converted into the type expected in its place,
+    /// e.g. into `Box<T>` or `Arc<T>`, or into the newtype wrapper implementing `From`.
+    ///
+    /// This is useful for the recursive types, which can't be constructed from the plain struct literals:
+    /// ```
+    /// # use serde::Serialize;
+    /// #[derive(Serialize)]
+    /// struct Node {
+    ///     value: u32,
+    ///     next: Option<Box<Node>>,
+    /// }
+    ///
+    /// let list = Node { value: 1, next: Some(Box::new(Node { value: 2, next: None })) };
+    /// let config = uneval::config::UnevalConfig { into: true, ..Default::default() };
+    /// assert_eq!(
+    ///     uneval::to_string_with(&list, &config)?,
+    ///     "Node {value: 1u32.into(),next: Some(Node {value: 2u32.into(),next: None}.into())}"
+    /// );
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    ///
+    /// Values of `Option` are converted inside `Some`, since `Option<T>` can't be converted into `Option<Box<T>>`.
+    /// Like overrides, conversion applies to struct fields, tuple positions, sequence elements and map values,
+    /// but not to map keys and to the outermost value; the overridden values are not converted, either.
+    /// Values in constant context are left as is, since `.into()` can't be called there.
+    ///
+    /// Values whose type is already inferred from their place, i.e. the collected sequences and maps,
+    /// strings emitted as `"...".into()`, tuples converted at runtime and `Default::default()`, are left as is,
+    /// since another `.into()` would make their type ambiguous. Note that the conversion still hides the type
+    /// of the converted value from the code around it, so in the generic contexts
+    /// [`with_into_at`][Uneval::with_into_at] might be necessary instead.
+    pub fn with_into(mut self, enabled: bool) -> Self {
+        self.into_all = enabled;
+        self
+    }
+
+    /// Appends `.into()` to the value at the given path; see [`with_into`][Uneval::with_into].
+    ///
+    /// Unlike the global setting, this reports the [`NotConst`][UnevalError::NotConst] error for the values
+    /// in constant context. See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_into_at(mut self, path: &str) -> Self {
+        self.into_at.insert(path, ());
+        self
+    }
+
+    /// Moves the strings and byte sequences of at least `threshold` bytes into separate files.
+    ///
+    /// See [`to_file_with_sidecars`][crate::funcs::to_file_with_sidecars] for details.
+    pub(crate) fn with_sidecars(mut self, sidecars: Sidecars) -> Self {
+        self.sidecars = Some(sidecars);
+        self
+    }
+
+    pub(crate) fn take_sidecars(&mut self) -> Option<Sidecars> {
+        self.sidecars.take()
+    }
+
+    /// Moves the sequence of numbers at the given path into the binary sidecar, e.g. for the large `Vec<f32>` tables.
+    ///
+    /// By default, only the byte sequences are moved into sidecars; with this option, the sequence of
+    /// primitive numbers of the same type (e.g. `f32` or `u64`) which takes at least the sidecar threshold
+    /// in bytes is written into the `.bin` file as little-endian binary data, and the generated code decodes
+    /// it with `from_le_bytes`, e.g.
+    /// `include_bytes!("data.rs.3c1f8e0a9b2d4c57.bin").chunks_exact(4).map(|chunk| f32::from_le_bytes(...)).collect()`.
+    /// The numbers are copied out of the included bytes, so the file can be used on targets with any
+    /// endianness, and its alignment doesn't matter. Floating-point numbers are stored bit-exact, including
+    /// the NaN payloads.
+    ///
+    /// This takes effect only when the sidecars are enabled, e.g. with
+    /// [`to_file_with_sidecars_with`][crate::funcs::to_file_with_sidecars_with], and only for sequences emitted
+    /// at runtime, i.e. not for [`SeqMode::Slice`]. Sequences containing anything but the numbers of
+    /// one type are emitted as usual. See [`with_tuple_mode_at`][Uneval::with_tuple_mode_at] for the path syntax.
+    pub fn with_numeric_sidecar_at(mut self, path: &str) -> Self {
+        self.numeric_sidecars_at.insert(path, ());
+        self
+    }
+
+    /// Enables deduplication of the repeated structs and enum variants, which code is at least `threshold` bytes long.
+    ///
+    /// Every such value which occurs more than once is emitted only once, as a closure in the block wrapping
+    /// the whole value, and each occurrence is replaced by the call to this closure:
+    /// ```
+    /// # #[derive(Debug, PartialEq)] struct Style { color: u32 }
+    /// # #[derive(Debug, PartialEq)] struct Node { style: Style, children: Vec<Node> }
+    /// let _: Vec<Node> = {
+    ///     let __u0 = || Style { color: 16777215u32 };
+    ///     vec![
+    ///         Node { style: __u0(), children: Default::default() },
+    ///         Node { style: __u0(), children: Default::default() },
+    ///     ].into_iter().collect()
+    /// };
+    /// ```
+    /// Only the structs and enum variants are deduplicated, since their type is known from their name.
+    /// The generic ones might have the same code for the different type parameters, e.g. when their fields
+    /// are emitted as `.into()` or `.collect()`, so only the values at the same position are shared, i.e. with
+    /// the same path to them, not counting the sequence indices and map keys, and inside the same enum variants.
+    ///
+    /// Note that the whole value is buffered in memory before being written.
+    pub fn with_dedup(mut self, threshold: usize) -> Self {
+        self.dedup
+            .get_or_insert_with(Dedup::default)
+            .set_threshold(threshold);
+        self
+    }
+
+    /// Breaks the deeply nested values into the sequence of `let` bindings.
+    ///
+    /// Every struct or enum variant nested at the multiple of `depth` levels is emitted as a separate binding
+    /// in the block wrapping the whole value, and referenced by name:
+    /// ```
+    /// # #[derive(Debug, PartialEq)] struct Node { value: u32, next: Vec<Node> }
+    /// let _: Node = {
+    ///     let __u0 = Node { value: 2u32, next: vec![].into_iter().collect() };
+    ///     Node { value: 1u32, next: vec![__u0].into_iter().collect() }
+    /// };
+    /// ```
+    /// This way, the nesting of the generated expression is bounded, so that the compiler doesn't hit
+    /// the recursion limit on deep values, like the long linked lists. Note that only the structs and
+    /// enum variants are flattened, not the sequences nested directly in each other.
+    ///
+    /// This can be combined with [`with_dedup`][Uneval::with_dedup]; then the bindings which are used more
+    /// than once are emitted as closures. Like with deduplication, the whole value is buffered in memory.
+    pub fn with_flatten_depth(mut self, depth: usize) -> Self {
+        self.dedup
+            .get_or_insert_with(Dedup::default)
+            .set_flatten(depth);
+        self
+    }
+
+    /// Emits the value as a constant expression, e.g. for the `const` or `static` item.
+    ///
+    /// In this mode, strings and byte strings are borrowed, i.e. emitted as `&'static str` and `&'static [u8]`,
+    /// since their owned counterparts can't be created in constant context. Tuples must be emitted either as
+    /// [arrays][TupleMode::Array] (which is the default here, instead of [`TupleMode::Convert`]) or as
+    /// [plain tuples][TupleMode::Tuple]; sequences are allowed only as [slices][SeqMode::Slice], and maps
+    /// are not allowed at all. Anything which can't be emitted is reported as [`NotConst`][UnevalError::NotConst]
+    /// error, with the path to the offending value:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::config::{SeqMode, UnevalConfig};
+    /// use uneval::ser::Uneval;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Font {
+    ///     name: &'static str,
+    ///     size: (u8, u8),
+    ///     glyphs: Vec<u16>,
+    /// }
+    ///
+    /// let font = Font { name: "mono", size: (8, 16), glyphs: vec![0x41, 0x42] };
+    /// let config = UnevalConfig { const_context: true, ..UnevalConfig::default() };
+    /// let err = uneval::to_string_with(&font, &config).unwrap_err();
+    /// assert_eq!(err.to_string(), "sequence at `Font.glyphs` can't be emitted in constant context");
+    ///
+    /// let mut out = Vec::new();
+    /// font.serialize(&mut Uneval::with_config(&mut out, &config).with_seq_mode_at("Font.glyphs", SeqMode::Slice))?;
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     "Font {name: \"mono\",size: [8u8,16u8],glyphs: &[65u16,66u16]}"
+    /// );
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn with_const_context(mut self) -> Self {
+        self.context = Context::Const;
+        self
+    }
+
+    /// Makes the tuples use the conversion runtime shared by the whole module, instead of the inline one.
+    ///
+    /// The lengths of the tuples are collected, to emit the runtime with [`take_shared_tuples`][Uneval::take_shared_tuples].
+    pub(crate) fn with_shared_tuples(mut self) -> Self {
+        self.shared_tuples = Some(BTreeSet::new());
+        self
+    }
+
+    /// Makes the serializer collect the problems with the generated code which don't stop the serialization,
+    /// e.g. the floats which have no literal form or the maps emitted in random order. They are available
+    /// in the [`report`][Uneval::report] afterwards, so that the build script can show them or fail:
+    /// ```
+    /// # use serde::Serialize;
+    /// let mut out = Vec::new();
+    /// let mut uneval = uneval::ser::Uneval::new(&mut out).with_diagnostics();
+    /// (1.0f32, f32::NAN).serialize(&mut uneval)?;
+    /// let diagnostics = uneval.report().diagnostics;
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].severity, uneval::Severity::Error);
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    /// See [`check`][crate::check] for the dry run, which reports the invalid identifiers this way, too, instead of
+    /// failing on the first one.
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics.get_or_insert_with(Vec::new);
+        self
+    }
+
+    /// Makes the serializer fail with [`UnevalError::Strict`] on the problems of the given severity or worse,
+    /// instead of generating the code which probably won't work as intended. See [`check`][crate::check]
+    /// for the kinds of problems found.
+    ///
+    /// E.g. with [`Severity::Error`], the floats which have no literal form are rejected, and with
+    /// [`Severity::Note`], even the unit structs not marked with
+    /// [`with_empty_tuple_struct`][Uneval::with_empty_tuple_struct] are, since they might be the empty tuple structs:
+    /// ```
+    /// # use serde::Serialize;
+    /// use uneval::{error::UnevalError, ser::Uneval, Severity};
+    ///
+    /// let err = (1.0f32, f32::NAN)
+    ///     .serialize(&mut Uneval::new(std::io::sink()).with_strict(Severity::Error))
+    ///     .unwrap_err();
+    /// assert!(matches!(err, UnevalError::Strict { path, .. } if path == ".1"));
+    /// ```
+    pub fn with_strict(mut self, severity: Severity) -> Self {
+        self.strict = Some(severity);
+        self
+    }
+
+    /// Makes the serializer collect the problems with the generated code for the dry run, to be obtained with
+    /// [`take_diagnostics`][Uneval::take_diagnostics].
+    ///
+    /// Invalid identifiers are reported as diagnostics instead of errors, so that the rest of the value is checked.
+    pub(crate) fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self.with_diagnostics()
+    }
+
+    pub(crate) fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take().unwrap_or_default()
+    }
+
+    pub(crate) fn current_path(&self) -> String {
+        self.path.to_string()
+    }
+
+    /// Serializes the value as the element of the sequence with the given index, so that the errors point to it,
+    /// e.g. when the sequence is split between several serializers.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn serialize_element_at(
+        &mut self,
+        index: usize,
+        value: &impl ser::Serialize,
+    ) -> SerResult {
+        self.path.enter();
+        self.path.element(index);
+        value
+            .serialize(&mut *self)
+            .map_err(|err| err.at(&self.path))?;
+        self.path.leave();
+        Ok(())
+    }
+
+    pub(crate) fn written(&self) -> usize {
+        self.writer.written()
+    }
+
+    pub(crate) fn tuple_trait(&self) -> Option<&str> {
+        self.tuple_trait.as_deref()
+    }
+
+    pub(crate) fn take_shared_tuples(&mut self) -> BTreeSet<usize> {
+        self.shared_tuples.take().unwrap_or_default()
+    }
+
+    /// Starts the map, which entries are written in the order they are serialized, even if maps are sorted.
+    pub(crate) fn start_unsorted_map(&mut self) -> SerResult {
+        let sort = std::mem::replace(&mut self.sort_maps, false);
+        let result = ser::Serializer::serialize_map(&mut *self, None).map(|_| ());
+        self.sort_maps = sort;
+        result
+    }
+
+    /// Returns the target the code was written into.
+    pub fn into_writer(self) -> W {
+        self.writer.into_inner()
+    }
+
+    /// Writes the value of the item with the given type.
+    ///
+    /// The top-level map or set of the item of type `phf::Map` or `phf::Set` is emitted as `phf` one,
+    /// unless the mode for the top-level value is set explicitly; this doesn't affect the other items.
+    pub(crate) fn write_item_value(
+        &mut self,
+        type_name: &str,
+        write: impl FnOnce(&mut Self) -> SerResult,
+    ) -> SerResult {
+        #[cfg(feature = "phf")]
+        {
+            let type_name = type_name.trim_start().trim_start_matches("::");
+            if type_name.starts_with("phf::Map<") {
+                self.item_modes.1 = Some(MapMode::Phf);
+            } else if type_name.starts_with("phf::Set<") {
+                self.item_modes.0 = Some(SeqMode::PhfSet);
+            }
+        }
+        #[cfg(not(feature = "phf"))]
+        let _ = type_name;
+        let result = write(self);
+        self.item_modes = (None, None);
+        result
+    }
+
+    /// Switches between the constant and runtime context, for the items of different kinds.
+    pub(crate) fn set_const_context(&mut self, is_const: bool) {
+        self.context = if is_const {
+            Context::Const
+        } else {
+            Context::Runtime
+        };
+    }
+
+    /// Writes the start of the item, e.g. `pub const NAME: Type = `, before the value.
+    ///
+    /// The item gets the attributes from the header, followed by its own ones.
+    pub(crate) fn write_item_start(
+        &mut self,
+        prefix: &str,
+        name: &str,
+        type_name: &str,
+        attributes: &[&str],
+    ) -> SerResult {
+        self.write_item_attributes(attributes)?;
+        self.write_item_head(prefix, name, type_name)
+    }
+
+    pub(crate) fn write_item_attributes(&mut self, attributes: &[&str]) -> SerResult {
+        let header = self.item_attributes.iter().map(String::as_str);
+        for attribute in header.chain(attributes.iter().copied()) {
+            writeln!(self.writer, "{}", attribute)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the start of the item without the attributes, e.g. inside the accessor function.
+    pub(crate) fn write_item_head(
+        &mut self,
+        prefix: &str,
+        name: &str,
+        type_name: &str,
+    ) -> SerResult {
+        write!(self.writer, "{} ", prefix)?;
+        self.write_ident(name, IdentContext::ItemName)?;
+        write!(self.writer, ": {} = ", type_name)?;
+        Ok(())
+    }
+
+    /// Writes the start of the accessor function, e.g. `pub fn data() -> &'static Type { `,
+    /// before the item holding the value.
+    pub(crate) fn write_accessor_start(
+        &mut self,
+        prefix: &str,
+        accessor: &str,
+        type_name: &str,
+        attributes: &[&str],
+    ) -> SerResult {
+        self.write_item_attributes(attributes)?;
+        write!(self.writer, "{} ", prefix)?;
+        self.write_ident(accessor, IdentContext::ItemName)?;
+        write!(self.writer, "() -> &'static {} {{ ", type_name)?;
+        Ok(())
+    }
+
+    /// Writes the end of the accessor function, returning the reference to the item.
+    pub(crate) fn write_accessor_end(&mut self, name: &str) -> SerResult {
+        write!(self.writer, "; &")?;
+        self.write_ident(name, IdentContext::ItemName)?;
+        writeln!(self.writer, " }}")?;
+        Ok(())
+    }
+
+    /// Writes the code around the values as is, e.g. to join several of them.
+    ///
+    /// The code isn't checked in any way, and it is written at the current position - so this shouldn't be
+    /// called while the value is being serialized.
+    pub fn write_raw(&mut self, code: &str) -> SerResult {
+        write!(self.writer, "{}", code)?;
+        Ok(())
+    }
+
+    /// Writes the end of the item, after the value.
+    pub(crate) fn write_item_end(&mut self) -> SerResult {
+        writeln!(self.writer, ";")?;
+        Ok(())
+    }
+
+    /// Enables pretty-printing, with every element of every non-empty struct, sequence or map on its own line,
+    /// indented with the given string according to its nesting depth.
+    ///
+    /// This doesn't change the meaning of the generated code - pretty-printed output always yields the same value
+    /// as the compact one.
+    ///
+    /// This is a shorthand for [`with_formatter`][Self::with_formatter] with [`PrettyFormatter`].
+    pub fn with_indent(self, indent: impl Into<String>) -> Self {
+        self.with_formatter(PrettyFormatter::new(indent))
+    }
+
+    /// Sets the formatter deciding the whitespace and separators in the generated code;
+    /// see [`Formatter`] for details. By default, the output is [compact][CompactFormatter].
+    pub fn with_formatter(mut self, formatter: impl Formatter + 'static) -> Self {
+        self.formatter = Box::new(formatter);
+        self
+    }
+
+    /// Sets the emitter deciding the code emitted for sequences, maps, strings and other values;
+    /// see [`Emitter`] for details. By default, the code is the one described in the [crate-level documentation](crate).
+    pub fn with_emitter(mut self, emitter: impl Emitter + 'static) -> Self {
+        self.emitter = Box::new(emitter);
+        self
+    }
+
+    /// Statistics of everything serialized so far; see [`Report`] for details.
+    ///
+    /// ```
+    /// # use serde::Serialize;
+    /// let mut out = Vec::new();
+    /// let mut uneval = uneval::ser::Uneval::new(&mut out);
+    /// vec![("a", 1u8)].serialize(&mut uneval)?;
+    /// let report = uneval.report();
+    /// assert_eq!((report.seq_elements, report.strings, report.numbers), (1, 1, 1));
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    pub fn report(&self) -> Report {
+        Report {
+            bytes: self.writer.written(),
+            diagnostics: self.diagnostics.clone().unwrap_or_default(),
+            ..self.report.clone()
+        }
+    }
+
+    /// Calls `hook` with the [statistics][Uneval::report] of everything serialized so far after every `every`
+    /// sequence elements and map entries, counted together, e.g. to show the progress of the very large
+    /// serialization in the build script:
+    /// ```
+    /// # use serde::Serialize;
+    /// let mut uneval = uneval::ser::Uneval::new(Vec::new()).with_progress(100_000, |report| {
+    ///     eprintln!("{} entries, {} bytes", report.seq_elements + report.map_entries, report.bytes);
+    /// });
+    /// vec![0u8; 250_000].serialize(&mut uneval)?;
+    /// # Ok::<(), uneval::error::UnevalError>(())
+    /// ```
+    /// The elements of tuples and arrays are not counted, since they can't be too many.
+    pub fn with_progress(mut self, every: usize, hook: impl FnMut(&Report) + 'static) -> Self {
+        self.progress = Some((every.max(1), Box::new(hook)));
+        self
+    }
+
+    /// Counts the sequence element or map entry, calling the progress hook if it is due.
+    fn count_entry(&mut self, seq: bool) {
+        if seq {
+            self.report.seq_elements += 1;
+        } else {
+            self.report.map_entries += 1;
+        }
+        let count = self.report.seq_elements + self.report.map_entries;
+        if self
+            .progress
+            .as_ref()
+            .is_some_and(|(every, _)| count % every == 0)
+        {
+            let report = self.report();
+            if let Some((_, hook)) = &mut self.progress {
+                hook(&report);
+            }
+        }
+    }
+
+    fn tuple_mode(&self, len: usize) -> TupleMode {
+        let mode = self
+            .tuple_modes_at
+            .get(&self.path)
+            .or_else(|| self.tuple_modes_by_len.get(&len))
+            .copied()
+            .unwrap_or(self.tuple_mode);
+        // The conversion runtime can't be used in constant context, so the arrays are the best guess there.
+        match (mode, self.context) {
+            (TupleMode::Convert, Context::Const) => TupleMode::Array,
+            (mode, _) => mode,
+        }
+    }
+
+    fn write_integer(&mut self, value: impl crate::helpers::Integer, suffix: &str) -> SerResult {
+        let format = self
+            .int_formats_at
+            .get(&self.path)
+            .copied()
+            .unwrap_or(self.int_format);
+        self.report.numbers += 1;
+        crate::helpers::write_integer(&mut self.writer, value, format, suffix)
+    }
+
+    /// Reports the problem of the generated code at the current path: records it as [`Diagnostic`], if they are
+    /// collected, or fails with [`Strict`][UnevalError::Strict] error, if its severity is high enough.
+    ///
+    /// The message is built only if it is actually used.
+    fn diagnose(&mut self, severity: Severity, message: impl FnOnce() -> String) -> SerResult {
+        if self.strict.is_some_and(|strict| severity >= strict) {
+            return Err(UnevalError::Strict {
+                message: message(),
+                path: self.path.to_string(),
+            });
+        }
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.push(Diagnostic {
+                severity,
+                message: message(),
+                path: self.path.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Writes the opening code of the current sequence or map, if it has not been written yet.
+    fn open_pending(&mut self) -> SerResult {
+        if let Some(open) = self.pending_opens.last_mut().and_then(Option::take) {
+            write!(self.writer, "{}", open)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the current sequence or map, replacing the whole code with `Default::default()`
+    /// if the container is empty and this is allowed.
+    fn close_container(&mut self, close: &str, default: bool) -> SerResult {
+        match self.pending_opens.pop().flatten() {
+            Some(_) if default && self.empty_as_default => {
+                let hint = self.collect_hints_at.get(&self.path);
+                let inferred = hint.is_none();
+                let code = self.emitter.default_value(hint.map(String::as_str));
+                write!(self.writer, "{}", code)?;
+                if inferred {
+                    self.mark_inferred();
+                }
+                Ok(())
+            }
+            Some(open) => {
+                write!(self.writer, "{}", open)?;
+                self.write_close(close)
+            }
+            None => self.write_close(close),
+        }
+    }
+
+    /// Writes the end of the sequence or map, with the type hint for the current path, if the end is `collect()`.
+    fn write_close(&mut self, close: &str) -> SerResult {
+        match (
+            close.strip_suffix("collect()"),
+            self.collect_hints_at.get(&self.path),
+        ) {
+            (Some(start), Some(hint)) => write!(self.writer, "{}collect::<{}>()", start, hint)?,
+            (Some(_), None) => {
+                write!(self.writer, "{}", close)?;
+                self.mark_inferred();
+            }
+            (None, _) => write!(self.writer, "{}", close)?,
+        }
+        Ok(())
+    }
+
+    /// Records the printable representation of the primitive value, if it is the map key being serialized.
+    ///
+    /// The label is taken from the key itself during its serialization, and not by serializing it separately,
+    /// so that the keys which can be serialized only once (e.g. the ones forwarded from deserializer) work too.
+    fn label(&mut self, value: impl std::fmt::Display) {
+        if let Some((depth, label)) = &mut self.key_label {
+            if *depth == self.depth {
+                *label = Some(value.to_string());
+            }
+        }
+    }
+
+    /// Whether anything was written at the current nesting level.
+    fn inside(&self) -> bool {
+        self.levels.last().copied().unwrap_or(false)
+    }
+
+    fn set_inside(&mut self, inside: bool) {
+        if let Some(level) = self.levels.last_mut() {
+            *level = inside;
         }
     }
 
     fn start_sub(&mut self) -> &mut Self {
-        self.inside = false;
+        self.levels.push(false);
+        self.depth += 1;
+        self.report.max_depth = self.report.max_depth.max(self.depth);
+        self.path.enter();
         self
     }
 
+    fn end_sub(&mut self) -> SerResult {
+        // The outer level keeps its own state, which was set when the container was started as its entry.
+        if self.levels.pop() == Some(true) {
+            self.formatter
+                .end_entries(&mut self.writer, self.depth - 1)?;
+        }
+        self.depth -= 1;
+        self.path.leave();
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) -> SerResult {
+        self.formatter.newline(&mut self.writer, depth)?;
+        Ok(())
+    }
+
     fn comma(&mut self) -> SerResult {
-        if self.inside {
-            write!(self.writer, ",")?;
+        let first = !self.inside();
+        self.formatter
+            .begin_entry(&mut self.writer, first, self.depth)?;
+        self.set_inside(true);
+        Ok(())
+    }
+
+    /// Checks that the value of the given kind can be emitted at the current position, if a literal is required.
+    fn not_literal(&self, what: &'static str) -> SerResult {
+        match self.context {
+            Context::Literal => Err(UnevalError::NotLiteral {
+                what,
+                path: self.path.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the current position allows non-constant expressions.
+    fn runtime_only(&self, what: &'static str) -> SerResult {
+        match self.context {
+            Context::Runtime => Ok(()),
+            Context::Const => Err(UnevalError::NotConst {
+                what,
+                path: self.path.to_string(),
+            }),
+            Context::Literal => Err(UnevalError::NotLiteral {
+                what,
+                path: self.path.to_string(),
+            }),
+        }
+    }
+
+    fn alloc_only(&self, what: &'static str) -> SerResult {
+        match self.env {
+            Env::Core => Err(UnevalError::RequiresAlloc {
+                what,
+                path: self.path.to_string(),
+            }),
+            Env::Std | Env::Alloc => Ok(()),
+        }
+    }
+
+    fn write_ident(&mut self, name: &str, context: IdentContext) -> SerResult {
+        if context == IdentContext::TypeName {
+            let path = self
+                .type_paths_at
+                .get(&self.path)
+                .or_else(|| self.type_paths.get(name));
+            if let Some(path) = path {
+                write!(self.writer, "{}", path)?;
+                return Ok(());
+            }
+        }
+        match crate::ident::write_ident(&mut self.writer, name, context, self.edition) {
+            Err(err @ UnevalError::InvalidIdentifier { .. }) if self.dry_run => {
+                self.diagnose(Severity::Error, || err.to_string())?;
+                write!(self.writer, "{}", name)?;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn write_variant(&mut self, name: &str, variant: &str) -> SerResult {
+        self.write_ident(name, IdentContext::TypeName)?;
+        write!(self.writer, "::")?;
+        self.write_ident(variant, IdentContext::VariantName)
+    }
+
+    /// Serializes the field of the tagged enum: the tag is emitted as the variant path, and the rest of fields
+    /// as its contents.
+    fn serialize_tagged_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> SerResult {
+        let Some(StructEnd::Tagged {
+            name,
+            tag,
+            variant,
+            opened,
+        }) = self.structs.last()
+        else {
+            return Ok(());
+        };
+        let (name, opened) = (*name, *opened);
+        match (tag, variant) {
+            (tag, None) if key == tag.tag() => {
+                let Some(variant) = crate::tagged::variant_name(value) else {
+                    return Err(self.invalid_tag(name));
+                };
+                self.write_variant(name, &variant)?;
+                if let Some(StructEnd::Tagged { variant: found, .. }) = self.structs.last_mut() {
+                    *found = Some(variant);
+                }
+                Ok(())
+            }
+            (EnumTag::Internal { .. }, Some(_)) => {
+                if !opened {
+                    write!(self.writer, " {{")?;
+                    if let Some(StructEnd::Tagged { opened, .. }) = self.structs.last_mut() {
+                        *opened = true;
+                    }
+                }
+                self.comma()?;
+                self.write_ident(key, IdentContext::FieldName)?;
+                self.formatter.begin_field_value(&mut self.writer)?;
+                self.serialize_nested(value)
+            }
+            (EnumTag::Adjacent { content, .. }, Some(variant)) if key == content => {
+                let newtype = self
+                    .newtype_variants
+                    .contains(&format!("{}::{}", name, variant));
+                let variant = (!newtype).then(|| variant.clone());
+                self.serialize_variant_content(variant, value)
+            }
+            _ => Err(self.invalid_tag(name)),
+        }
+    }
+
+    /// Serializes the contents of the adjacently tagged variant, after the variant path.
+    ///
+    /// Tuple and struct variants are recognized by the tuple or struct starting right at the beginning
+    /// of the contents (see [`take_variant_content`][Uneval::take_variant_content]); anything else is the newtype
+    /// variant, whose contents must be wrapped in parentheses. Without the `variant`, the contents are always
+    /// the newtype ones.
+    fn serialize_variant_content<T: ?Sized + ser::Serialize>(
+        &mut self,
+        variant: Option<String>,
+        value: &T,
+    ) -> SerResult {
+        let detect = variant.is_some();
+        self.writer.capture();
+        let outer = std::mem::replace(
+            &mut self.variant_content,
+            variant.map(|variant| (variant, self.writer.position())),
+        );
+        // The contents are on the same level as the variant itself.
+        self.depth -= 1;
+        self.serialize_nested(value)?;
+        self.depth += 1;
+        let content = std::mem::replace(&mut self.variant_content, outer);
+        let code = self.writer.release();
+        // The contents which were not taken as the tuple or struct ones are the newtype ones.
+        let newtype = !detect || content.is_some();
+        if newtype {
+            write!(self.writer, "(")?;
+            self.writer.write_all(&code)?;
+            write!(self.writer, ")")?;
+        } else {
+            self.writer.write_all(&code)?;
         }
-        self.inside = true;
         Ok(())
     }
 
+    /// Checks whether the tuple or struct with the given name is the contents of the adjacently tagged variant.
+    fn take_variant_content(&mut self, name: Option<&str>) -> bool {
+        let found = matches!(
+            &self.variant_content,
+            Some((variant, at)) if *at == self.writer.position() && name.is_none_or(|name| name == variant)
+        );
+        if found {
+            self.variant_content = None;
+        }
+        found
+    }
+
+    /// Starts capturing the struct, if it might be the contents of the newtype variant of the internally tagged enum.
+    ///
+    /// Serde serializes such variants as the inner struct with the tag field added first, so the variant
+    /// is recognized only when the first field is known; see [`end_internal_newtype`][Uneval::end_internal_newtype].
+    fn start_internal_newtype(&mut self) {
+        let internal = self
+            .tagged_enums
+            .values()
+            .any(|tag| matches!(tag, EnumTag::Internal { .. }));
+        if internal {
+            self.writer.capture();
+        }
+        self.internal_newtypes.push(internal.then_some(false));
+    }
+
+    /// Stops capturing the struct started with [`start_internal_newtype`][Uneval::start_internal_newtype]
+    /// on its first field (or on its end, if it has none). If the field is the tag of the internally tagged enum,
+    /// the struct is wrapped into the variant found there, and the tag field is skipped, so this returns `true`.
+    fn end_internal_newtype<T: ?Sized + ser::Serialize>(
+        &mut self,
+        field: Option<(&'static str, &T)>,
+    ) -> Result<bool, UnevalError> {
+        let Some(state @ Some(false)) = self.internal_newtypes.last_mut() else {
+            return Ok(false);
+        };
+        *state = None;
+        let code = self.writer.release();
+        let variant = field.and_then(|(key, value)| {
+            let variant = crate::tagged::variant_name(value)?;
+            let mut enums = self
+                .tagged_enums
+                .iter()
+                .filter(|(_, tag)| matches!(tag, EnumTag::Internal { tag } if tag == key));
+            Some((
+                key,
+                enums.next()?.0.clone(),
+                enums.next().is_none(),
+                variant,
+            ))
+        });
+        let Some((key, name, unique, variant)) = variant else {
+            self.writer.write_all(&code)?;
+            return Ok(false);
+        };
+        if !unique {
+            return Err(UnevalError::AmbiguousTag {
+                tag: key,
+                path: self.path.to_string(),
+            });
+        }
+        self.write_variant(&name, &variant)?;
+        write!(self.writer, "(")?;
+        self.writer.write_all(&code)?;
+        if let Some(state) = self.internal_newtypes.last_mut() {
+            *state = Some(true);
+        }
+        Ok(true)
+    }
+
+    fn invalid_tag(&self, name: &'static str) -> UnevalError {
+        UnevalError::InvalidTag {
+            name,
+            path: self.path.to_string(),
+        }
+    }
+
     fn serialize_item(&mut self, item: impl ser::Serialize) -> SerResult {
         self.comma()?;
-        item.serialize(self)?;
+        self.serialize_nested(&item)
+    }
+
+    /// Serializes the nested value at the current path, applying the override set for it, if any.
+    ///
+    /// This is called at every level of nesting, so everything except the serialization itself is moved
+    /// out of line, to keep the stack usage for deeply nested values low.
+    #[inline(always)]
+    fn serialize_nested<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> SerResult {
+        #[cfg(feature = "json")]
+        if self.is_json() {
+            return self.serialize_json(value);
+        }
+        #[cfg(any(feature = "postcard", feature = "bincode"))]
+        if let Some(codec) = self.fallback() {
+            return self.serialize_fallback(value, codec);
+        }
+        let overridden = self.start_override();
+        let into = !overridden && self.start_into();
+        if let Err(err) = value.serialize(&mut *self) {
+            return Err(err.at(&self.path));
+        }
+        self.end_nested(overridden, into)
+    }
+
+    /// Finishes the nested value, applying the override or the conversion started for it.
+    #[inline(never)]
+    fn end_nested(&mut self, overridden: bool, into: bool) -> SerResult {
+        self.end_override(overridden)?;
+        self.end_into(into)
+    }
+
+    /// Whether the nested value at the current path is emitted as the `serde_json::json!` invocation.
+    #[cfg(feature = "json")]
+    #[inline(never)]
+    fn is_json(&self) -> bool {
+        self.json_at.get(&self.path).is_some()
+    }
+
+    /// The codec of the fallback blob the nested value at the current path is embedded as, if any.
+    #[cfg(any(feature = "postcard", feature = "bincode"))]
+    #[inline(never)]
+    fn fallback(&self) -> Option<crate::config::FallbackCodec> {
+        self.fallbacks_at.get(&self.path).copied()
+    }
+
+    #[cfg(feature = "json")]
+    #[inline(never)]
+    fn serialize_json<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> SerResult {
+        self.runtime_only("`serde_json::json!` invocation")?;
+        crate::json::write_json(&mut self.writer, value).map_err(|err| err.at(&self.path))
+    }
+
+    #[cfg(any(feature = "postcard", feature = "bincode"))]
+    #[inline(never)]
+    fn serialize_fallback<T: ?Sized + ser::Serialize>(
+        &mut self,
+        value: &T,
+        codec: crate::config::FallbackCodec,
+    ) -> SerResult {
+        self.runtime_only("fallback blob")?;
+        let blob = codec.encode(value).map_err(|err| err.at(&self.path))?;
+        self.report.payload(blob.len(), || self.path.to_string());
+        let bytes = match &mut self.sidecars {
+            Some(sidecars) if blob.len() >= sidecars.threshold() => {
+                sidecars.include(&blob, false, false, self.env)?
+            }
+            _ => format!("b\"{}\"", blob.escape_ascii()),
+        };
+        write!(self.writer, "{}", codec.decode(&bytes))?;
+        Ok(())
+    }
+
+    /// Starts capturing the code of the nested value, if it has an override.
+    #[inline(never)]
+    fn start_override(&mut self) -> bool {
+        let overridden = self.overrides_at.get(&self.path).is_some();
+        if overridden {
+            self.writer.capture();
+        }
+        overridden
+    }
+
+    /// Writes the captured code of the nested value, as replaced by the override for the current path.
+    #[inline(never)]
+    fn end_override(&mut self, overridden: bool) -> SerResult {
+        if overridden {
+            let code = String::from_utf8(self.writer.release())?;
+            if let Some(emit) = self.overrides_at.get(&self.path) {
+                write!(self.writer, "{}", emit(&code))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts capturing the code of the nested value, if it must be converted with `.into()`.
+    ///
+    /// The previously pending conversion is saved, to be restored by [`end_into`][Uneval::end_into].
+    #[inline(never)]
+    fn start_into(&mut self) -> bool {
+        if self.into_at.get(&self.path).is_none()
+            && !(self.into_all && self.context == Context::Runtime)
+        {
+            return false;
+        }
+        self.writer.capture();
+        let outer = self.pending_into.replace(self.writer.position());
+        self.outer_into.push(outer);
+        true
+    }
+
+    /// Writes the captured code of the nested value with `.into()`, unless it was already converted
+    /// inside `Some`.
+    #[inline(never)]
+    fn end_into(&mut self, into: bool) -> SerResult {
+        if !into {
+            return Ok(());
+        }
+        let outer = self.outer_into.pop().flatten();
+        let pending = std::mem::replace(&mut self.pending_into, outer);
+        if self.into_at.get(&self.path).is_some() {
+            self.runtime_only("`.into()` conversion")?;
+        }
+        if pending.is_some() {
+            self.release_into()
+        } else {
+            let code = self.writer.release();
+            self.writer.write_all(&code)?;
+            Ok(())
+        }
+    }
+
+    /// Takes the pending conversion, if the `Option` value starts right at its position.
+    fn take_into(&mut self) -> bool {
+        let found = self.pending_into == Some(self.writer.position());
+        if found {
+            self.pending_into = None;
+        }
+        found
+    }
+
+    /// Marks the value which was just written as the one whose type is inferred from its place.
+    fn mark_inferred(&mut self) {
+        self.inferred_end = Some(self.writer.position());
+    }
+
+    /// Stops capturing the code of the converted value and writes it with `.into()`, wrapping it in parentheses
+    /// if the method call would bind too tightly.
+    ///
+    /// Values whose type is inferred from their place, e.g. `Default::default()` or `vec![...].into_iter().collect()`,
+    /// already are of the expected type, and are left as is, since `.into()` would make their type ambiguous.
+    fn release_into(&mut self) -> SerResult {
+        let inferred = self.inferred_end == Some(self.writer.position());
+        let code = self.writer.release();
+        if inferred {
+            self.writer.write_all(&code)?;
+        } else if matches!(code.first(), Some(b'-' | b'&')) {
+            write!(self.writer, "(")?;
+            self.writer.write_all(&code)?;
+            write!(self.writer, ").into()")?;
+        } else {
+            self.writer.write_all(&code)?;
+            write!(self.writer, ".into()")?;
+        }
+        Ok(())
+    }
+
+    /// Starts the compound value, i.e. anything except primitives and unit values.
+    ///
+    /// With deduplication enabled, the code of the value is captured, if it might be replaced by the binding.
+    fn begin_value(&mut self, named: bool) {
+        if let Some(dedup) = &mut self.dedup {
+            if dedup.begin(named && self.context == Context::Runtime) {
+                self.writer.capture();
+            }
+        }
+    }
+
+    /// Ends the compound value, which was started with [`begin_value`][Uneval::begin_value].
+    fn end_value(&mut self) -> SerResult {
+        let Some(dedup) = &mut self.dedup else {
+            return Ok(());
+        };
+        if !dedup.end() {
+            return Ok(());
+        }
+        let code = self.writer.release();
+        if !dedup.is_outermost() {
+            let code = dedup.hoist(code, self.path.shape(), self.depth);
+            self.writer.write_all(&code)?;
+            return Ok(());
+        }
+        let hoisted = dedup.finish(code);
+        if hoisted.bindings.is_empty() {
+            self.writer.write_all(&hoisted.value)?;
+            return Ok(());
+        }
+        write!(self.writer, "{{")?;
+        for binding in hoisted.bindings {
+            self.newline(self.depth + 1)?;
+            self.writer.write_all(&binding)?;
+        }
+        self.newline(self.depth + 1)?;
+        self.writer.write_all(&hoisted.value)?;
+        self.newline(self.depth)?;
+        write!(self.writer, "}}")?;
+        Ok(())
+    }
+
+    /// Writes the map with the string keys as the struct, according to the first matching hint
+    /// set with [`with_map_as_struct`][Uneval::with_map_as_struct].
+    ///
+    /// Returns `false` if no hint matches. The entries which are not the fields are dropped, and reported
+    /// as warnings.
+    fn write_map_struct(
+        &mut self,
+        entries: &[(Option<String>, Vec<u8>)],
+    ) -> Result<bool, UnevalError> {
+        let Some((name, fields)) = self
+            .map_structs
+            .iter()
+            .find(|(_, fields)| {
+                fields
+                    .iter()
+                    .all(|field| entries.iter().any(|(key, _)| key.as_ref() == Some(field)))
+            })
+            .cloned()
+        else {
+            return Ok(false);
+        };
+        self.write_ident(&name, IdentContext::TypeName)?;
+        write!(self.writer, " {{")?;
+        self.levels.push(false);
+        self.depth += 1;
+        let mut dropped = Vec::new();
+        for (key, code) in entries {
+            match key {
+                Some(key) if fields.contains(key) => {
+                    self.comma()?;
+                    self.write_ident(key, IdentContext::FieldName)?;
+                    self.formatter.begin_field_value(&mut self.writer)?;
+                    self.writer.write_all(code)?;
+                }
+                _ => dropped.push(key.as_deref().unwrap_or("<non-primitive>")),
+            }
+        }
+        for key in &dropped {
+            self.diagnose(Severity::Warning, || {
+                format!(
+                    "map entry `{}` is not a field of `{}`, so it is dropped and taken from `Default`",
+                    key, name
+                )
+            })?;
+        }
+        if !dropped.is_empty() {
+            self.comma()?;
+            write!(self.writer, "..Default::default()")?;
+            // The base expression must be the last one, without the trailing comma.
+            self.newline(self.depth - 1)?;
+            self.set_inside(false);
+        }
+        self.depth -= 1;
+        if self.levels.pop() == Some(true) {
+            self.formatter.end_entries(&mut self.writer, self.depth)?;
+        }
+        write!(self.writer, "}}")?;
+        Ok(true)
+    }
+
+    /// Starts the sequence or map, which entries will be sorted if `sort` is set.
+    fn start_sorted(&mut self, sort: bool) {
+        self.sorted.push(if sort { Some(Vec::new()) } else { None });
+    }
+
+    fn is_sorted(&self) -> bool {
+        matches!(self.sorted.last(), Some(Some(_)))
+    }
+
+    /// Starts the entry of the current sequence or map.
+    ///
+    /// If the container is sorted, the entry is captured to be written later; otherwise, it is simply separated
+    /// from the previous one.
+    fn start_entry(&mut self) -> SerResult {
+        if self.is_sorted() {
+            self.writer.capture();
+            Ok(())
+        } else {
+            self.comma()
+        }
+    }
+
+    fn end_entry(&mut self) {
+        if self.is_sorted() {
+            let entry = self.writer.release();
+            if let Some(Some(entries)) = self.sorted.last_mut() {
+                entries.push(entry);
+            }
+        }
+    }
+
+    /// Writes all the entries of the current sequence or map, if it is sorted.
+    fn end_sorted(&mut self) -> SerResult {
+        if let Some(Some(mut entries)) = self.sorted.pop() {
+            entries.sort();
+            self.set_inside(false);
+            for entry in entries {
+                self.comma()?;
+                self.writer.write_all(&entry)?;
+            }
+        }
         Ok(())
     }
 }
 
+impl Uneval<Vec<u8>> {
+    /// Drops the code written after the given number of bytes, e.g. the partial item which failed to serialize,
+    /// and resets the state left from it, so that the next value starts from scratch.
+    pub(crate) fn discard_after(&mut self, written: usize) {
+        self.writer.truncate(written);
+        self.levels = vec![false];
+        self.structs.clear();
+        self.internal_newtypes.clear();
+        self.variant_content = None;
+        self.tuples.clear();
+        self.seqs.clear();
+        self.item_modes = (None, None);
+        self.maps.clear();
+        self.map_structs.clear();
+        self.map_entries.clear();
+        self.pending_opens.clear();
+        self.pending_into = None;
+        self.outer_into.clear();
+        self.inferred_end = None;
+        self.sorted.clear();
+        self.context = Context::Runtime;
+        self.contexts.clear();
+        self.blobs.clear();
+        self.tuple_structs.clear();
+        self.depth = 0;
+        self.path = Path::default();
+    }
+}
+
 impl<W: Write> ser::Serializer for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
@@ -55,108 +2128,230 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
+    fn is_human_readable(&self) -> bool {
+        self.human_readable
+    }
+
     fn serialize_bool(self, v: bool) -> SerResult {
+        self.label(v);
         write!(self.writer, "{}", v)?;
         Ok(())
     }
 
     fn serialize_i8(self, v: i8) -> SerResult {
-        write!(self.writer, "{}i8", v)?;
+        self.label(v);
+        self.write_integer(v, "i8")?;
         Ok(())
     }
 
     fn serialize_i16(self, v: i16) -> SerResult {
-        write!(self.writer, "{}i16", v)?;
+        self.label(v);
+        self.write_integer(v, "i16")?;
         Ok(())
     }
 
     fn serialize_i32(self, v: i32) -> SerResult {
-        write!(self.writer, "{}i32", v)?;
+        self.label(v);
+        self.write_integer(v, "i32")?;
         Ok(())
     }
 
     fn serialize_i64(self, v: i64) -> SerResult {
-        write!(self.writer, "{}i64", v)?;
+        self.label(v);
+        self.write_integer(v, "i64")?;
         Ok(())
     }
 
     fn serialize_i128(self, v: i128) -> SerResult {
-        write!(self.writer, "{}i128", v)?;
+        self.require_rust("128-bit integer", 26)?;
+        self.label(v);
+        self.write_integer(v, "i128")?;
         Ok(())
     }
 
     fn serialize_u8(self, v: u8) -> SerResult {
-        write!(self.writer, "{}u8", v)?;
+        self.label(v);
+        self.write_integer(v, "u8")?;
         Ok(())
     }
 
     fn serialize_u16(self, v: u16) -> SerResult {
-        write!(self.writer, "{}u16", v)?;
+        self.label(v);
+        self.write_integer(v, "u16")?;
         Ok(())
     }
 
     fn serialize_u32(self, v: u32) -> SerResult {
-        write!(self.writer, "{}u32", v)?;
+        self.label(v);
+        self.write_integer(v, "u32")?;
         Ok(())
     }
 
     fn serialize_u64(self, v: u64) -> SerResult {
-        write!(self.writer, "{}u64", v)?;
+        self.label(v);
+        self.write_integer(v, "u64")?;
         Ok(())
     }
 
     fn serialize_u128(self, v: u128) -> SerResult {
-        write!(self.writer, "{}u128", v)?;
+        self.require_rust("128-bit integer", 26)?;
+        self.label(v);
+        self.write_integer(v, "u128")?;
         Ok(())
     }
 
     fn serialize_f32(self, v: f32) -> SerResult {
-        write!(self.writer, "{}f32", v)?;
+        self.label(v);
+        self.not_literal("floating-point number")?;
+        self.report.numbers += 1;
+        if !v.is_finite() {
+            self.diagnose(Severity::Error, || {
+                format!(
+                    "`{}` has no literal form, so the generated code won't compile",
+                    v
+                )
+            })?;
+        }
+        crate::helpers::write_float(&mut self.writer, v, "f32")?;
         Ok(())
     }
 
     fn serialize_f64(self, v: f64) -> SerResult {
-        write!(self.writer, "{}f64", v)?;
+        self.label(v);
+        self.not_literal("floating-point number")?;
+        self.report.numbers += 1;
+        if !v.is_finite() {
+            self.diagnose(Severity::Error, || {
+                format!(
+                    "`{}` has no literal form, so the generated code won't compile",
+                    v
+                )
+            })?;
+        }
+        crate::helpers::write_float(&mut self.writer, v, "f64")?;
         Ok(())
     }
 
     fn serialize_char(self, v: char) -> SerResult {
+        self.label(v);
         write!(self.writer, "'{}'", v.escape_default().collect::<String>())?;
         Ok(())
     }
 
     fn serialize_str(self, v: &str) -> SerResult {
-        write!(self.writer, "\"{}\".into()", v.escape_default().collect::<String>())?;
+        self.label(v);
+        let mode = match (
+            self.str_modes_at
+                .get(&self.path)
+                .copied()
+                .unwrap_or(self.str_mode),
+            self.context,
+        ) {
+            (StrMode::Parse, Context::Const | Context::Literal) => {
+                self.runtime_only("parsed string")?;
+                StrMode::Parse
+            }
+            (_, Context::Literal) => StrMode::Borrowed,
+            (StrMode::CowBorrowed, _) => StrMode::CowBorrowed,
+            (_, Context::Const) => StrMode::Borrowed,
+            (mode, Context::Runtime) => mode,
+        };
+        if mode.allocates() {
+            self.alloc_only("owned string")?;
+        }
+        self.report.strings += 1;
+        self.report.payload(v.len(), || self.path.to_string());
+        let open = self.emitter.str_open(mode, self.env);
+        write!(self.writer, "{}", open)?;
+        match &mut self.sidecars {
+            Some(sidecars)
+                if self.context != Context::Literal && v.len() >= sidecars.threshold() =>
+            {
+                let owned = self.context == Context::Runtime
+                    && !matches!(mode, StrMode::Borrowed | StrMode::CowBorrowed);
+                let code = sidecars
+                    .include(v.as_bytes(), true, owned, self.env)
+                    .map_err(|err| err.at(&self.path))?;
+                write!(self.writer, "{}", code)?;
+            }
+            _ => write!(self.writer, "{}", crate::helpers::str_literal(v))?,
+        }
+        let close = self.emitter.str_close(mode);
+        write!(self.writer, "{}", close)?;
+        if matches!(mode, StrMode::IntoString | StrMode::Parse) {
+            self.mark_inferred();
+        }
         Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> SerResult {
-        self.collect_seq(v)?;
+        self.report.payload(v.len(), || self.path.to_string());
+        if self.context == Context::Runtime {
+            self.collect_seq(v)?;
+        } else if let Some(sidecars) = self
+            .sidecars
+            .as_mut()
+            .filter(|sidecars| self.context == Context::Const && v.len() >= sidecars.threshold())
+        {
+            let code = sidecars.include(v, false, false, self.env)?;
+            write!(self.writer, "{}", code)?;
+        } else {
+            write!(self.writer, "b\"{}\"", v.escape_ascii())?;
+        }
         Ok(())
     }
 
     fn serialize_none(self) -> SerResult {
+        self.not_literal("`None`")?;
+        self.take_into();
         write!(self.writer, "None")?;
         Ok(())
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> SerResult
+    fn serialize_some<T>(self, value: &T) -> SerResult
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.not_literal("`Some`")?;
+        let into = self.take_into();
         write!(self.writer, "Some(")?;
-        value.serialize(&mut *self)?;
+        if into {
+            self.writer.capture();
+        }
+        value
+            .serialize(&mut *self)
+            .map_err(|err| err.at(&self.path))?;
+        if into {
+            self.release_into()?;
+        }
         write!(self.writer, ")")?;
         Ok(())
     }
 
     fn serialize_unit(self) -> SerResult {
-        write!(self.writer, "()")?;
+        self.not_literal("unit")?;
+        let code = self.emitter.unit();
+        write!(self.writer, "{}", code)?;
         Ok(())
     }
 
     fn serialize_unit_struct(self, name: &'static str) -> SerResult {
-        write!(self.writer, "{}", name)?;
+        self.not_literal("struct")?;
+        self.report.structs += 1;
+        self.label(name);
+        if !self.empty_tuple_structs.contains(name) {
+            self.diagnose(Severity::Note, || {
+                format!(
+                    "`{0}` is emitted as the unit struct, which doesn't compile if it is the empty tuple struct \
+                     `{0}()`; mark it with `with_empty_tuple_struct` in this case",
+                    name
+                )
+            })?;
+        }
+        self.write_ident(name, IdentContext::TypeName)?;
+        if self.empty_tuple_structs.contains(name) {
+            write!(self.writer, "()")?;
+        }
         Ok(())
     }
 
@@ -166,21 +2361,40 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
         _variant_index: u32,
         variant: &'static str,
     ) -> SerResult {
-        write!(self.writer, "{}::{}", name, variant)?;
+        self.not_literal("enum variant")?;
+        self.report.variants += 1;
+        self.label(variant);
+        self.write_variant(name, variant)?;
+        if self
+            .empty_tuple_variants
+            .iter()
+            .any(|(n, v)| n == name && v == variant)
+        {
+            write!(self.writer, "()")?;
+        }
         Ok(())
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> SerResult
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> SerResult
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        write!(self.writer, "{}(", name)?;
-        value.serialize(&mut *self)?;
+        self.not_literal("struct")?;
+        self.report.structs += 1;
+        self.begin_value(true);
+        self.write_ident(name, IdentContext::TypeName)?;
+        write!(self.writer, "(")?;
+        self.path.name(name, None);
+        value
+            .serialize(&mut *self)
+            .map_err(|err| err.at(&self.path))?;
+        self.path.unname();
         write!(self.writer, ")")?;
+        self.end_value()?;
         Ok(())
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         name: &'static str,
         _variant_index: u32,
@@ -188,32 +2402,162 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
         value: &T,
     ) -> SerResult
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        write!(self.writer, "{}::{}(", name, variant)?;
-        value.serialize(&mut *self)?;
+        self.not_literal("enum variant")?;
+        self.report.variants += 1;
+        self.begin_value(true);
+        self.write_variant(name, variant)?;
+        write!(self.writer, "(")?;
+        self.path.name(name, Some(variant));
+        value
+            .serialize(&mut *self)
+            .map_err(|err| err.at(&self.path))?;
+        self.path.unname();
         write!(self.writer, ")")?;
+        self.end_value()?;
         Ok(())
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        write!(self.writer, "vec![")?;
+        let mode = self
+            .seq_modes_at
+            .get(&self.path)
+            .copied()
+            .or(self.item_modes.0.filter(|_| self.path.is_top()))
+            .unwrap_or(self.seq_mode);
+        let mode = match mode {
+            SeqMode::FromArray if !self.rust_has(44) => SeqMode::PlainVec,
+            mode => mode,
+        };
+        self.contexts.push(self.context);
+        if mode.is_phf() {
+            self.not_literal("static set")?;
+            self.context = Context::Literal;
+        } else if mode.is_slice() {
+            self.not_literal("slice")?;
+            if mode == SeqMode::CowSlice {
+                self.alloc_only("slice")?;
+            }
+            self.context = Context::Const;
+        } else {
+            self.runtime_only("sequence")?;
+            self.alloc_only("sequence")?;
+        }
+        self.begin_value(false);
+        let sort = self.sorted_seqs_at.get(&self.path).is_some();
+        // Sorted sequences are never moved into sidecars, since the generated code wouldn't match them;
+        // neither are `Cow` slices, since `include_bytes!` can't be wrapped into `Cow::Borrowed` by suffix.
+        let sidecar = !sort && !mode.is_phf() && mode != SeqMode::CowSlice;
+        if self.sidecars.is_some() && sidecar {
+            self.writer.capture();
+            // Numeric sidecars are decoded with `TryInto` (Rust 1.34), so the older versions get them inline.
+            let numeric = !mode.is_slice()
+                && self.rust_has(34)
+                && self.numeric_sidecars_at.get(&self.path).is_some();
+            self.blobs.push(if numeric {
+                Blob::Numbers(None, Vec::new())
+            } else {
+                Blob::Bytes(Vec::new())
+            });
+        } else {
+            self.blobs.push(Blob::Off);
+        }
+        self.pending_opens.push(Some(self.emitter.seq_open(mode, self.env)));
+        self.seqs.push(mode);
+        self.start_sorted(sort);
         Ok(self.start_sub())
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        write!(self.writer, "{{")?;
-        crate::helpers::tuple_converter(&mut self.writer, len)?;
-        write!(self.writer, "convert((")?;
+        self.report.tuple_arities.insert(len);
+        let mode = if self.take_variant_content(None) {
+            TupleMode::Tuple
+        } else {
+            self.tuple_mode(len)
+        };
+        match mode {
+            TupleMode::Convert => self.runtime_only("tuple")?,
+            TupleMode::Array => self.not_literal("array")?,
+            TupleMode::Tuple => self.not_literal("tuple")?,
+        }
+        if mode == TupleMode::Convert
+            && len > 16
+            && self
+                .tuple_trait
+                .as_ref()
+                .is_some_and(|path| path.ends_with("uneval::convert::FromTuple"))
+        {
+            self.diagnose(Severity::Warning, || {
+                format!(
+                    "`uneval::convert::FromTuple` is implemented for tuples of up to 16 elements and arrays \
+                     of up to 32 elements, but this one has {}",
+                    len
+                )
+            })?;
+        }
+        self.begin_value(false);
+        match mode {
+            TupleMode::Convert if self.shared_tuples.is_some() => {
+                if let Some(lens) = &mut self.shared_tuples {
+                    lens.insert(len);
+                }
+                write!(self.writer, "__uneval_tuple_{}::convert((", len)?;
+            }
+            TupleMode::Convert if self.formatter.is_multiline() => {
+                // The block with runtime gets its own indentation level.
+                self.depth += 1;
+                let mut converter = Vec::new();
+                crate::helpers::tuple_converter(
+                    &mut converter,
+                    len,
+                    "",
+                    self.tuple_trait.as_deref(),
+                )?;
+                write!(self.writer, "{{")?;
+                for (level, line) in crate::helpers::reindent(&String::from_utf8(converter)?) {
+                    self.newline(self.depth + level)?;
+                    write!(self.writer, "{}", line)?;
+                }
+                self.newline(self.depth)?;
+                write!(self.writer, "convert((")?;
+            }
+            TupleMode::Convert => {
+                write!(self.writer, "{{")?;
+                crate::helpers::tuple_converter(
+                    &mut self.writer,
+                    len,
+                    "",
+                    self.tuple_trait.as_deref(),
+                )?;
+                write!(self.writer, "convert((")?;
+            }
+            TupleMode::Array | TupleMode::Tuple => {
+                let open = self.emitter.tuple_open(mode, len);
+                write!(self.writer, "{}", open)?;
+            }
+        }
+        self.tuples.push((mode, len));
         Ok(self.start_sub())
     }
 
     fn serialize_tuple_struct(
         self,
         name: &'static str,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        write!(self.writer, "{}(", name)?;
+        let as_tuple = self.tuple_structs_as_tuples.contains(name);
+        self.tuple_structs.push(as_tuple);
+        if as_tuple {
+            self.path.name(name, None);
+            return ser::Serializer::serialize_tuple(self, len);
+        }
+        self.not_literal("struct")?;
+        self.report.structs += 1;
+        self.begin_value(true);
+        self.write_ident(name, IdentContext::TypeName)?;
+        write!(self.writer, "(")?;
+        self.path.name(name, None);
         Ok(self.start_sub())
     }
 
@@ -224,12 +2568,44 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        write!(self.writer, "{}::{}(", name, variant)?;
+        self.not_literal("enum variant")?;
+        self.report.variants += 1;
+        self.begin_value(true);
+        self.write_variant(name, variant)?;
+        write!(self.writer, "(")?;
+        self.path.name(name, Some(variant));
         Ok(self.start_sub())
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        write!(self.writer, "vec![")?;
+        let mode = self
+            .map_modes_at
+            .get(&self.path)
+            .or(self.item_modes.1.as_ref().filter(|_| self.path.is_top()))
+            .unwrap_or(&self.map_mode)
+            .clone();
+        let mode = match mode {
+            MapMode::FromArray { .. } if !self.rust_has(56) => MapMode::Collect,
+            mode => mode,
+        };
+        self.contexts.push(self.context);
+        if mode.is_phf() {
+            self.not_literal("static map")?;
+            self.context = Context::Const;
+        } else {
+            self.runtime_only("map")?;
+            self.alloc_only("map")?;
+        }
+        self.begin_value(false);
+        // Maps which might be emitted as structs are captured, until all the keys are known.
+        let as_struct = !mode.is_phf() && !self.map_structs.is_empty();
+        if as_struct {
+            self.writer.capture();
+        }
+        self.map_entries.push(as_struct.then(Vec::new));
+        self.pending_opens.push(Some(self.emitter.map_open(&mode, self.env)));
+        self.maps.push(mode);
+        self.start_sorted(self.sort_maps);
         Ok(self.start_sub())
     }
 
@@ -238,7 +2614,53 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
         name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        write!(self.writer, "{} {{", name)?;
+        self.not_literal("struct")?;
+        self.report.structs += 1;
+        if self.take_variant_content(Some(name)) {
+            self.structs.push(StructEnd::Literal);
+            self.internal_newtypes.push(None);
+            self.begin_value(false);
+            write!(self.writer, " {{")?;
+            return Ok(self.start_sub());
+        }
+        if let Some(tag) = self.tagged_enums.get(name) {
+            self.structs.push(StructEnd::Tagged {
+                name,
+                tag: tag.clone(),
+                variant: None,
+                opened: false,
+            });
+            self.internal_newtypes.push(None);
+            self.begin_value(true);
+            self.path.name(name, None);
+            return Ok(self.start_sub());
+        }
+        if let Some(constructor) = self.constructors.get(name) {
+            if !constructor.is_const {
+                self.runtime_only("constructor call")?;
+            }
+            let (open, close) = (constructor.open.clone(), constructor.close.clone());
+            self.structs.push(StructEnd::Call(close));
+            self.begin_value(true);
+            self.start_internal_newtype();
+            write!(self.writer, "{}", open)?;
+            self.path.name(name, None);
+            return Ok(self.start_sub());
+        }
+        let fill = self.fill_defaults || self.fill_defaults_for.contains(name);
+        if fill {
+            self.runtime_only("struct with default fields")?;
+        }
+        self.structs.push(if fill {
+            StructEnd::Filled
+        } else {
+            StructEnd::Literal
+        });
+        self.begin_value(true);
+        self.start_internal_newtype();
+        self.write_ident(name, IdentContext::TypeName)?;
+        write!(self.writer, " {{")?;
+        self.path.name(name, None);
         Ok(self.start_sub())
     }
 
@@ -249,7 +2671,12 @@ impl<W: Write> ser::Serializer for &mut Uneval<W> {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        write!(self.writer, "{}::{} {{", name, variant)?;
+        self.not_literal("enum variant")?;
+        self.report.variants += 1;
+        self.begin_value(true);
+        self.write_variant(name, variant)?;
+        write!(self.writer, " {{")?;
+        self.path.name(name, Some(variant));
         Ok(self.start_sub())
     }
 }
@@ -258,16 +2685,76 @@ impl<W: Write> ser::SerializeSeq for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        self.serialize_item(value)
+        self.path.next_element();
+        self.count_entry(true);
+        self.open_pending()?;
+        if let Some(blob @ (Blob::Bytes(_) | Blob::Numbers(..))) = self.blobs.last_mut() {
+            let number = match self.overrides_at.get(&self.path) {
+                Some(_) => None,
+                None => crate::sidecar::number_value(value),
+            };
+            match (number, blob) {
+                (Some((Number::U8, byte)), Blob::Bytes(bytes)) => bytes.extend(byte),
+                (Some((number, le_bytes)), Blob::Numbers(expected, bytes))
+                    if expected.is_none_or(|expected| expected == number) =>
+                {
+                    *expected = Some(number);
+                    bytes.extend(le_bytes);
+                }
+                (_, blob) => {
+                    // Not a byte (or number) sequence, so there's no need to capture it anymore.
+                    *blob = Blob::Off;
+                    let code = self.writer.release();
+                    self.writer.write_all(&code)?;
+                }
+            }
+        }
+        self.start_entry()?;
+        (**self).serialize_nested(value)?;
+        self.end_entry();
+        Ok(())
     }
 
     fn end(self) -> SerResult {
-        write!(self.writer, "].into_iter().collect()")?;
-        self.inside = true;
+        self.end_sorted()?;
+        self.end_sub()?;
+        let mode = self.seqs.pop().unwrap_or_default();
+        let close = self.emitter.seq_close(mode);
+        self.close_container(&close, mode.has_default())?;
+        match self.blobs.pop() {
+            // Byte sequences are included as is, even if they are requested to be numeric.
+            Some(Blob::Bytes(bytes) | Blob::Numbers(Some(Number::U8), bytes)) => {
+                let code = self.writer.release();
+                match &mut self.sidecars {
+                    Some(sidecars) if bytes.len() >= sidecars.threshold() => {
+                        let code = sidecars
+                            .include(&bytes, false, true, self.env)
+                            .map_err(|err| err.at(&self.path))?;
+                        write!(self.writer, "{}", code)?;
+                        self.write_close(mode.bytes_suffix())?;
+                    }
+                    _ => self.writer.write_all(&code)?,
+                }
+            }
+            Some(Blob::Numbers(number, bytes)) => {
+                let code = self.writer.release();
+                match (number, &mut self.sidecars) {
+                    (Some(number), Some(sidecars)) if bytes.len() >= sidecars.threshold() => {
+                        let code = sidecars.include_numbers(&bytes, number)?;
+                        write!(self.writer, "{}", code)?;
+                        self.write_close(&mode.numbers_suffix(self.env))?;
+                    }
+                    _ => self.writer.write_all(&code)?,
+                }
+            }
+            Some(Blob::Off) | None => {}
+        }
+        self.context = self.contexts.pop().unwrap_or(Context::Runtime);
+        self.end_value()?;
         Ok(())
     }
 }
@@ -275,16 +2762,41 @@ impl<W: Write> ser::SerializeTuple for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.path.next_position();
         self.serialize_item(value)
     }
 
     fn end(self) -> SerResult {
-        write!(self.writer, ")) }}")?;
-        self.inside = true;
+        self.end_sub()?;
+        match self.tuples.pop() {
+            Some((mode @ (TupleMode::Array | TupleMode::Tuple), len)) => {
+                let close = self.emitter.tuple_close(mode, len);
+                write!(self.writer, "{}", close)?;
+            }
+            Some((TupleMode::Convert, len)) => {
+                // One-element tuple needs the trailing comma to be a tuple.
+                if len == 1 {
+                    write!(self.writer, ",")?;
+                }
+                if self.shared_tuples.is_some() {
+                    write!(self.writer, "))")?;
+                } else if self.formatter.is_multiline() {
+                    self.depth -= 1;
+                    write!(self.writer, "))")?;
+                    self.newline(self.depth)?;
+                    write!(self.writer, "}}")?;
+                } else {
+                    write!(self.writer, ")) }}")?;
+                }
+                self.mark_inferred();
+            }
+            None => {}
+        }
+        self.end_value()?;
         Ok(())
     }
 }
@@ -292,16 +2804,21 @@ impl<W: Write> ser::SerializeTupleStruct for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.path.next_position();
         self.serialize_item(value)
     }
 
     fn end(self) -> SerResult {
+        if self.tuple_structs.pop() == Some(true) {
+            return ser::SerializeTuple::end(self);
+        }
+        self.end_sub()?;
         write!(self.writer, ")")?;
-        self.inside = true;
+        self.end_value()?;
         Ok(())
     }
 }
@@ -309,16 +2826,18 @@ impl<W: Write> ser::SerializeTupleVariant for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.path.next_position();
         self.serialize_item(value)
     }
 
     fn end(self) -> SerResult {
+        self.end_sub()?;
         write!(self.writer, ")")?;
-        self.inside = true;
+        self.end_value()?;
         Ok(())
     }
 }
@@ -326,29 +2845,74 @@ impl<W: Write> ser::SerializeMap for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        self.comma()?;
-        write!(self.writer, "(")?;
-        key.serialize(&mut **self)?;
-        write!(self.writer, ",")?;
+        self.path.key(None);
+        self.count_entry(false);
+        self.open_pending()?;
+        self.start_entry()?;
+        let outer = self.key_label.replace((self.depth, None));
+        if self.maps.last().is_some_and(MapMode::is_phf) {
+            let context = std::mem::replace(&mut self.context, Context::Literal);
+            key.serialize(&mut **self)
+                .map_err(|err| err.at(&self.path))?;
+            self.context = context;
+            write!(self.writer, " => ")?;
+        } else {
+            write!(self.writer, "(")?;
+            key.serialize(&mut **self)
+                .map_err(|err| err.at(&self.path))?;
+            self.formatter.begin_map_value(&mut self.writer)?;
+        }
+        let label = std::mem::replace(&mut self.key_label, outer).and_then(|(_, label)| label);
+        if let Some(Some(entries)) = self.map_entries.last_mut() {
+            entries.push((label.clone(), Vec::new()));
+        }
+        self.path.key(label);
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
-        value.serialize(&mut **self)?;
-        write!(self.writer, ")")?;
+        let as_struct = matches!(self.map_entries.last(), Some(Some(_)));
+        if as_struct {
+            self.writer.capture();
+        }
+        (**self).serialize_nested(value)?;
+        if as_struct {
+            let code = self.writer.release();
+            self.writer.write_all(&code)?;
+            if let Some(Some(entries)) = self.map_entries.last_mut() {
+                if let Some((_, value)) = entries.last_mut() {
+                    *value = code;
+                }
+            }
+        }
+        if !self.maps.last().is_some_and(MapMode::is_phf) {
+            write!(self.writer, ")")?;
+        }
+        self.end_entry();
         Ok(())
     }
 
     fn end(self) -> SerResult {
-        write!(self.writer, "].into_iter().collect()")?;
-        self.inside = true;
+        self.end_sorted()?;
+        self.end_sub()?;
+        let mode = self.maps.pop().unwrap_or_default();
+        let close = self.emitter.map_close(&mode);
+        self.close_container(&close, !mode.is_phf())?;
+        if let Some(Some(entries)) = self.map_entries.pop() {
+            let code = self.writer.release();
+            if !self.write_map_struct(&entries)? {
+                self.writer.write_all(&code)?;
+            }
+        }
+        self.context = self.contexts.pop().unwrap_or(Context::Runtime);
+        self.end_value()?;
         Ok(())
     }
 }
@@ -356,23 +2920,57 @@ impl<W: Write> ser::SerializeStruct for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.path.field(key);
+        if let Some(StructEnd::Tagged { .. }) = self.structs.last() {
+            return (**self).serialize_tagged_field(key, value);
+        }
+        if (**self).end_internal_newtype(Some((key, value)))? {
+            return Ok(());
+        }
         self.comma()?;
-        write!(self.writer, "{}: ", key)?;
-        value.serialize(&mut **self)?;
+        if !matches!(self.structs.last(), Some(StructEnd::Call(_))) {
+            self.write_ident(key, IdentContext::FieldName)?;
+            self.formatter.begin_field_value(&mut self.writer)?;
+        }
+        (**self).serialize_nested(value)?;
         Ok(())
     }
 
     fn end(self) -> SerResult {
-        write!(self.writer, "}}")?;
-        self.inside = true;
+        self.end_internal_newtype::<()>(None)?;
+        let newtype = self.internal_newtypes.pop().flatten().is_some();
+        let end = self.structs.pop().unwrap_or(StructEnd::Literal);
+        match &end {
+            StructEnd::Tagged {
+                name,
+                variant: None,
+                ..
+            } => return Err(self.invalid_tag(name)),
+            // Only the internally tagged variant with fields has anything to close.
+            StructEnd::Tagged { opened: false, .. } => self.set_inside(false),
+            _ => {}
+        }
+        if let StructEnd::Filled = end {
+            self.comma()?;
+            write!(self.writer, "..Default::default()")?;
+            // The base expression must be the last one, without the trailing comma.
+            self.newline(self.depth - 1)?;
+            self.set_inside(false);
+        }
+        self.end_sub()?;
+        match end {
+            StructEnd::Call(close) => write!(self.writer, "{}", close)?,
+            StructEnd::Tagged { opened: false, .. } => {}
+            _ => write!(self.writer, "}}")?,
+        }
+        if newtype {
+            write!(self.writer, ")")?;
+        }
+        self.end_value()?;
         Ok(())
     }
 }
@@ -380,23 +2978,22 @@ impl<W: Write> ser::SerializeStructVariant for &mut Uneval<W> {
     type Ok = ();
     type Error = UnevalError;
 
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: serde::Serialize,
+        T: ?Sized + serde::Serialize,
     {
+        self.path.field(key);
         self.comma()?;
-        write!(self.writer, "{}: ", key)?;
-        value.serialize(&mut **self)?;
+        self.write_ident(key, IdentContext::FieldName)?;
+        self.formatter.begin_field_value(&mut self.writer)?;
+        (**self).serialize_nested(value)?;
         Ok(())
     }
 
     fn end(self) -> SerResult {
+        self.end_sub()?;
         write!(self.writer, "}}")?;
-        self.inside = true;
+        self.end_value()?;
         Ok(())
     }
 }
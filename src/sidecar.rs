@@ -0,0 +1,381 @@
+use crate::config::Env;
+use crate::error::UnevalError;
+use serde::ser;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Files with the large blobs, which are written next to the generated code and included into it.
+pub(crate) struct Sidecars {
+    dir: PathBuf,
+    base: String,
+    threshold: usize,
+    written: Vec<String>,
+    #[cfg(feature = "compress")]
+    codec: Option<crate::config::Codec>,
+}
+
+impl Sidecars {
+    /// Prepares the sidecars for the given generated file.
+    ///
+    /// Sidecars are named after this file and the hash of their contents, e.g. `data.rs.3c1f8e0a9b2d4c57.bin`
+    /// for `data.rs`, so that the same value always yields the same files, whatever the order of serialization
+    /// (which is random e.g. for `HashMap`).
+    pub(crate) fn new(target: &Path, threshold: usize) -> Result<Self, UnevalError> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "target file name must be valid UTF-8",
+            )
+        };
+        let base = target
+            .file_name()
+            .ok_or_else(invalid)?
+            .to_str()
+            .ok_or_else(invalid)?;
+        Ok(Self {
+            dir: target.parent().map(Path::to_path_buf).unwrap_or_default(),
+            base: base.into(),
+            threshold,
+            written: Vec::new(),
+            #[cfg(feature = "compress")]
+            codec: None,
+        })
+    }
+
+    /// Compresses the blobs which are included as owned values.
+    #[cfg(feature = "compress")]
+    pub(crate) fn with_codec(mut self, codec: crate::config::Codec) -> Self {
+        self.codec = Some(codec);
+        self
+    }
+
+    /// Writes the blob into the sidecar file and returns the expression including it.
+    ///
+    /// This is `include_bytes!` or, for the `text`, `include_str!`. If the `owned` value is acceptable, i.e.
+    /// `Vec<u8>` or `String`, the blob might be compressed instead, and the expression decompresses it at runtime;
+    /// since decompression requires `std`, this fails in other environments.
+    pub(crate) fn include(
+        &mut self,
+        contents: &[u8],
+        text: bool,
+        owned: bool,
+        env: Env,
+    ) -> Result<String, UnevalError> {
+        #[cfg(feature = "compress")]
+        if let Some(codec) = self.codec.filter(|_| owned) {
+            if env != Env::Std {
+                return Err(UnevalError::RequiresStd {
+                    what: "compressed sidecar",
+                    path: String::new(),
+                });
+            }
+            let compressed = codec.compress(contents)?;
+            // Incompressible blobs are not worth the runtime cost.
+            if compressed.len() < contents.len() {
+                let extension =
+                    format!("{}.{}", if text { "txt" } else { "bin" }, codec.extension());
+                let name = self.write(&compressed, &extension)?;
+                let bytes = format!(
+                    "{{let mut data = ::std::vec::Vec::new();\
+                     ::std::io::Read::read_to_end(&mut {}::new(&include_bytes!({:?})[..]), &mut data).unwrap();\
+                     data}}",
+                    codec.decoder(),
+                    name
+                );
+                return Ok(if text {
+                    format!("::std::string::String::from_utf8({}).unwrap()", bytes)
+                } else {
+                    bytes
+                });
+            }
+        }
+        #[cfg(not(feature = "compress"))]
+        let _ = (owned, env);
+        Ok(if text {
+            format!("include_str!({:?})", self.write(contents, "txt")?)
+        } else {
+            format!("include_bytes!({:?})", self.write(contents, "bin")?)
+        })
+    }
+
+    /// Writes the little-endian numbers into the sidecar file and returns the iterator decoding them.
+    ///
+    /// Every number is copied out of the included bytes with `from_le_bytes`, so neither the alignment
+    /// of `include_bytes!` nor the endianness of the target matters.
+    pub(crate) fn include_numbers(
+        &mut self,
+        contents: &[u8],
+        number: Number,
+    ) -> Result<String, UnevalError> {
+        Ok(format!(
+            "include_bytes!({:?}).chunks_exact({}).map(|chunk| {}::from_le_bytes(::core::convert::TryInto::try_into(chunk).unwrap()))",
+            self.write(contents, "bin")?,
+            number.size(),
+            number.name()
+        ))
+    }
+
+    /// Minimal size of the blob, in bytes, to be moved into the sidecar.
+    pub(crate) fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Writes the blob into the sidecar file and returns its name, relative to the generated file.
+    ///
+    /// The file is left untouched if it already has the same contents; equal blobs share the same file.
+    fn write(&mut self, contents: &[u8], extension: &str) -> Result<String, UnevalError> {
+        let name = format!(
+            "{}.{:016x}.{}",
+            self.base,
+            content_hash(contents),
+            extension
+        );
+        if self.written.contains(&name) {
+            return Ok(name);
+        }
+        let path = self.dir.join(&name);
+        if !crate::funcs::same_contents(&path, contents)? {
+            crate::funcs::write_atomically(&path, |writer| {
+                Ok(std::io::Write::write_all(writer, contents)?)
+            })?;
+        }
+        self.written.push(name.clone());
+        Ok(name)
+    }
+
+    /// Removes the sidecars left from the previous runs, which were not written this time.
+    pub(crate) fn cleanup(&self) -> Result<(), UnevalError> {
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if self.is_sidecar(name) && !self.written.iter().any(|written| written == name) {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_sidecar(&self, name: &str) -> bool {
+        let Some(rest) = name
+            .strip_prefix(&self.base)
+            .and_then(|rest| rest.strip_prefix('.'))
+        else {
+            return false;
+        };
+        let rest = rest
+            .strip_suffix(".deflate")
+            .or_else(|| rest.strip_suffix(".gz"))
+            .unwrap_or(rest);
+        match rest.split_once('.') {
+            // Numbered sidecars were written by the older versions.
+            Some((hash, "bin" | "txt")) => {
+                !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, which, unlike the [`Hash`][std::hash::Hash]-based ones, is the same on every platform
+/// and with every version of Rust.
+fn content_hash(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Returns the type of the number and its little-endian bytes, or `None` if the value is not a number.
+pub(crate) fn number_value<T: ?Sized + ser::Serialize>(value: &T) -> Option<(Number, Vec<u8>)> {
+    value.serialize(NumberValue).ok()
+}
+
+/// Primitive numeric type of the sequence elements which are moved into sidecar as binary data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Number {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl Number {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Number::U8 => "u8",
+            Number::U16 => "u16",
+            Number::U32 => "u32",
+            Number::U64 => "u64",
+            Number::I8 => "i8",
+            Number::I16 => "i16",
+            Number::I32 => "i32",
+            Number::I64 => "i64",
+            Number::F32 => "f32",
+            Number::F64 => "f64",
+        }
+    }
+
+    /// Size of the number, in bytes.
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Number::U8 | Number::I8 => 1,
+            Number::U16 | Number::I16 => 2,
+            Number::U32 | Number::I32 | Number::F32 => 4,
+            Number::U64 | Number::I64 | Number::F64 => 8,
+        }
+    }
+}
+
+/// Serializer which accepts only primitive numbers, to check whether the sequence can be moved into sidecar.
+struct NumberValue;
+
+impl ser::Serializer for NumberValue {
+    type Ok = (Number, Vec<u8>);
+    type Error = fmt::Error;
+
+    type SerializeSeq = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeTuple = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeTupleStruct = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeMap = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeStruct = ser::Impossible<Self::Ok, fmt::Error>;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, fmt::Error>;
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::U8, vec![v]))
+    }
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::U16, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::U32, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::U64, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::I8, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::I16, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::I32, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::I64, v.to_le_bytes().to_vec()))
+    }
+    // Bits are kept as is, including the NaN payloads.
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::F32, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, fmt::Error> {
+        Ok((Number::F64, v.to_le_bytes().to_vec()))
+    }
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_none(self) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_unit(self) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+}
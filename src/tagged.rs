@@ -0,0 +1,157 @@
+//! Support for the enums serialized with the tag, i.e. with `#[serde(tag = "...")]` attribute.
+//!
+//! Such enums are serialized as structs named after the enum, with the variant name stored in the tag field
+//! (and, for the adjacently tagged ones, the variant contents stored in the content field).
+
+use serde::ser;
+use std::fmt;
+
+/// Extracts the variant name from the value of the tag field.
+pub(crate) fn variant_name<T: ?Sized + ser::Serialize>(value: &T) -> Option<String> {
+    value.serialize(VariantName).ok()
+}
+
+/// Serializer which accepts only strings and unit variants, i.e. the possible representations of the tag.
+struct VariantName;
+
+impl ser::Serializer for VariantName {
+    type Ok = String;
+    type Error = fmt::Error;
+
+    type SerializeSeq = ser::Impossible<String, fmt::Error>;
+    type SerializeTuple = ser::Impossible<String, fmt::Error>;
+    type SerializeTupleStruct = ser::Impossible<String, fmt::Error>;
+    type SerializeTupleVariant = ser::Impossible<String, fmt::Error>;
+    type SerializeMap = ser::Impossible<String, fmt::Error>;
+    type SerializeStruct = ser::Impossible<String, fmt::Error>;
+    type SerializeStructVariant = ser::Impossible<String, fmt::Error>;
+
+    fn serialize_u8(self, _v: u8) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_bool(self, _v: bool) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_i8(self, _v: i8) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_i16(self, _v: i16) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_i32(self, _v: i32) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_i64(self, _v: i64) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_u16(self, _v: u16) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_u32(self, _v: u32) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_u64(self, _v: u64) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_f32(self, _v: f32) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_f64(self, _v: f64) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_char(self, _v: char) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_str(self, v: &str) -> Result<String, fmt::Error> {
+        Ok(v.into())
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_none(self) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_some<T>(self, _value: &T) -> Result<String, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_unit(self) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, fmt::Error> {
+        Ok(variant.into())
+    }
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<String, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, fmt::Error>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        Err(fmt::Error)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+}
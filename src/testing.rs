@@ -0,0 +1,150 @@
+//! Checking that the generated code compiles and yields the original value.
+//!
+//! This is the same kind of check `uneval` runs on its own test fixtures, available for the user types,
+//! e.g. to be run in CI for the data generated by the build script.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MAIN: &str = r#"#![allow(dead_code, unused_imports)]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+{definitions}
+
+fn main() {
+    let value: {type_name} = include!("generated.rs");
+    let expected: {type_name} = serde_json::from_str(include_str!("expected.json")).unwrap();
+    if value != expected {
+        eprintln!("generated code yields the value different from the original one");
+        std::process::exit(1);
+    }
+}
+"#;
+
+const MANIFEST: &str = r#"[package]
+name = "{name}"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+serde_json = "1.0"
+
+[workspace]
+"#;
+
+/// Checks that the code generated for the value compiles and evaluates to the same value.
+///
+/// This creates the temporary crate, with the provided type definitions at its root, and the program which
+/// [`include!`]s the code generated by [`to_file`][crate::to_file] and compares it with the original value,
+/// passed through JSON. The crate is built and run with `cargo` from `CARGO` environment variable,
+/// if it is set, i.e. with the current toolchain, and `serde` (with `derive` feature) and `serde_json`
+/// as dependencies; `Deserialize` and `Serialize` derive macros are in scope. Build artifacts of the
+/// dependencies are shared between the checks, so only the first one has to build them; every check gets its own
+/// package name, so that the checks running in parallel don't overwrite each other's programs.
+///
+/// The type of the value is written without the module paths, e.g. `Vec<Point>` for `Vec<my_crate::geo::Point>`,
+/// so all the types used must be defined in `type_defs`, be in the prelude or be one of the collections
+/// from [`std::collections`], which are imported too. The definitions must derive
+/// `Deserialize` and `PartialEq`, in addition to the traits required by the value itself.
+///
+/// # Panics
+///
+/// Panics, if the value can't be converted to code, if it doesn't survive the JSON round-trip (in this case
+/// the comparison is impossible), or if the generated code fails to compile or yields the different value.
+/// The message contains the generated code and the compiler output.
+///
+/// ```no_run
+/// #[derive(serde::Serialize, serde::Deserialize, PartialEq)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// uneval::testing::assert_embeds(
+///     &vec![Point { x: 1, y: 2 }],
+///     "#[derive(Serialize, Deserialize, PartialEq)] pub struct Point { x: i32, y: i32 }",
+/// );
+/// ```
+pub fn assert_embeds<T: Serialize + DeserializeOwned + PartialEq>(value: &T, type_defs: &str) {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let json = serde_json::to_string(value).expect("value can't be serialized to JSON");
+    let round_trip: T = serde_json::from_str(&json).expect("value can't be deserialized from JSON");
+    assert!(
+        round_trip == *value,
+        "value doesn't survive JSON round-trip, so it can't be compared with the generated one"
+    );
+
+    let root = std::env::temp_dir().join("uneval-testing");
+    let name = format!(
+        "uneval-embed-check-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let dir = root.join(&name);
+    let src = dir.join("src");
+    std::fs::create_dir_all(&src).expect("can't create the temporary crate");
+    crate::to_file(value, src.join("generated.rs")).expect("value can't be converted to code");
+    let code = std::fs::read_to_string(src.join("generated.rs")).unwrap_or_default();
+    let main = MAIN
+        .replace("{type_name}", &type_name::<T>())
+        .replace("{definitions}", type_defs);
+    let manifest = MANIFEST.replace("{name}", &name);
+    for (path, contents) in [
+        (dir.join("Cargo.toml"), manifest.as_str()),
+        (src.join("main.rs"), &main),
+        (src.join("expected.json"), &json),
+    ] {
+        std::fs::write(path, contents).expect("can't write the temporary crate");
+    }
+
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .arg("run")
+        .arg("--quiet")
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", root.join("target"))
+        .output();
+    let _ = std::fs::remove_dir_all(&dir);
+    match output {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => panic!("{}", failure(&code, &main, &output)),
+        Err(err) => panic!("can't run cargo: {}", err),
+    }
+}
+
+fn failure(code: &str, main: &str, output: &Output) -> String {
+    format!(
+        "generated code check failed\n--- generated.rs ---\n{}\n--- main.rs ---\n{}\n--- cargo output ---\n{}{}",
+        code,
+        main,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+/// The name of the type, with the module paths stripped from every path in it.
+fn type_name<T>() -> String {
+    let full = std::any::type_name::<T>();
+    let mut name = String::new();
+    let mut segment = String::new();
+    for c in full.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            name.push_str(last_segment(&segment));
+            segment.clear();
+            name.push(c);
+        }
+    }
+    name.push_str(last_segment(&segment));
+    name
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "serialize")]
+
+use serde::Serialize;
+use std::collections::HashMap;
+use uneval::{Diagnostic, Severity};
+
+#[derive(Serialize)]
+struct Marker;
+
+#[derive(Serialize)]
+struct Config {
+    ratio: f64,
+    #[serde(rename = "max-retries")]
+    max_retries: u8,
+    marker: Marker,
+    limits: HashMap<String, u32>,
+}
+
+fn config() -> Config {
+    Config {
+        ratio: f64::INFINITY,
+        max_retries: 3,
+        marker: Marker,
+        limits: [("a".to_string(), 1)].into_iter().collect(),
+    }
+}
+
+fn summary(diagnostics: &[Diagnostic]) -> Vec<(Severity, &str)> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.severity, diagnostic.path.as_str()))
+        .collect()
+}
+
+#[test]
+fn diagnostics() {
+    let diagnostics = uneval::check(&config());
+    assert_eq!(
+        summary(&diagnostics),
+        [
+            (Severity::Error, "Config.ratio"),
+            (Severity::Error, "Config.max-retries"),
+            (Severity::Note, "Config.marker"),
+        ]
+    );
+    assert!(diagnostics[0].message.contains("`inf`"));
+    assert!(diagnostics[1].message.contains("`max-retries`"));
+    assert!(diagnostics[2].message.contains("with_empty_tuple_struct"));
+
+    // The marked empty tuple structs are fine.
+    let diagnostics = uneval::check_with(
+        &config(),
+        uneval::ser::Uneval::new(std::io::sink()).with_empty_tuple_struct("Marker"),
+    );
+    assert_eq!(
+        summary(&diagnostics),
+        [
+            (Severity::Error, "Config.ratio"),
+            (Severity::Error, "Config.max-retries"),
+        ]
+    );
+}
+
+#[test]
+fn long_tuples_and_errors() {
+    let value = ([0u8; 20], (1u8, 2u8));
+    assert!(uneval::check(&value).is_empty());
+    let diagnostics = uneval::check_with(
+        &value,
+        uneval::ser::Uneval::new(std::io::sink()).with_tuple_trait("::uneval::convert::FromTuple"),
+    );
+    assert_eq!(summary(&diagnostics), [(Severity::Warning, ".0")]);
+
+    // The value which can't be serialized is reported as the last diagnostic.
+    let diagnostics = uneval::check_with(
+        &vec![Some(f32::NAN), None],
+        uneval::ser::Uneval::new(std::io::sink()).with_const_context(),
+    );
+    assert_eq!(summary(&diagnostics), [(Severity::Error, "")]);
+    assert!(diagnostics[0].message.contains("constant context"));
+}
+
+#[test]
+fn reported_with_output() {
+    let mut out = Vec::new();
+    let report = uneval::write_with_report(
+        (
+            f32::NAN,
+            std::iter::once((1u8, 2u8)).collect::<HashMap<_, _>>(),
+        ),
+        &mut out,
+    )
+    .unwrap();
+    assert_eq!(summary(&report.diagnostics), [(Severity::Error, ".0")]);
+    assert_eq!(report.bytes, out.len());
+
+    // Unlike the dry run, the invalid identifiers are still the errors.
+    assert!(uneval::write_with_report(config(), std::io::sink()).is_err());
+}
+
+#[test]
+fn strict() {
+    use uneval::{error::UnevalError, ser::Uneval};
+
+    let strict = |severity| Uneval::new(std::io::sink()).with_strict(severity);
+    let value = (Marker, 1.0f32);
+    assert!(value.serialize(&mut strict(Severity::Warning)).is_ok());
+    match value.serialize(&mut strict(Severity::Note)) {
+        Err(UnevalError::Strict { message, path }) => {
+            assert!(message.contains("with_empty_tuple_struct"), "{}", message);
+            assert_eq!(path, ".0");
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(value
+        .serialize(&mut strict(Severity::Note).with_empty_tuple_struct("Marker"))
+        .is_ok());
+
+    let err = f64::INFINITY
+        .serialize(&mut strict(Severity::Error))
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`inf` has no literal form, so the generated code won't compile"
+    );
+}
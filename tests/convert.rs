@@ -0,0 +1,120 @@
+#![cfg(all(feature = "glam", feature = "serialize"))]
+
+use serde::Serialize;
+use std::{fs, path::PathBuf, process::Command};
+
+#[derive(Serialize)]
+struct Body {
+    position: glam::Vec3,
+    scale: [f32; 3],
+    rotation: glam::Quat,
+    pair: (u8, String),
+}
+
+fn body() -> Body {
+    Body {
+        position: glam::Vec3::new(1.0, 2.5, -3.0),
+        scale: [1.0, 1.0, 2.0],
+        rotation: glam::Quat::from_xyzw(0.0, 0.0, 0.5, 0.5),
+        pair: (7, "seven".into()),
+    }
+}
+
+/// The generated code refers to `uneval` with `glam` feature, so it is checked in the separate crate,
+/// like the one used by `testing::assert_embeds`.
+const MANIFEST: &str = r#"[package]
+name = "uneval-convert-check"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+uneval = { path = "{uneval}", default-features = false, features = ["glam"] }
+glam = "0.30"
+
+[workspace]
+"#;
+
+const BODY: &str = r#"
+#[derive(Debug, PartialEq)]
+pub struct Body {
+    position: glam::Vec3,
+    scale: [f32; 3],
+    rotation: glam::Quat,
+    pair: (u8, String),
+}
+
+fn expected() -> Body {
+    Body {
+        position: glam::Vec3::new(1.0, 2.5, -3.0),
+        scale: [1.0, 1.0, 2.0],
+        rotation: glam::Quat::from_xyzw(0.0, 0.0, 0.5, 0.5),
+        pair: (7, "seven".into()),
+    }
+}
+"#;
+
+const INLINE: &str = r#"
+fn main() {
+    let body: Body = include!(concat!(env!("CARGO_MANIFEST_DIR"), "/inline.rs"));
+    assert_eq!(body, expected());
+}
+"#;
+
+const MODULE: &str = r#"
+include!(concat!(env!("CARGO_MANIFEST_DIR"), "/module.rs"));
+
+fn main() {
+    assert_eq!(*BODY, expected());
+}
+"#;
+
+#[test]
+fn third_party_types() {
+    let mut inline = Vec::new();
+    body()
+        .serialize(
+            &mut uneval::ser::Uneval::new(&mut inline)
+                .with_tuple_trait("::uneval::convert::FromTuple")
+                .with_tuple_struct_as_tuple("Vec3")
+                .with_tuple_struct_as_tuple("Quat"),
+        )
+        .unwrap();
+    let inline = String::from_utf8(inline).unwrap();
+    assert!(!inline.contains("trait FromTuple"));
+
+    let mut module = uneval::Module::new()
+        .with_tuple_trait("::uneval::convert::FromTuple")
+        .with_tuple_struct_as_tuple("Vec3")
+        .with_tuple_struct_as_tuple("Quat");
+    module.add_static("BODY", "Body", body()).unwrap();
+    let module = module.into_string().unwrap();
+    assert_eq!(module.matches("mod __uneval_tuple_3").count(), 1);
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("convert");
+    let bin = dir.join("src").join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let manifest = MANIFEST.replace("{uneval}", env!("CARGO_MANIFEST_DIR"));
+    for (path, contents) in [
+        (dir.join("Cargo.toml"), manifest),
+        (bin.join("inline.rs"), format!("{}{}", BODY, INLINE)),
+        (dir.join("inline.rs"), inline),
+        (bin.join("module.rs"), format!("{}{}", BODY, MODULE)),
+        (dir.join("module.rs"), module),
+    ] {
+        fs::write(path, contents).unwrap();
+    }
+
+    for name in ["inline", "module"] {
+        let output = Command::new(env!("CARGO"))
+            .args(["run", "--quiet", "--bin", name])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
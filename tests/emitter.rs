@@ -0,0 +1,83 @@
+#![cfg(feature = "serialize")]
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use uneval::ser::Uneval;
+use uneval::{MapEmitter, SeqEmitter};
+
+/// Writer which only counts the bytes, so that nothing is kept in memory.
+#[derive(Default)]
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn seq_matches_vec() {
+    let value = vec![(1u8, "one"), (2, "two"), (3, "three")];
+    let mut emitter = SeqEmitter::new(Vec::new()).unwrap();
+    for item in &value {
+        emitter.push(item).unwrap();
+    }
+    let out = String::from_utf8(emitter.finish().unwrap()).unwrap();
+    assert_eq!(out, uneval::to_string(&value).unwrap());
+}
+
+#[test]
+fn map_matches_map() {
+    let value: BTreeMap<_, _> = vec![(1u8, vec!["one"]), (2, vec![]), (3, vec!["three", "3"])]
+        .into_iter()
+        .collect();
+    let mut emitter = MapEmitter::new(Vec::new()).unwrap();
+    for (key, value) in &value {
+        emitter.entry(key, value).unwrap();
+    }
+    let out = String::from_utf8(emitter.finish().unwrap()).unwrap();
+    assert_eq!(out, uneval::to_string(&value).unwrap());
+}
+
+#[test]
+fn map_keeps_order() {
+    let mut emitter = MapEmitter::new(Vec::new()).unwrap();
+    emitter.entry(&2u8, &"two").unwrap();
+    emitter.entry(&1u8, &"one").unwrap();
+    let out = String::from_utf8(emitter.finish().unwrap()).unwrap();
+    assert_eq!(
+        out,
+        r#"vec![(2u8,"two".into()),(1u8,"one".into())].into_iter().collect()"#
+    );
+}
+
+#[test]
+fn configured() {
+    let value = vec![vec![1u8, 2], vec![3]];
+    let mut emitter =
+        SeqEmitter::with_serializer(Uneval::new(Vec::new()).with_indent("    ")).unwrap();
+    for item in &value {
+        emitter.push(item).unwrap();
+    }
+    let out = String::from_utf8(emitter.finish().unwrap()).unwrap();
+    assert_eq!(out, uneval::to_string_pretty(&value).unwrap());
+}
+
+#[test]
+fn million_elements() {
+    const LEN: u32 = 1_000_000;
+    let mut emitter = SeqEmitter::new(Counter::default()).unwrap();
+    for i in 0..LEN {
+        emitter.push(&i).unwrap();
+    }
+    let written = emitter.finish().unwrap().0;
+
+    let elements: usize = (0..LEN).map(|i| i.to_string().len() + "u32".len()).sum();
+    let expected = "vec![".len() + elements + (LEN as usize - 1) + "].into_iter().collect()".len();
+    assert_eq!(written, expected);
+}
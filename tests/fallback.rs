@@ -0,0 +1,172 @@
+#![cfg(any(feature = "postcard", feature = "bincode"))]
+
+use serde::Serialize;
+use std::{fs, path::PathBuf, process::Command};
+use uneval::config::FallbackCodec;
+use uneval::ser::Uneval;
+
+/// Mirror of the type which fields are private in the crate using the generated code.
+#[derive(Serialize)]
+struct Opaque {
+    id: u64,
+    name: String,
+    tags: Vec<u16>,
+}
+
+#[derive(Serialize)]
+struct Config {
+    name: String,
+    opaque: Opaque,
+    values: Vec<u32>,
+    large: Option<Opaque>,
+}
+
+fn config() -> Config {
+    Config {
+        name: "config".into(),
+        opaque: Opaque {
+            id: u64::MAX - 1,
+            name: "small \"quoted\"\n".into(),
+            tags: vec![1, 300, 65535],
+        },
+        values: vec![1, 2, 3],
+        large: Some(Opaque {
+            id: 7,
+            name: "large".repeat(20),
+            tags: (0..100).collect(),
+        }),
+    }
+}
+
+/// The generated code requires the codec crates, so it is checked in the separate crate.
+const MANIFEST: &str = r#"[package]
+name = "uneval-fallback-check"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+postcard = { version = "1.0", default-features = false, features = ["alloc"] }
+bincode = { version = "2.0", features = ["serde"] }
+
+[workspace]
+"#;
+
+const MAIN: &str = r#"
+mod third_party {
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    pub struct Opaque {
+        id: u64,
+        name: String,
+        tags: Vec<u16>,
+    }
+
+    impl Opaque {
+        pub fn new(id: u64, name: String, tags: Vec<u16>) -> Self {
+            Self { id, name, tags }
+        }
+    }
+}
+
+use third_party::Opaque;
+
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    name: String,
+    opaque: Opaque,
+    values: Vec<u32>,
+    large: Option<Opaque>,
+}
+
+fn main() {
+    let config: Config = include!(concat!(env!("CARGO_MANIFEST_DIR"), "/generated.rs"));
+    assert_eq!(
+        config,
+        Config {
+            name: "config".into(),
+            opaque: Opaque::new(u64::MAX - 1, "small \"quoted\"\n".into(), vec![1, 300, 65535]),
+            values: vec![1, 2, 3],
+            large: Some(Opaque::new(7, "large".repeat(20), (0..100).collect())),
+        }
+    );
+}
+"#;
+
+fn check(name: &str, codec: FallbackCodec, decoder: &str) {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("fallback_{}", name));
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("Cargo.toml"), MANIFEST).unwrap();
+    fs::write(dir.join("src").join("main.rs"), MAIN).unwrap();
+
+    let path = dir.join("generated.rs");
+    let uneval = Uneval::new(Vec::new())
+        .with_fallback_at("Config.opaque", codec)
+        .with_fallback_at("Config.large", codec);
+    uneval::to_file_with_sidecars_with(config(), &path, 128, uneval).unwrap();
+    let code = fs::read_to_string(&path).unwrap();
+    // Only the designated fields are blobs, and only the large one is moved into the sidecar.
+    assert!(code.starts_with("Config {name: \"config\".into(),"));
+    assert!(code.contains(&format!("opaque: {}(b\"", decoder)));
+    assert!(code.contains("values: vec![1u32,2u32,3u32].into_iter().collect()"));
+    assert!(code.contains(&format!(
+        "large: {}(include_bytes!(\"generated.rs.",
+        decoder
+    )));
+
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--quiet"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn postcard() {
+    check(
+        "postcard",
+        FallbackCodec::Postcard,
+        "::postcard::from_bytes",
+    );
+}
+
+#[cfg(feature = "bincode")]
+#[test]
+fn bincode() {
+    check(
+        "bincode",
+        FallbackCodec::Bincode,
+        "::bincode::serde::decode_from_slice",
+    );
+}
+
+#[cfg(feature = "postcard")]
+#[test]
+fn const_context() {
+    #[derive(Serialize)]
+    struct Table {
+        entries: Vec<Opaque>,
+    }
+
+    let table = Table {
+        entries: vec![config().opaque],
+    };
+    let err = table
+        .serialize(
+            &mut Uneval::new(Vec::new())
+                .with_seq_mode(uneval::config::SeqMode::Slice)
+                .with_fallback_at("Table.entries[*]", FallbackCodec::Postcard),
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, uneval::error::UnevalError::NotConst { what: "fallback blob", ref path } if path == "Table.entries[0]"),
+        "{}",
+        err
+    );
+}
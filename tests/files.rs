@@ -0,0 +1,374 @@
+#![cfg(feature = "serialize")]
+
+use std::path::PathBuf;
+
+fn target(name: &str) -> PathBuf {
+    [env!("CARGO_TARGET_TMPDIR"), name].iter().collect()
+}
+
+/// Name of the only sidecar of `data.rs` with the given extension in the directory.
+fn sidecar(dir: &std::path::Path, extension: &str) -> String {
+    let mut names = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("data.rs.") && name.ends_with(extension));
+    let name = names.next().unwrap();
+    assert_eq!(names.next(), None);
+    name
+}
+
+#[test]
+fn large_sequence_to_file() {
+    let value: Vec<u64> = (0..1_000_000).collect();
+    let path = target("large_sequence.rs");
+    uneval::to_file(&value, &path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        uneval::to_string(&value).unwrap()
+    );
+}
+
+#[test]
+fn missing_directories_are_created() {
+    let dir = target("nested");
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("gen").join("data").join("config.rs");
+    uneval::to_file(vec![1u8], &path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        uneval::to_string(vec![1u8]).unwrap()
+    );
+}
+
+#[test]
+fn report() {
+    let value = vec![vec![1u8, 2], vec![3]];
+    let path = target("report.rs");
+    let report = uneval::to_file_with_report(&value, &path).unwrap();
+    assert_eq!(
+        report.bytes,
+        std::fs::metadata(&path).unwrap().len() as usize
+    );
+    assert_eq!(report.seq_elements, 5);
+    assert_eq!(report.max_depth, 2);
+}
+
+#[test]
+fn configured_serializer() {
+    use uneval::config::{IntFormat, UnevalConfig};
+
+    let config = UnevalConfig {
+        int_format: IntFormat::Hex,
+        ..UnevalConfig::default()
+    };
+    let path = target("configured.rs");
+    uneval::to_file_with(vec![255u8, 16], &path, &config).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(code, "vec![0xFFu8,0x10u8].into_iter().collect()");
+    assert_eq!(
+        uneval::to_string_with(vec![255u8, 16], &config).unwrap(),
+        code
+    );
+    // The default configuration is the one of the default serializer.
+    assert_eq!(
+        uneval::to_string_with(vec![255u8, 16], &UnevalConfig::default()).unwrap(),
+        uneval::to_string(vec![255u8, 16]).unwrap()
+    );
+}
+
+#[test]
+fn cached_file_is_written_only_on_change() {
+    let path = target("cached.rs");
+    let _ = std::fs::remove_file(&path);
+    assert!(uneval::to_file_cached(vec![1u8, 2u8], &path).unwrap());
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    assert!(!uneval::to_file_cached(vec![1u8, 2u8], &path).unwrap());
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().modified().unwrap(),
+        modified
+    );
+    // Same length, different contents.
+    assert!(uneval::to_file_cached(vec![1u8, 3u8], &path).unwrap());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        uneval::to_string(vec![1u8, 3u8]).unwrap()
+    );
+    assert!(uneval::to_file_cached(vec![1u8], &path).unwrap());
+}
+
+#[test]
+fn cached_file_with_configured_serializer() {
+    use uneval::{config::IntFormat, ser::Uneval};
+
+    let configure = || Uneval::new(Vec::new()).with_int_format(IntFormat::Hex);
+    let path = target("cached_configured.rs");
+    let _ = std::fs::remove_file(&path);
+    assert!(uneval::to_file_cached_with(vec![255u8], &path, configure()).unwrap());
+    assert!(!uneval::to_file_cached_with(vec![255u8], &path, configure()).unwrap());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "vec![0xFFu8].into_iter().collect()"
+    );
+    // The same value with the other configuration yields the other code.
+    assert!(uneval::to_file_cached(vec![255u8], &path).unwrap());
+}
+
+#[test]
+fn failed_serialization_keeps_old_file() {
+    struct Failing;
+    impl serde::Serialize for Failing {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::{Error, SerializeSeq};
+            let mut seq = serializer.serialize_seq(None)?;
+            seq.serialize_element(&1u8)?;
+            Err(S::Error::custom("failed midway"))
+        }
+    }
+
+    let dir = target("atomic");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("atomic.rs");
+
+    assert!(uneval::to_file(Failing, &path).is_err());
+    assert!(!path.exists());
+
+    uneval::to_file(vec![1u8], &path).unwrap();
+    assert!(uneval::to_file(Failing, &path).is_err());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        uneval::to_string(vec![1u8]).unwrap()
+    );
+    // No temporary files are left behind.
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn concurrent_writes_of_same_file() {
+    let dir = target("concurrent");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("concurrent.rs");
+
+    let values: Vec<Vec<u64>> = (0..8).map(|index| vec![index; 10_000]).collect();
+    std::thread::scope(|scope| {
+        for value in &values {
+            let path = &path;
+            scope.spawn(move || uneval::to_file(value, path).unwrap());
+        }
+    });
+    // The file is written completely by one of the threads, and nothing else is left.
+    let code = std::fs::read_to_string(&path).unwrap();
+    assert!(values
+        .iter()
+        .any(|value| uneval::to_string(value).unwrap() == code));
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn sidecars() {
+    let dir = target("sidecars");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("data.rs");
+    // Left from the previous run.
+    std::fs::write(dir.join("data.rs.1.bin"), b"stale").unwrap();
+    std::fs::write(dir.join("other.rs.1.bin"), b"unrelated").unwrap();
+
+    let value = (vec![1u8; 16], "a".repeat(16), vec![2u8; 15]);
+    uneval::to_file_with_sidecars(&value, &path, 16).unwrap();
+    let code = std::fs::read_to_string(&path).unwrap();
+    let (bin, txt) = (sidecar(&dir, ".bin"), sidecar(&dir, ".txt"));
+    assert!(code.contains(&format!(
+        "include_bytes!({:?}).iter().copied().collect()",
+        bin
+    )));
+    assert!(code.contains(&format!("include_str!({:?}).into()", txt)));
+    assert!(code.contains("vec![2u8,"));
+    assert_eq!(std::fs::read(dir.join(&bin)).unwrap(), [1; 16]);
+    assert_eq!(std::fs::read_to_string(dir.join(&txt)).unwrap(), value.1);
+    assert!(!dir.join("data.rs.1.bin").exists());
+    assert!(dir.join("other.rs.1.bin").exists());
+
+    // Unchanged sidecars are not rewritten.
+    let modified = std::fs::metadata(dir.join(&bin))
+        .unwrap()
+        .modified()
+        .unwrap();
+    uneval::to_file_with_sidecars(&value, &path, 16).unwrap();
+    assert_eq!(
+        std::fs::metadata(dir.join(&bin))
+            .unwrap()
+            .modified()
+            .unwrap(),
+        modified
+    );
+}
+
+#[test]
+fn sidecars_of_hash_map() {
+    let dir = target("sidecars_hash_map");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir(&dir).unwrap();
+    let path = dir.join("data.rs");
+
+    let value: std::collections::HashMap<u8, Vec<u8>> =
+        (0..16).map(|key| (key, vec![key; 16])).collect();
+    let files = || {
+        let mut files: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                (path.clone(), std::fs::read(path).unwrap())
+            })
+            .collect();
+        files.sort();
+        files
+    };
+    let sorted = || uneval::ser::Uneval::new(Vec::new()).with_sorted_maps(true);
+    uneval::to_file_with_sidecars_with(&value, &path, 16, sorted()).unwrap();
+    let first = files();
+    assert_eq!(first.len(), 17);
+    // Another map with the same contents has different iteration order, but yields the same files.
+    let value: std::collections::HashMap<u8, Vec<u8>> = value.into_iter().collect();
+    uneval::to_file_with_sidecars_with(&value, &path, 16, sorted()).unwrap();
+    assert_eq!(files(), first);
+}
+
+#[test]
+fn regions() {
+    let path = target("regions.rs");
+    let source = [
+        "// Generated tables.\r\n",
+        "pub fn primes() -> Vec<u8> {\r\n",
+        "    // uneval:begin primes\r\n",
+        "    vec![]\r\n",
+        "    // uneval:end primes\r\n",
+        "}\r\n",
+        "\r\n",
+        "pub fn names() -> Vec<String> {\r\n",
+        "    let names =\r\n",
+        "        // uneval:begin names\r\n",
+        "        vec![\r\n",
+        "            \"stale\".into(),\r\n",
+        "        ].into_iter().collect();\r\n",
+        "        // uneval:end names\r\n",
+        "    names\r\n",
+        "}\r\n",
+    ]
+    .concat();
+    std::fs::write(&path, &source).unwrap();
+
+    assert!(uneval::to_file_region(vec![2u8, 3, 5], &path, "primes").unwrap());
+    assert!(uneval::to_file_region(vec!["first", "second"], &path, "names").unwrap());
+    let expected = source
+        .replace(
+            "    vec![]\r\n",
+            "    vec![2u8,3u8,5u8].into_iter().collect()\r\n",
+        )
+        .replace(
+            "        vec![\r\n            \"stale\".into(),\r\n        ].into_iter().collect();\r\n",
+            "        vec![\"first\".into(),\"second\".into()].into_iter().collect();\r\n",
+        );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), expected);
+
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+    assert!(!uneval::to_file_region(vec![2u8, 3, 5], &path, "primes").unwrap());
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().modified().unwrap(),
+        modified
+    );
+
+    // Multiline code gets the line endings of the file.
+    assert!(uneval::to_file_region((1u8, 2u8), &path, "primes").unwrap());
+    let updated = std::fs::read_to_string(&path).unwrap();
+    assert!(updated.contains("convert((1u8,2u8)) }\r\n"));
+    assert!(!updated.replace("\r\n", "").contains('\n'));
+}
+
+#[test]
+fn unnamed_region_end() {
+    let path = target("unnamed_region_end.rs");
+    let source = [
+        "// uneval:begin first\n",
+        "1u8\n",
+        "// uneval:end\n",
+        "// uneval:begin second\n",
+        "2u8\n",
+        "// uneval:end\n",
+    ]
+    .concat();
+    std::fs::write(&path, &source).unwrap();
+
+    assert!(uneval::to_file_region(3u8, &path, "second").unwrap());
+    assert!(uneval::to_file_region(4u8, &path, "first").unwrap());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        source.replace("1u8", "4u8").replace("2u8", "3u8")
+    );
+}
+
+#[test]
+fn invalid_regions() {
+    let path = target("invalid_regions.rs");
+    let reason = |source: &str, name: &str| {
+        std::fs::write(&path, source).unwrap();
+        match uneval::to_file_region(1u8, &path, name).unwrap_err() {
+            uneval::error::UnevalError::InvalidRegion { reason, .. } => reason,
+            err => panic!("unexpected error: {}", err),
+        }
+    };
+    assert_eq!(
+        reason("// uneval:end a\n", "a"),
+        "the begin marker is missing"
+    );
+    assert_eq!(
+        reason("// uneval:begin a\n// uneval:end ab\n", "a"),
+        "the end marker is missing"
+    );
+    assert_eq!(
+        reason(
+            "// uneval:begin a\n// uneval:begin a\n// uneval:end a\n",
+            "a"
+        ),
+        "the marker is duplicated"
+    );
+    assert_eq!(
+        reason("// uneval:end a\n// uneval:begin a\n", "a"),
+        "the end marker precedes the begin marker"
+    );
+    // The file is left intact on error.
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "// uneval:end a\n// uneval:begin a\n"
+    );
+}
+
+#[test]
+fn rustfmt() {
+    #[derive(serde::Serialize)]
+    struct Entry {
+        name: String,
+        ids: Vec<u8>,
+    }
+    let value = Entry {
+        name: "first".into(),
+        ids: vec![1, 2],
+    };
+    let code = uneval::to_string(&value).unwrap();
+    let formatted = uneval::rustfmt(&code).unwrap();
+    // The binary might be missing, in which case the code is kept as is.
+    if formatted != code {
+        assert_eq!(
+            formatted,
+            "Entry {\n    name: \"first\".into(),\n    ids: vec![1u8, 2u8].into_iter().collect(),\n}"
+        );
+        assert!(matches!(
+            uneval::rustfmt("vec![1u8,"),
+            Err(uneval::error::UnevalError::Rustfmt { .. })
+        ));
+    }
+    let path = target("rustfmt.rs");
+    uneval::to_file_rustfmt(&value, &path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), formatted);
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "serialize")]
+
+use std::{fs, path::PathBuf, process::Command};
+
+/// `OUT_DIR` is available only to the build scripts, so the generation and inclusion are checked
+/// in the separate crate.
+const MANIFEST: &str = r#"[package]
+name = "uneval-out-dir-check"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+uneval = { path = "{uneval}", default-features = false }
+
+[build-dependencies]
+uneval = { path = "{uneval}" }
+
+[workspace]
+"#;
+
+const BUILD: &str = r#"
+fn main() {
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+
+    let path = uneval::to_out_dir(vec![(1u8, "one"), (2, "two")], "data/tables.rs").unwrap();
+    assert_eq!(path, out_dir.join("data").join("tables.rs"));
+    assert!(path.is_file());
+
+    let path =
+        uneval::to_out_dir_typed([1u32, 2, 3].to_vec(), "data/typed/numbers.rs", "Vec<u32>").unwrap();
+    assert_eq!(path, out_dir.join("data").join("typed").join("numbers.rs"));
+
+    let mut module = uneval::Module::new();
+    module.add_const("ANSWER", "u32", 42u32).unwrap();
+    let path = module.to_out_dir("data/module.rs").unwrap();
+    assert_eq!(path, out_dir.join("data").join("module.rs"));
+
+    let mut chunked = uneval::Module::new().with_header(uneval::config::Header::new());
+    chunked
+        .add_const("FIRST", "u8", 1u8)
+        .unwrap()
+        .add_static("PAIRS", "Vec<(u8, u8)>", vec![(1u8, 2u8)])
+        .unwrap()
+        .add_const("LAST", "u8", 3u8)
+        .unwrap();
+    let chunks = chunked.to_out_dir_chunked("chunked", uneval::config::ChunkSize::Elements(2)).unwrap();
+    assert_eq!(chunks, 2);
+    assert!(out_dir.join("chunked_1.rs").is_file());
+
+    let generated = out_dir.join("generated");
+    uneval::to_file(7u8, generated.join("seven.rs")).unwrap();
+    println!("cargo:rustc-env=UNEVAL_GENERATED={}", generated.display());
+}
+"#;
+
+const MAIN: &str = r#"
+uneval::include_out_dir!("data/module.rs");
+uneval::include_out_dir!("chunked.rs");
+
+fn main() {
+    let tables: Vec<(u8, String)> = uneval::include_out_dir!("data/tables.rs");
+    assert_eq!(tables, [(1, "one".to_string()), (2, "two".to_string())]);
+    // The typed output doesn't need the annotation.
+    assert_eq!(uneval::include_out_dir!("data/typed/numbers.rs").len(), 3);
+    assert_eq!(ANSWER, 42);
+    assert_eq!((FIRST, PAIRS[0], LAST), (1, (1, 2), 3));
+    assert_eq!(uneval::include_out_dir!(env = "UNEVAL_GENERATED", "seven.rs"), 7u8);
+}
+"#;
+
+#[test]
+fn nested_include() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("out_dir");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = MANIFEST.replace("{uneval}", env!("CARGO_MANIFEST_DIR"));
+    for (path, contents) in [
+        (dir.join("Cargo.toml"), manifest.as_str()),
+        (dir.join("build.rs"), BUILD),
+        (dir.join("src").join("main.rs"), MAIN),
+    ] {
+        fs::write(path, contents).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO"))
+        .args(["run", "--quiet"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,1810 @@
+#![cfg(feature = "serialize")]
+
+use serde::Serialize;
+use uneval::config::{MapMode, SeqMode, TupleMode, UnevalConfig};
+use uneval::error::{IdentContext, UnevalError};
+
+#[test]
+fn raw_identifiers() {
+    #[derive(Serialize)]
+    struct Keywords {
+        r#type: u8,
+        r#async: u8,
+        r#match: u8,
+    }
+    let out = uneval::to_string(Keywords {
+        r#type: 1,
+        r#async: 2,
+        r#match: 3,
+    })
+    .unwrap();
+    assert_eq!(out, "Keywords {r#type: 1u8,r#async: 2u8,r#match: 3u8}");
+}
+
+#[test]
+fn edition_keywords() {
+    use uneval::config::Edition;
+
+    #[derive(Serialize)]
+    struct Keywords {
+        r#gen: u8,
+        r#dyn: u8,
+        r#type: u8,
+    }
+    let code = |edition| {
+        to_string_with(
+            Keywords {
+                r#gen: 1,
+                r#dyn: 2,
+                r#type: 3,
+            },
+            |ser| ser.with_edition(edition),
+        )
+    };
+    assert_eq!(
+        code(Edition::Rust2024),
+        "Keywords {r#gen: 1u8,r#dyn: 2u8,r#type: 3u8}"
+    );
+    assert_eq!(
+        code(Edition::Rust2021),
+        "Keywords {gen: 1u8,r#dyn: 2u8,r#type: 3u8}"
+    );
+    assert_eq!(
+        code(Edition::Rust2015),
+        "Keywords {gen: 1u8,dyn: 2u8,r#type: 3u8}"
+    );
+}
+
+#[test]
+fn non_raw_keywords() {
+    #[derive(Serialize)]
+    struct Renamed {
+        #[serde(rename = "self")]
+        field: u8,
+    }
+    let err = uneval::to_string(Renamed { field: 1 }).unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::InvalidIdentifier { name, context: IdentContext::FieldName, .. } if name == "self"
+    ));
+}
+
+fn invalid_field(field: &'static str) -> Option<String> {
+    struct Renamed(&'static str);
+    impl Serialize for Renamed {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeStruct;
+            let mut s = serializer.serialize_struct("Renamed", 1)?;
+            s.serialize_field(self.0, &1u8)?;
+            s.end()
+        }
+    }
+    match uneval::to_string(Renamed(field)) {
+        Ok(_) => None,
+        Err(UnevalError::InvalidIdentifier { name, context, .. }) => {
+            assert_eq!(context, IdentContext::FieldName);
+            Some(name)
+        }
+        Err(err) => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn invalid_identifiers() {
+    assert_eq!(invalid_field("max-retries").as_deref(), Some("max-retries"));
+    assert_eq!(invalid_field("0th").as_deref(), Some("0th"));
+    assert_eq!(invalid_field("").as_deref(), Some(""));
+    assert_eq!(invalid_field("_").as_deref(), Some("_"));
+    assert_eq!(invalid_field("with space").as_deref(), Some("with space"));
+    assert_eq!(invalid_field("❤").as_deref(), Some("❤"));
+    assert_eq!(invalid_field("_0th"), None);
+    assert_eq!(invalid_field("größe"), None);
+    assert_eq!(invalid_field("имя"), None);
+    assert_eq!(invalid_field("名前"), None);
+}
+
+#[test]
+fn invalid_variant_name() {
+    #[derive(Serialize)]
+    enum Enum {
+        #[serde(rename = "kebab-case")]
+        Variant,
+    }
+    let err = uneval::to_string(Enum::Variant).unwrap_err();
+    assert!(matches!(
+        &err,
+        UnevalError::InvalidIdentifier { name, context: IdentContext::VariantName, .. } if name == "kebab-case"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "`kebab-case` is not a valid Rust identifier (used as variant name)"
+    );
+}
+
+#[test]
+fn tuple_runtime_is_self_contained() {
+    let out = uneval::to_string((1u8, [2u8, 3u8], ())).unwrap();
+    assert!(out.contains("convert(("));
+    assert!(!out.contains("uneval"));
+}
+
+fn to_string_with<T: Serialize>(
+    value: T,
+    configure: impl FnOnce(uneval::ser::Uneval<&mut Vec<u8>>) -> uneval::ser::Uneval<&mut Vec<u8>>,
+) -> String {
+    let mut out = Vec::new();
+    value
+        .serialize(&mut configure(uneval::ser::Uneval::new(&mut out)))
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[derive(Serialize)]
+struct Asset {
+    position: [f32; 3],
+    scale: [f32; 3],
+    pair: (u8, u8),
+    points: Vec<[u8; 2]>,
+}
+
+const ASSET: Asset = Asset {
+    position: [1.0, 2.0, 3.0],
+    scale: [1.0, 1.0, 1.0],
+    pair: (1, 2),
+    points: Vec::new(),
+};
+
+#[test]
+fn tuple_mode_at_path() {
+    let out = to_string_with(ASSET, |ser| {
+        ser.with_tuple_mode_at("Asset.position", TupleMode::Array)
+    });
+    assert!(out.starts_with("Asset {position: [1f32,2f32,3f32],scale: {"));
+}
+
+#[test]
+fn tuple_mode_at_wildcard_path() {
+    let value = Asset {
+        points: vec![[1, 2], [3, 4]],
+        ..ASSET
+    };
+    let out = to_string_with(value, |ser| {
+        ser.with_tuple_mode_at("*.points[*]", TupleMode::Array)
+    });
+    assert!(out.ends_with("points: vec![[1u8,2u8],[3u8,4u8]].into_iter().collect()}"));
+}
+
+#[test]
+fn tuple_mode_for_len() {
+    let out = to_string_with(ASSET, |ser| {
+        ser.with_tuple_mode_for_len(3, TupleMode::Array)
+    });
+    assert!(out.starts_with("Asset {position: [1f32,2f32,3f32],scale: [1f32,1f32,1f32],pair: {"));
+}
+
+#[test]
+fn tuple_mode_precedence() {
+    let out = to_string_with(ASSET, |ser| {
+        ser.with_tuple_mode(TupleMode::Array)
+            .with_tuple_mode_for_len(2, TupleMode::Convert)
+            .with_tuple_mode_at("Asset.position", TupleMode::Convert)
+    });
+    assert!(out.starts_with("Asset {position: {"));
+    assert!(out.contains("scale: [1f32,1f32,1f32],pair: {"));
+}
+
+#[test]
+fn seq_modes() {
+    struct Bytes;
+    impl Serialize for Bytes {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(b"ab")
+        }
+    }
+    let expected = [
+        (SeqMode::VecCollect, "vec![97u8,98u8].into_iter().collect()"),
+        (SeqMode::PlainVec, "vec![97u8,98u8]"),
+        (SeqMode::FromArray, "Vec::from([97u8,98u8])"),
+        (SeqMode::BoxedSlice, "vec![97u8,98u8].into_boxed_slice()"),
+    ];
+    for (mode, expected) in expected {
+        assert_eq!(
+            to_string_with(Bytes, |ser| ser.with_seq_mode(mode)),
+            expected
+        );
+    }
+}
+
+#[test]
+fn pretty() {
+    #[derive(Serialize)]
+    struct Pretty {
+        list: Vec<u8>,
+        empty: Vec<u8>,
+        unit: (),
+    }
+    let out = uneval::to_string_pretty(Pretty {
+        list: vec![1, 2],
+        empty: vec![],
+        unit: (),
+    })
+    .unwrap();
+    assert_eq!(
+        out,
+        "Pretty {
+    list: vec![
+        1u8,
+        2u8,
+    ].into_iter().collect(),
+    empty: Default::default(),
+    unit: (),
+}"
+    );
+}
+
+#[test]
+fn pretty_without_trailing_comma() {
+    #[derive(Serialize)]
+    struct Outer {
+        inner: Inner,
+        list: Vec<u8>,
+    }
+    #[derive(Serialize)]
+    struct Inner {
+        flag: bool,
+    }
+    let out = to_string_with(
+        Outer {
+            inner: Inner { flag: true },
+            list: vec![1],
+        },
+        |ser| {
+            ser.with_formatter(
+                uneval::format::PrettyFormatter::new("\t").with_trailing_comma(false),
+            )
+        },
+    );
+    assert_eq!(
+        out,
+        "Outer {\n\tinner: Inner {\n\t\tflag: true\n\t},\n\tlist: vec![\n\t\t1u8\n\t].into_iter().collect()\n}"
+    );
+}
+
+#[test]
+fn custom_formatter() {
+    use std::collections::BTreeMap;
+    use std::io::{self, Write};
+    use uneval::format::Formatter;
+
+    /// Keeps everything on one line, but with spaces after the separators.
+    struct Spaced;
+
+    impl Formatter for Spaced {
+        fn begin_entry(&mut self, writer: &mut dyn Write, first: bool, _: usize) -> io::Result<()> {
+            if !first {
+                write!(writer, ", ")?;
+            }
+            Ok(())
+        }
+
+        fn begin_map_value(&mut self, writer: &mut dyn Write) -> io::Result<()> {
+            write!(writer, ", ")
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Spacious {
+        list: Vec<u8>,
+        map: BTreeMap<u8, bool>,
+        tuple: (u8, u8),
+    }
+    let value = Spacious {
+        list: vec![1, 2],
+        map: [(1, true), (2, false)].into_iter().collect(),
+        tuple: (3, 4),
+    };
+    assert_eq!(
+        to_string_with(&value, |ser| ser
+            .with_formatter(Spaced)
+            .with_tuple_mode(TupleMode::Tuple)),
+        "Spacious {list: vec![1u8, 2u8].into_iter().collect(), \
+         map: vec![(1u8, true), (2u8, false)].into_iter().collect(), tuple: (3u8, 4u8)}"
+    );
+
+    // The built-in formatters match the default and pretty-printed output.
+    assert_eq!(
+        to_string_with(&value, |ser| ser
+            .with_formatter(uneval::format::CompactFormatter)),
+        uneval::to_string(&value).unwrap()
+    );
+    assert_eq!(
+        to_string_with(&value, |ser| ser
+            .with_formatter(uneval::format::PrettyFormatter::default())),
+        uneval::to_string_pretty(&value).unwrap()
+    );
+}
+
+#[test]
+fn custom_emitter() {
+    use std::collections::BTreeMap;
+    use uneval::backend::Emitter;
+    use uneval::config::{Env, StrMode};
+
+    /// Emits the constant expressions with the borrowed slices.
+    struct Borrowing;
+
+    impl Emitter for Borrowing {
+        fn seq_open(&mut self, _: SeqMode, _: Env) -> String {
+            "&[".into()
+        }
+
+        fn seq_close(&mut self, _: SeqMode) -> String {
+            "]".into()
+        }
+
+        fn map_open(&mut self, _: &MapMode, _: Env) -> String {
+            "&[".into()
+        }
+
+        fn map_close(&mut self, _: &MapMode) -> String {
+            "]".into()
+        }
+
+        fn str_open(&mut self, _: StrMode, _: Env) -> String {
+            String::new()
+        }
+
+        fn str_close(&mut self, _: StrMode) -> String {
+            String::new()
+        }
+
+        fn tuple_close(&mut self, _: TupleMode, _: usize) -> String {
+            ")".into()
+        }
+
+        fn default_value(&mut self, _: Option<&str>) -> String {
+            "&[]".into()
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Borrowed {
+        list: Vec<String>,
+        map: BTreeMap<u8, (u8,)>,
+        empty: Vec<u8>,
+        unit: (),
+    }
+    let value = Borrowed {
+        list: vec!["a".into()],
+        map: [(1, (2,))].into_iter().collect(),
+        empty: vec![],
+        unit: (),
+    };
+    assert_eq!(
+        to_string_with(&value, |ser| ser
+            .with_emitter(Borrowing)
+            .with_tuple_mode(TupleMode::Tuple)),
+        "Borrowed {list: &[\"a\"],map: &[(1u8,(2u8))],empty: &[],unit: ()}"
+    );
+
+    // The built-in emitter matches the default output.
+    assert_eq!(
+        to_string_with(&value, |ser| ser.with_emitter(uneval::backend::DefaultEmitter)),
+        uneval::to_string(&value).unwrap()
+    );
+}
+
+#[test]
+fn sorted_maps() {
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashMap;
+    fn nested(seed: &RandomState) -> HashMap<u32, HashMap<String, u32>, RandomState> {
+        (0..50)
+            .map(|i| {
+                let mut inner = HashMap::with_hasher(seed.clone());
+                inner.extend((0..10).map(|j| (format!("key{}", j), i * j)));
+                (i, inner)
+            })
+            .fold(HashMap::with_hasher(seed.clone()), |mut map, (k, v)| {
+                map.insert(k, v);
+                map
+            })
+    }
+    let first = to_string_with(nested(&RandomState::new()), |ser| {
+        ser.with_sorted_maps(true)
+    });
+    let second = to_string_with(nested(&RandomState::new()), |ser| {
+        ser.with_sorted_maps(true)
+    });
+    assert_eq!(first, second);
+    assert!(first.starts_with("vec![(0u32,vec![(\"key0\".into(),0u32),"));
+}
+
+#[test]
+fn unsorted_maps() {
+    struct Ordered;
+    impl Serialize for Ordered {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map([(2u8, ()), (1u8, ())])
+        }
+    }
+    assert_eq!(
+        uneval::to_string(Ordered).unwrap(),
+        "vec![(2u8,()),(1u8,())].into_iter().collect()"
+    );
+    assert_eq!(
+        to_string_with(Ordered, |ser| ser.with_sorted_maps(true)),
+        "vec![(1u8,()),(2u8,())].into_iter().collect()"
+    );
+    // Ordered maps are kept as is, even though their keys would be sorted differently as code.
+    let ordered = std::collections::BTreeMap::from([(2u32, ()), (10u32, ())]);
+    assert_eq!(
+        uneval::to_string(&ordered).unwrap(),
+        "vec![(2u32,()),(10u32,())].into_iter().collect()"
+    );
+}
+
+#[test]
+fn sorted_seq_at_path() {
+    #[derive(Serialize)]
+    struct Tags {
+        tags: std::collections::HashSet<&'static str>,
+        order: Vec<u8>,
+    }
+    let value = Tags {
+        tags: ["c", "a", "b"].into_iter().collect(),
+        order: vec![3, 1, 2],
+    };
+    assert_eq!(
+        to_string_with(value, |ser| ser.with_sorted_seq_at("Tags.tags")),
+        "Tags {tags: vec![\"a\".into(),\"b\".into(),\"c\".into()].into_iter().collect(),order: vec![3u8,1u8,2u8].into_iter().collect()}"
+    );
+}
+
+#[test]
+fn map_modes() {
+    let map: std::collections::BTreeMap<u8, u8> = [(1, 2)].into_iter().collect();
+    assert_eq!(
+        to_string_with(&map, |ser| ser.with_map_mode(MapMode::FromArray {
+            type_name: "BTreeMap".into()
+        })),
+        "BTreeMap::from([(1u8,2u8)])"
+    );
+}
+
+#[derive(Serialize)]
+struct Style {
+    color: u32,
+    font: &'static str,
+}
+
+#[derive(Serialize)]
+struct Node {
+    style: Style,
+    label: &'static str,
+}
+
+#[test]
+fn dedup() {
+    let nodes: Vec<Node> = (0..2)
+        .map(|_| Node {
+            style: Style {
+                color: 0xffffff,
+                font: "mono",
+            },
+            label: "node",
+        })
+        .chain(std::iter::once(Node {
+            style: Style {
+                color: 0,
+                font: "mono",
+            },
+            label: "node",
+        }))
+        .collect();
+    assert_eq!(
+        to_string_with(&nodes, |ser| ser.with_dedup(16)),
+        concat!(
+            "{let __u0 = || Node {style: Style {color: 16777215u32,font: \"mono\".into()},label: \"node\".into()};",
+            "vec![__u0(),__u0(),Node {style: Style {color: 0u32,font: \"mono\".into()},label: \"node\".into()}]",
+            ".into_iter().collect()}"
+        )
+    );
+}
+
+#[test]
+fn dedup_generic() {
+    #[derive(Serialize)]
+    struct Wrapper<T> {
+        items: T,
+        label: &'static str,
+    }
+    #[derive(Serialize)]
+    struct Pair {
+        list: Vec<Wrapper<Vec<u8>>>,
+        set: Wrapper<std::collections::BTreeSet<u8>>,
+    }
+    let wrapper = || Wrapper {
+        items: vec![1, 2],
+        label: "wrapper",
+    };
+    let pair = Pair {
+        list: vec![wrapper(), wrapper()],
+        set: Wrapper {
+            items: [1, 2].into_iter().collect(),
+            label: "wrapper",
+        },
+    };
+    // The same code at the other field might be of the other type, so it is not shared.
+    assert_eq!(
+        to_string_with(&pair, |ser| ser.with_dedup(16)),
+        concat!(
+            "{let __u0 = || Wrapper {items: vec![1u8,2u8].into_iter().collect(),label: \"wrapper\".into()};",
+            "Pair {list: vec![__u0(),__u0()].into_iter().collect(),",
+            "set: Wrapper {items: vec![1u8,2u8].into_iter().collect(),label: \"wrapper\".into()}}}"
+        )
+    );
+}
+
+#[test]
+fn dedup_reduces_size() {
+    let nodes: Vec<Node> = (0..1000)
+        .map(|i| Node {
+            style: Style {
+                color: i % 2,
+                font: "mono",
+            },
+            label: "node",
+        })
+        .collect();
+    let plain = uneval::to_string(&nodes).unwrap();
+    let deduped = to_string_with(&nodes, |ser| ser.with_dedup(16));
+    assert!(deduped.len() * 5 < plain.len());
+}
+
+#[test]
+fn dedup_is_deterministic() {
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashMap;
+    let map = |seed: RandomState| {
+        let mut map = HashMap::with_hasher(seed);
+        map.extend((0..100).map(|i| {
+            let node = Node {
+                style: Style {
+                    color: i % 3,
+                    font: "mono",
+                },
+                label: "node",
+            };
+            (i, node)
+        }));
+        map
+    };
+    assert_eq!(
+        to_string_with(map(RandomState::new()), |ser| ser
+            .with_dedup(16)
+            .with_sorted_maps(true)),
+        to_string_with(map(RandomState::new()), |ser| ser
+            .with_dedup(16)
+            .with_sorted_maps(true))
+    );
+}
+
+#[test]
+fn flatten_depth() {
+    #[derive(Serialize)]
+    struct Node {
+        value: u32,
+        next: Vec<Node>,
+    }
+    let list = (0..3).rev().fold(None, |next: Option<Node>, value| {
+        Some(Node {
+            value,
+            next: next.into_iter().collect(),
+        })
+    });
+    assert_eq!(
+        to_string_with(list.unwrap(), |ser| ser.with_flatten_depth(2)),
+        concat!(
+            "{let __u0 = Node {value: 2u32,next: Default::default()};",
+            "let __u1 = Node {value: 1u32,next: vec![__u0].into_iter().collect()};",
+            "Node {value: 0u32,next: vec![__u1].into_iter().collect()}}"
+        )
+    );
+}
+
+#[derive(Serialize)]
+struct Entry {
+    name: &'static str,
+    position: [u8; 2],
+    kind: Kind,
+    tag: Option<char>,
+}
+
+#[derive(Serialize)]
+enum Kind {
+    Plain,
+    Weighted(f32),
+}
+
+const ENTRIES: [Entry; 2] = [
+    Entry {
+        name: "first",
+        position: [1, 2],
+        kind: Kind::Plain,
+        tag: None,
+    },
+    Entry {
+        name: "second",
+        position: [3, 4],
+        kind: Kind::Weighted(0.5),
+        tag: Some('x'),
+    },
+];
+
+#[test]
+fn to_const() {
+    assert_eq!(
+        uneval::to_const(&ENTRIES, "ENTRIES", "[Entry; 2]").unwrap(),
+        concat!(
+            "pub const ENTRIES: [Entry; 2] = [",
+            "Entry {name: \"first\",position: [1u8,2u8],kind: Kind::Plain,tag: None},",
+            "Entry {name: \"second\",position: [3u8,4u8],kind: Kind::Weighted(0.5f32),tag: Some('x')}",
+            "];\n"
+        )
+    );
+}
+
+#[test]
+fn to_const_with_tuples() {
+    let mut out = Vec::new();
+    uneval::write_const(
+        ((1u8, "a"), [(2u8, "b")]),
+        "PAIRS",
+        "((u8, &str), [(u8, &str); 1])",
+        uneval::ser::Uneval::new(&mut out)
+            .with_tuple_mode(TupleMode::Tuple)
+            .with_tuple_mode_at(".1", TupleMode::Array),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "pub const PAIRS: ((u8, &str), [(u8, &str); 1]) = ((1u8,\"a\"),[(2u8,\"b\")]);\n"
+    );
+}
+
+#[test]
+fn to_const_rejects_runtime_values() {
+    #[derive(Serialize)]
+    struct Table {
+        entries: Vec<u8>,
+    }
+    let err = uneval::to_const(Table { entries: vec![] }, "TABLE", "Table").unwrap_err();
+    assert!(matches!(
+        &err,
+        UnevalError::NotConst { what: "sequence", path } if path == "Table.entries"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "sequence at `Table.entries` can't be emitted in constant context"
+    );
+}
+
+#[test]
+fn to_static() {
+    use std::collections::HashMap;
+    use uneval::config::{StaticKind, StaticOpts};
+
+    let table: HashMap<u8, &str> = [(1, "one")].into_iter().collect();
+    assert_eq!(
+        uneval::to_static(&table, StaticOpts::new("TABLE", "HashMap<u8, String>")).unwrap(),
+        concat!(
+            "pub static TABLE: std::sync::LazyLock<HashMap<u8, String>> = ",
+            "std::sync::LazyLock::new(|| vec![(1u8,\"one\".into())].into_iter().collect());\n"
+        )
+    );
+    // The same code, to check that it compiles and is initialized on first access.
+    static TABLE: std::sync::LazyLock<HashMap<u8, String>> =
+        std::sync::LazyLock::new(|| vec![(1u8, "one".into())].into_iter().collect());
+    assert_eq!(TABLE[&1], "one");
+
+    #[cfg(feature = "once_cell")]
+    {
+        let opts = StaticOpts {
+            kind: StaticKind::OnceCell,
+            visibility: "",
+            ..StaticOpts::new("TABLE", "HashMap<u8, String>")
+        };
+        assert!(uneval::to_static(&table, opts).unwrap().starts_with(
+            "static TABLE: once_cell::sync::Lazy<HashMap<u8, String>> = once_cell::sync::Lazy::new(|| "
+        ));
+    }
+
+    #[derive(Serialize)]
+    struct Entry {
+        key: &'static str,
+        value: [u8; 2],
+    }
+    let entry = Entry {
+        key: "one",
+        value: [1, 2],
+    };
+    let opts = StaticOpts {
+        kind: StaticKind::Plain,
+        ..StaticOpts::new("ENTRY", "Entry")
+    };
+    assert_eq!(
+        uneval::to_static(&entry, opts.clone()).unwrap(),
+        "pub static ENTRY: Entry = Entry {key: \"one\",value: [1u8,2u8]};\n"
+    );
+    static ENTRY: Entry = Entry {
+        key: "one",
+        value: [1u8, 2u8],
+    };
+    assert_eq!(ENTRY.key, "one");
+    let err = uneval::to_static(&table, opts).unwrap_err();
+    assert!(matches!(err, UnevalError::NotConst { what: "map", .. }));
+
+    let opts = StaticOpts {
+        accessor: Some("lookup"),
+        ..StaticOpts::new("TABLE", "HashMap<u8, String>")
+    };
+    assert_eq!(
+        uneval::to_static(&table, opts).unwrap(),
+        concat!(
+            "pub fn lookup() -> &'static HashMap<u8, String> { ",
+            "static TABLE: std::sync::LazyLock<HashMap<u8, String>> = ",
+            "std::sync::LazyLock::new(|| vec![(1u8,\"one\".into())].into_iter().collect()); &TABLE }\n"
+        )
+    );
+    // The same code, to check that it compiles.
+    pub fn lookup() -> &'static HashMap<u8, String> {
+        static TABLE: std::sync::LazyLock<HashMap<u8, String>> =
+            std::sync::LazyLock::new(|| vec![(1u8, "one".into())].into_iter().collect());
+        &TABLE
+    }
+    assert_eq!(lookup()[&1], "one");
+}
+
+#[test]
+fn min_rust_version() {
+    use std::collections::BTreeMap;
+    use uneval::config::StaticOpts;
+    use uneval::ser::Uneval;
+
+    let config = |min_rust_version| UnevalConfig {
+        map_mode: MapMode::FromArray {
+            type_name: "BTreeMap".into(),
+        },
+        seq_mode: SeqMode::FromArray,
+        min_rust_version,
+        ..UnevalConfig::default()
+    };
+    let map = BTreeMap::from([(1u8, vec![2u8])]);
+    let code = |min_rust_version| uneval::to_string_with(&map, &config(min_rust_version)).unwrap();
+    assert_eq!(code(None), "BTreeMap::from([(1u8,Vec::from([2u8]))])");
+    assert_eq!(
+        code(Some(43)),
+        "vec![(1u8,vec![2u8])].into_iter().collect()"
+    );
+    assert_eq!(code(Some(56)), "BTreeMap::from([(1u8,Vec::from([2u8]))])");
+
+    let old = UnevalConfig {
+        min_rust_version: Some(25),
+        ..UnevalConfig::default()
+    };
+    let err = uneval::to_string_with(vec![1u128], &old).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "128-bit integer at `[0]` requires Rust 1.26, but the generated code must compile on Rust 1.25"
+    );
+
+    let mut out = Vec::new();
+    let err = uneval::write_static(
+        &map,
+        StaticOpts::new("MAP", "BTreeMap<u8, Vec<u8>>"),
+        Uneval::new(&mut out).with_min_rust_version(70),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::Unavailable {
+            required: 80,
+            target: 70,
+            ..
+        }
+    ));
+    #[cfg(feature = "once_cell")]
+    {
+        let opts = StaticOpts {
+            kind: uneval::config::StaticKind::OnceCell,
+            ..StaticOpts::new("MAP", "BTreeMap<u8, Vec<u8>>")
+        };
+        uneval::write_static(&map, opts, Uneval::new(&mut out).with_min_rust_version(70)).unwrap();
+    }
+}
+
+#[test]
+fn into() {
+    #[derive(Serialize)]
+    struct Node {
+        value: i32,
+        children: Vec<Node>,
+        next: Option<Box<Node>>,
+    }
+
+    let tree = Node {
+        value: -1,
+        children: vec![Node {
+            value: 3,
+            children: vec![],
+            next: None,
+        }],
+        next: Some(Box::new(Node {
+            value: 2,
+            children: vec![],
+            next: None,
+        })),
+    };
+    let config = UnevalConfig {
+        into: true,
+        ..UnevalConfig::default()
+    };
+    let code = uneval::to_string_with(&tree, &config).unwrap();
+    assert_eq!(
+        code,
+        concat!(
+            "Node {value: (-1i32).into(),",
+            "children: vec![Node {value: 3i32.into(),children: Default::default(),next: None}.into()]",
+            ".into_iter().collect(),",
+            "next: Some(Node {value: 2i32.into(),children: Default::default(),next: None}.into())}"
+        )
+    );
+    // The same code, to check that it compiles.
+    #[allow(clippy::useless_conversion)]
+    let _: Node = Node {
+        value: (-1i32).into(),
+        children: vec![Node {
+            value: 3i32.into(),
+            children: Default::default(),
+            next: None,
+        }
+        .into()]
+        .into_iter()
+        .collect(),
+        next: Some(
+            Node {
+                value: 2i32.into(),
+                children: Default::default(),
+                next: None,
+            }
+            .into(),
+        ),
+    };
+
+    assert_eq!(
+        to_string_with(&tree, |ser| ser.with_into_at("Node.next")),
+        concat!(
+            "Node {value: -1i32,children: vec![Node {value: 3i32,children: Default::default(),next: None}]",
+            ".into_iter().collect(),",
+            "next: Some(Node {value: 2i32,children: Default::default(),next: None}.into())}"
+        )
+    );
+    assert_eq!(
+        to_string_with(vec![1u8, 2u8], |ser| ser
+            .with_seq_mode(SeqMode::PlainVec)
+            .with_into_at("[*]")),
+        "vec![1u8.into(),2u8.into()]"
+    );
+}
+
+/// Value which always fails to serialize.
+struct Failing;
+
+impl Serialize for Failing {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("broken avatar"))
+    }
+}
+
+#[test]
+fn error_path() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Root {
+        users: Vec<User>,
+    }
+    #[derive(Serialize)]
+    struct User {
+        name: &'static str,
+        profile: Option<Profile>,
+    }
+    #[derive(Serialize)]
+    struct Profile {
+        avatar: Failing,
+    }
+
+    let root = Root {
+        users: vec![
+            User {
+                name: "first",
+                profile: None,
+            },
+            User {
+                name: "second",
+                profile: Some(Profile { avatar: Failing }),
+            },
+        ],
+    };
+    let err = uneval::to_string(&root).unwrap_err();
+    assert!(matches!(
+        &err,
+        UnevalError::Custom { message, path } if message == "broken avatar" && path == "Root.users[1].profile.avatar"
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Unknown error at `Root.users[1].profile.avatar`: broken avatar"
+    );
+
+    let map: BTreeMap<_, _> = [("fine", vec![None]), ("broken", vec![None, Some(Failing)])]
+        .into_iter()
+        .collect();
+    assert!(matches!(
+        uneval::to_string(&map).unwrap_err(),
+        UnevalError::Custom { path, .. } if path == "[broken][1]"
+    ));
+}
+
+#[test]
+fn io_error_path() {
+    use std::io;
+
+    /// Writer which accepts only the limited number of bytes.
+    struct Limited(usize);
+    impl io::Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.0 {
+                return Err(io::Error::new(io::ErrorKind::WriteZero, "limit reached"));
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Table {
+        rows: Vec<u32>,
+    }
+    let table = Table {
+        rows: (0..100).collect(),
+    };
+    // `Table {rows: vec![` takes 18 bytes, and every element up to 10 takes 5 bytes with comma.
+    let err = uneval::write(&table, Limited(18 + 5 * 3)).unwrap_err();
+    assert!(matches!(
+        &err,
+        UnevalError::Io { path, .. } if path == "Table.rows[3]"
+    ));
+}
+
+#[test]
+fn fill_defaults() {
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize, Default)]
+    struct Settings {
+        name: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout: Option<u32>,
+        hidden: Hidden,
+    }
+    #[derive(Serialize, Default)]
+    struct Hidden {
+        #[serde(skip)]
+        _cache: Vec<u8>,
+    }
+
+    let to_string = |uneval: fn(&mut Vec<u8>) -> Uneval<&mut Vec<u8>>| {
+        let mut out = Vec::new();
+        Settings::default()
+            .serialize(&mut uneval(&mut out))
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        to_string(|out| Uneval::new(out).with_fill_defaults(true)),
+        r#"Settings {name: "".into(),hidden: Hidden {..Default::default()},..Default::default()}"#
+    );
+    assert_eq!(
+        to_string(|out| Uneval::new(out).with_fill_defaults_for("Hidden")),
+        r#"Settings {name: "".into(),hidden: Hidden {..Default::default()}}"#
+    );
+    assert_eq!(
+        to_string(|out| Uneval::new(out).with_fill_defaults(true).with_indent("  ")),
+        concat!(
+            "Settings {\n",
+            "  name: \"\".into(),\n",
+            "  hidden: Hidden {\n",
+            "    ..Default::default()\n",
+            "  },\n",
+            "  ..Default::default()\n",
+            "}"
+        )
+    );
+}
+
+#[test]
+fn str_modes() {
+    use std::borrow::Cow;
+    use uneval::config::StrMode;
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    struct Texts {
+        owned: String,
+        borrowed: &'static str,
+        cow: Cow<'static, str>,
+        generic: String,
+    }
+    let texts = Texts {
+        owned: "a".into(),
+        borrowed: "b",
+        cow: Cow::Borrowed("c"),
+        generic: "d".into(),
+    };
+    let mut out = Vec::new();
+    texts
+        .serialize(
+            &mut Uneval::new(&mut out)
+                .with_str_mode_at("Texts.borrowed", StrMode::Borrowed)
+                .with_str_mode_at("Texts.cow", StrMode::CowBorrowed)
+                .with_str_mode_at("Texts.generic", StrMode::StringFrom),
+        )
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"Texts {owned: "a".into(),borrowed: "b",cow: std::borrow::Cow::Borrowed("c"),generic: String::from("d")}"#
+    );
+}
+
+#[test]
+fn parsed_strings_are_runtime_only() {
+    use std::net::IpAddr;
+    use uneval::config::StrMode;
+    use uneval::ser::Uneval;
+
+    let addr: IpAddr = "10.0.0.1".parse().unwrap();
+    let mut out = Vec::new();
+    let err = addr
+        .serialize(
+            &mut Uneval::new(&mut out)
+                .with_str_mode(StrMode::Parse)
+                .with_const_context(),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::NotConst {
+            what: "parsed string",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn raw_strings() {
+    assert_eq!(
+        uneval::to_string(r"C:\dir\file").unwrap(),
+        r#"r"C:\dir\file".into()"#
+    );
+    assert_eq!(
+        uneval::to_string(r#"say "hi" and "bye""#).unwrap(),
+        r##"r#"say "hi" and "bye""#.into()"##
+    );
+    assert_eq!(
+        uneval::to_string(r##""#1" "#2" "#3""##).unwrap(),
+        r###"r##""#1" "#2" "#3""##.into()"###
+    );
+    // Single escape doesn't make the raw literal shorter.
+    assert_eq!(uneval::to_string(r"a\b").unwrap(), r#""a\\b".into()"#);
+    // Control characters are always escaped.
+    assert_eq!(
+        uneval::to_string("\"quoted\"\n\"lines\"").unwrap(),
+        r#""\"quoted\"\n\"lines\"".into()"#
+    );
+}
+
+#[test]
+fn constructors() {
+    use std::time::Duration;
+    use uneval::config::Constructor;
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    struct Timeouts {
+        read: Duration,
+        hours: std::ops::RangeInclusive<u8>,
+    }
+    let timeouts = Timeouts {
+        read: Duration::new(1, 5),
+        hours: 1..=2,
+    };
+    assert_eq!(
+        uneval::to_const(&timeouts, "TIMEOUTS", "Timeouts").unwrap(),
+        concat!(
+            "pub const TIMEOUTS: Timeouts = Timeouts {read: core::time::Duration::new(1u64,5u32),",
+            "hours: core::ops::RangeInclusive::new(1u8,2u8)};\n"
+        )
+    );
+    let err =
+        uneval::to_const(std::time::SystemTime::UNIX_EPOCH, "EPOCH", "SystemTime").unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::NotConst {
+            what: "constructor call",
+            ..
+        }
+    ));
+
+    #[derive(Serialize)]
+    struct Seconds {
+        secs: u64,
+    }
+    #[derive(Serialize)]
+    #[serde(rename = "Duration")]
+    struct OwnDuration {
+        secs: u64,
+    }
+    let mut out = Vec::new();
+    (OwnDuration { secs: 1 }, Seconds { secs: 2 })
+        .serialize(
+            &mut Uneval::new(&mut out)
+                .with_tuple_mode(uneval::config::TupleMode::Tuple)
+                .with_struct_literal("Duration")
+                .with_constructor("Seconds", Constructor::new("Seconds::new(", ")")),
+        )
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "(Duration {secs: 1u64},Seconds::new(2u64))"
+    );
+}
+
+#[test]
+fn human_readable() {
+    use std::net::IpAddr;
+    use uneval::ser::Uneval;
+
+    let addr: IpAddr = "10.0.0.1".parse().unwrap();
+    assert_eq!(uneval::to_string(addr).unwrap(), r#""10.0.0.1".into()"#);
+    let mut out = Vec::new();
+    addr.serialize(
+        &mut Uneval::new(&mut out)
+            .with_human_readable(false)
+            .with_tuple_mode(TupleMode::Array),
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "IpAddr::V4([10u8,0u8,0u8,1u8])"
+    );
+}
+
+#[test]
+fn tagged_enums() {
+    use uneval::config::EnumTag;
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    #[serde(tag = "kind")]
+    enum Internal {
+        Circle { radius: u8 },
+        Point,
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Adjacent {
+        Move { x: i8 },
+        Write(u8),
+    }
+
+    fn to_string(
+        value: impl Serialize,
+        uneval: fn(&mut Vec<u8>) -> Uneval<&mut Vec<u8>>,
+    ) -> Result<String, UnevalError> {
+        let mut out = Vec::new();
+        value.serialize(&mut uneval(&mut out))?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    let internal: fn(&mut Vec<u8>) -> Uneval<&mut Vec<u8>> = |out| {
+        Uneval::new(out)
+            .with_tagged_enum("Internal", EnumTag::Internal { tag: "kind".into() })
+            .with_indent("  ")
+    };
+    assert_eq!(
+        to_string(
+            vec![Internal::Circle { radius: 1 }, Internal::Point],
+            internal
+        )
+        .unwrap(),
+        concat!(
+            "vec![\n",
+            "  Internal::Circle {\n",
+            "    radius: 1u8,\n",
+            "  },\n",
+            "  Internal::Point,\n",
+            "].into_iter().collect()"
+        )
+    );
+
+    let adjacent: fn(&mut Vec<u8>) -> Uneval<&mut Vec<u8>> = |out| {
+        Uneval::new(out)
+            .with_tagged_enum(
+                "Adjacent",
+                EnumTag::Adjacent {
+                    tag: "t".into(),
+                    content: "c".into(),
+                },
+            )
+            .with_indent("  ")
+    };
+    assert_eq!(
+        to_string(vec![Adjacent::Move { x: -1 }, Adjacent::Write(2)], adjacent).unwrap(),
+        concat!(
+            "vec![\n",
+            "  Adjacent::Move {\n",
+            "    x: -1i8,\n",
+            "  },\n",
+            "  Adjacent::Write(2u8),\n",
+            "].into_iter().collect()"
+        )
+    );
+
+    // Newtype variant with the struct of the same name looks exactly like the struct variant.
+    #[derive(Serialize)]
+    struct Circle {
+        radius: u32,
+    }
+    #[derive(Serialize)]
+    #[serde(tag = "t", content = "c")]
+    enum Shape {
+        Circle(Circle),
+        Pair((u8, u8)),
+    }
+    let shapes = || vec![Shape::Circle(Circle { radius: 3 }), Shape::Pair((1, 2))];
+    fn shape(out: &mut Vec<u8>) -> Uneval<&mut Vec<u8>> {
+        Uneval::new(out).with_tagged_enum(
+            "Shape",
+            EnumTag::Adjacent {
+                tag: "t".into(),
+                content: "c".into(),
+            },
+        )
+    }
+    assert_eq!(
+        to_string(shapes(), shape).unwrap(),
+        "vec![Shape::Circle {radius: 3u32},Shape::Pair(1u8,2u8)].into_iter().collect()"
+    );
+    assert_eq!(
+        to_string(shapes(), |out| shape(out)
+            .with_newtype_variant("Shape", "Circle")
+            .with_newtype_variant("Shape", "Pair")
+            .with_tuple_mode(uneval::config::TupleMode::Tuple))
+        .unwrap(),
+        "vec![Shape::Circle(Circle {radius: 3u32}),Shape::Pair((1u8,2u8))].into_iter().collect()"
+    );
+
+    // Internally tagged newtype variant is serialized as the inner struct, starting with the tag.
+    #[derive(Serialize)]
+    #[serde(tag = "kind")]
+    enum Wrapped {
+        Round(Circle),
+    }
+    assert_eq!(
+        to_string(Wrapped::Round(Circle { radius: 3 }), |out| {
+            Uneval::new(out)
+                .with_tagged_enum("Wrapped", EnumTag::Internal { tag: "kind".into() })
+                .with_indent("  ")
+        })
+        .unwrap(),
+        "Wrapped::Round(Circle {\n  radius: 3u32,\n})"
+    );
+    let err = to_string(Wrapped::Round(Circle { radius: 3 }), |out| {
+        Uneval::new(out)
+            .with_tagged_enum("Wrapped", EnumTag::Internal { tag: "kind".into() })
+            .with_tagged_enum("Internal", EnumTag::Internal { tag: "kind".into() })
+    })
+    .unwrap_err();
+    assert!(matches!(err, UnevalError::AmbiguousTag { tag: "kind", .. }));
+
+    let err = to_string(Internal::Point, |out| {
+        Uneval::new(out).with_tagged_enum("Internal", EnumTag::Internal { tag: "type".into() })
+    })
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        UnevalError::InvalidTag {
+            name: "Internal",
+            ..
+        }
+    ));
+}
+
+#[test]
+fn map_as_struct() {
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    struct Inner {
+        verbose: bool,
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        name: &'static str,
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    let mut out = Vec::new();
+    let mut uneval = Uneval::new(&mut out)
+        .with_map_as_struct("Config", &["name"])
+        .with_indent("  ")
+        .with_diagnostics();
+    vec![Config {
+        name: "main",
+        inner: Inner { verbose: false },
+    }]
+    .serialize(&mut uneval)
+    .unwrap();
+    let diagnostics = uneval.report().diagnostics;
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, uneval::Severity::Warning);
+    assert_eq!(
+        diagnostics[0].message,
+        "map entry `verbose` is not a field of `Config`, so it is dropped and taken from `Default`"
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            "vec![\n",
+            "  Config {\n",
+            "    name: \"main\".into(),\n",
+            "    ..Default::default()\n",
+            "  },\n",
+            "].into_iter().collect()"
+        )
+    );
+}
+
+#[test]
+fn numeric_literals() {
+    #[derive(Serialize)]
+    struct Numbers {
+        floats: Vec<f64>,
+        single: f32,
+        signed: i128,
+        unsigned: u64,
+        small: i8,
+    }
+    let out = uneval::to_string(Numbers {
+        floats: vec![1.5, -2.0, 0.1, f64::MAX],
+        single: 0.25,
+        signed: i128::MIN,
+        unsigned: u64::MAX,
+        small: -7,
+    })
+    .unwrap();
+    // `f64::MAX` is formatted differently by `Display` and `ryu`, but both are valid literals.
+    assert!(out.starts_with("Numbers {floats: vec![1.5f64,-2f64,0.1f64,"));
+    assert!(out.ends_with(
+        "f64].into_iter().collect(),single: 0.25f32,signed: -170141183460469231731687303715884105728i128,\
+         unsigned: 18446744073709551615u64,small: -7i8}"
+    ));
+}
+
+#[test]
+fn fmt_target() {
+    #[derive(Serialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    let mut out = String::from("const POINT: Point = ");
+    uneval::write_fmt(Point { x: 1, y: -1 }, &mut out).unwrap();
+    assert_eq!(out, "const POINT: Point = Point {x: 1i32,y: -1i32}");
+
+    let mut out = String::new();
+    vec![(1u8, 2u8)]
+        .serialize(&mut uneval::ser::Uneval::from_fmt(&mut out).with_tuple_mode(TupleMode::Tuple))
+        .unwrap();
+    assert_eq!(out, "vec![(1u8,2u8)].into_iter().collect()");
+
+    struct Failing;
+    impl std::fmt::Write for Failing {
+        fn write_str(&mut self, _: &str) -> std::fmt::Result {
+            Err(std::fmt::Error)
+        }
+    }
+    let err = uneval::write_fmt(Point { x: 1, y: 1 }, Failing).unwrap_err();
+    assert!(matches!(err, UnevalError::Fmt { .. }), "{:?}", err);
+}
+
+#[test]
+fn bytes() {
+    let value = vec![("first", 1u8)];
+    assert_eq!(
+        uneval::to_vec(&value).unwrap(),
+        uneval::to_string(&value).unwrap().into_bytes()
+    );
+}
+
+#[test]
+fn int_formats() {
+    use uneval::config::IntFormat;
+
+    #[derive(Serialize)]
+    struct Masks {
+        byte: u8,
+        half: u16,
+        word: u32,
+        wide: u64,
+        offset: i16,
+    }
+    let masks = Masks {
+        byte: 0xA5,
+        half: 0x1,
+        word: 0xD000_0000,
+        wide: 0x1_2345_6789,
+        offset: -0x7FF,
+    };
+    let with_format = |format| {
+        let mut out = Vec::new();
+        masks
+            .serialize(&mut uneval::ser::Uneval::new(&mut out).with_int_format(format))
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        with_format(IntFormat::Decimal),
+        "Masks {byte: 165u8,half: 1u16,word: 3489660928u32,wide: 4886718345u64,offset: -2047i16}"
+    );
+    assert_eq!(
+        with_format(IntFormat::Hex),
+        "Masks {byte: 0xA5u8,half: 0x1u16,word: 0xD0000000u32,wide: 0x123456789u64,offset: -0x7FFi16}"
+    );
+    assert_eq!(
+        with_format(IntFormat::GroupedHex),
+        "Masks {byte: 0xA5u8,half: 0x1u16,word: 0xD000_0000u32,wide: 0x1_2345_6789u64,offset: -0x7FFi16}"
+    );
+    assert_eq!(
+        with_format(IntFormat::GroupedBinary),
+        "Masks {byte: 0b1010_0101u8,half: 0b1u16,word: 0b1101_0000_0000_0000_0000_0000_0000_0000u32,\
+         wide: 0b1_0010_0011_0100_0101_0110_0111_1000_1001u64,offset: -0b111_1111_1111i16}"
+    );
+}
+
+#[test]
+fn borrowed_slices() {
+    #[derive(Serialize)]
+    struct Entry {
+        name: &'static str,
+        values: &'static [u8],
+        owned: Vec<String>,
+    }
+    let entry = Entry {
+        name: "entry",
+        values: &[1, 2],
+        owned: vec!["owned".into()],
+    };
+    let with_slice_at = |path| {
+        let mut out = Vec::new();
+        entry
+            .serialize(
+                &mut uneval::ser::Uneval::new(&mut out)
+                    .with_str_mode_at("Entry.name", uneval::config::StrMode::Borrowed)
+                    .with_seq_mode_at(path, SeqMode::Slice),
+            )
+            .map(|_| String::from_utf8(out).unwrap())
+    };
+    assert_eq!(
+        with_slice_at("Entry.values").unwrap(),
+        "Entry {name: \"entry\",values: &[1u8,2u8],owned: vec![\"owned\".into()].into_iter().collect()}"
+    );
+    // Elements of slices are emitted in constant context, so the strings are always borrowed.
+    assert_eq!(
+        with_slice_at("Entry.owned").unwrap(),
+        "Entry {name: \"entry\",values: vec![1u8,2u8].into_iter().collect(),owned: &[\"owned\"]}"
+    );
+    let nested = vec![vec![1u8]];
+    let mut out = Vec::new();
+    let err = nested
+        .serialize(
+            &mut uneval::ser::Uneval::new(&mut out)
+                .with_seq_mode_at("[*]", SeqMode::VecCollect)
+                .with_seq_mode(SeqMode::Slice),
+        )
+        .unwrap_err();
+    assert!(matches!(err, UnevalError::NotConst { .. }), "{}", err);
+}
+
+#[test]
+fn cow_slices() {
+    use std::borrow::Cow;
+
+    #[derive(Serialize)]
+    struct Palette {
+        colors: Cow<'static, [(u8, u8, u8)]>,
+    }
+    let palette = Palette {
+        colors: Cow::Borrowed(&[(255, 0, 0)]),
+    };
+    let mut out = Vec::new();
+    palette
+        .serialize(
+            &mut uneval::ser::Uneval::new(&mut out)
+                .with_seq_mode_at("Palette.colors", SeqMode::CowSlice)
+                .with_tuple_mode_at("Palette.colors[*]", uneval::config::TupleMode::Tuple),
+        )
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "Palette {colors: std::borrow::Cow::Borrowed(&[(255u8,0u8,0u8)])}"
+    );
+    const _: Palette = Palette {
+        colors: std::borrow::Cow::Borrowed(&[(255u8, 0u8, 0u8)]),
+    };
+}
+
+#[test]
+fn report() {
+    #[derive(Serialize)]
+    enum Shape {
+        Empty,
+        Circle(f32),
+        Rect { size: (u8, u8) },
+    }
+    struct Blob(Vec<u8>);
+    impl Serialize for Blob {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+    #[derive(Serialize)]
+    struct Scene {
+        name: String,
+        shapes: Vec<Shape>,
+        tags: std::collections::BTreeMap<String, Vec<u16>>,
+        blob: Blob,
+    }
+    let scene = Scene {
+        name: "scene".into(),
+        shapes: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Rect { size: (2, 3) },
+        ],
+        tags: [
+            ("first".to_string(), vec![1, 2]),
+            ("second".to_string(), vec![]),
+        ]
+        .into_iter()
+        .collect(),
+        blob: Blob(vec![0; 10]),
+    };
+    let mut out = Vec::new();
+    let report = uneval::write_with_report(&scene, &mut out).unwrap();
+    assert_eq!(report.bytes, out.len());
+    assert_eq!(report.structs, 1);
+    assert_eq!(report.variants, 3);
+    // Three shapes, two tags and ten bytes of the blob, which is emitted as the sequence at runtime.
+    assert_eq!(report.seq_elements, 15);
+    assert_eq!(report.map_entries, 2);
+    assert_eq!(report.strings, 3);
+    // `1.5`, the size, the tags and the blob.
+    assert_eq!(report.numbers, 15);
+    // Scene -> shapes -> Rect -> size.
+    assert_eq!(report.max_depth, 4);
+    assert_eq!(report.largest_payload, Some((10, "Scene.blob".to_string())));
+    // "scene", "first", "second" and the blob.
+    assert_eq!(report.payload_bytes, 26);
+    assert_eq!(report.tuple_arities.into_iter().collect::<Vec<_>>(), [2]);
+}
+
+#[test]
+fn progress() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let hook_calls = calls.clone();
+    let mut out = Vec::new();
+    let value: Vec<std::collections::BTreeMap<u8, u8>> =
+        vec![[(1, 1), (2, 2)].into_iter().collect(); 3];
+    value
+        .serialize(
+            &mut uneval::ser::Uneval::new(&mut out).with_progress(4, move |report| {
+                hook_calls
+                    .borrow_mut()
+                    .push((report.seq_elements, report.map_entries))
+            }),
+        )
+        .unwrap();
+    // Every sequence element is counted before the entries of its map.
+    assert_eq!(*calls.borrow(), [(2, 2), (3, 5)]);
+}
+
+#[test]
+fn tuple_trait() {
+    #[derive(Serialize)]
+    struct Color(u8, u8, u8);
+
+    #[derive(Serialize)]
+    struct Pixel {
+        color: Color,
+        at: (u8, u8),
+    }
+
+    let code = to_string_with(
+        Pixel {
+            color: Color(1, 2, 3),
+            at: (4, 5),
+        },
+        |ser| {
+            ser.with_tuple_trait("::my_crate::FromTuple")
+                .with_tuple_struct_as_tuple("Color")
+                .with_tuple_mode_at("Pixel.at", TupleMode::Array)
+        },
+    );
+    assert!(code.starts_with("Pixel {color: {"));
+    assert!(code.contains("Out: ::my_crate::FromTuple<(T0,T1,T2,)>"));
+    assert!(!code.contains("trait FromTuple"));
+    assert!(code.ends_with("convert((1u8,2u8,3u8)) },at: [4u8,5u8]}"));
+}
+
+#[test]
+fn collect_hints() {
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Index<C> {
+        entries: C,
+    }
+
+    struct Bytes(Vec<u8>);
+
+    impl Serialize for Bytes {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Catalog {
+        indexes: Vec<Index<BTreeMap<String, u32>>>,
+        bytes: Index<Bytes>,
+    }
+
+    let code = to_string_with(
+        Catalog {
+            indexes: vec![Index {
+                entries: [("a".to_string(), 1)].into_iter().collect(),
+            }],
+            bytes: Index {
+                entries: Bytes(vec![1, 2]),
+            },
+        },
+        |ser| {
+            ser.with_collect_hint_at("Catalog.indexes[*].entries", "BTreeMap<String, u32>")
+                .with_collect_hint_at("Catalog.bytes.entries", "Vec<u8>")
+        },
+    );
+    assert_eq!(
+        code,
+        "Catalog {indexes: vec![Index {entries: vec![(\"a\".into(),1u32)].into_iter().collect::<BTreeMap<String, u32>>()}]\
+         .into_iter().collect(),bytes: Index {entries: vec![1u8,2u8].into_iter().collect::<Vec<u8>>()}}"
+    );
+}
+
+#[test]
+fn empty_as_default() {
+    use std::collections::BTreeMap;
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    struct Empties {
+        list: Vec<u8>,
+        map: BTreeMap<u8, u8>,
+        maybe: Option<Vec<u8>>,
+        hinted: Vec<u8>,
+        slice: Vec<u8>,
+    }
+
+    let empties = || Empties {
+        list: vec![],
+        map: BTreeMap::new(),
+        maybe: Some(vec![]),
+        hinted: vec![],
+        slice: vec![],
+    };
+    fn configure(ser: Uneval<&mut Vec<u8>>) -> Uneval<&mut Vec<u8>> {
+        ser.with_collect_hint_at("Empties.hinted", "Vec<u8>")
+            .with_seq_mode_at("Empties.slice", SeqMode::Slice)
+    }
+    assert_eq!(
+        to_string_with(empties(), configure),
+        "Empties {list: Default::default(),map: Default::default(),maybe: Some(Default::default()),\
+         hinted: <Vec<u8>>::default(),slice: &[]}"
+    );
+    assert_eq!(
+        to_string_with(empties(), |ser| configure(ser).with_empty_as_default(false)),
+        "Empties {list: vec![].into_iter().collect(),map: vec![].into_iter().collect(),\
+         maybe: Some(vec![].into_iter().collect()),hinted: vec![].into_iter().collect::<Vec<u8>>(),slice: &[]}"
+    );
+}
+
+#[test]
+fn empty_nested_commas() {
+    #[derive(Serialize)]
+    struct Empty();
+
+    #[derive(Serialize)]
+    struct S {
+        a: Vec<u32>,
+        e: Empty,
+        m: std::collections::BTreeMap<u8, u8>,
+        b: u8,
+        seqs: Vec<Vec<u8>>,
+    }
+
+    let value = S {
+        a: vec![],
+        e: Empty(),
+        m: Default::default(),
+        b: 0,
+        seqs: vec![vec![], vec![]],
+    };
+    assert_eq!(
+        to_string_with(&value, |ser| ser.with_empty_as_default(false)),
+        "S {a: vec![].into_iter().collect(),e: Empty(),m: vec![].into_iter().collect(),b: 0u8,\
+         seqs: vec![vec![].into_iter().collect(),vec![].into_iter().collect()].into_iter().collect()}"
+    );
+}
+
+#[test]
+fn sequential_values() {
+    use uneval::ser::Uneval;
+
+    #[derive(Serialize)]
+    struct Point {
+        x: u8,
+        tags: Vec<u8>,
+    }
+
+    let mut uneval = Uneval::new(Vec::new()).with_indent("  ");
+    for (index, point) in [
+        Point { x: 1, tags: vec![] },
+        Point {
+            x: 2,
+            tags: vec![3],
+        },
+    ]
+    .iter()
+    .enumerate()
+    {
+        uneval
+            .write_raw(&format!("let p{}: Point = ", index))
+            .unwrap();
+        point.serialize(&mut uneval).unwrap();
+        uneval.write_raw(";\n").unwrap();
+    }
+    assert_eq!(
+        String::from_utf8(uneval.into_writer()).unwrap(),
+        "let p0: Point = Point {\n  x: 1u8,\n  tags: Default::default(),\n};\n\
+         let p1: Point = Point {\n  x: 2u8,\n  tags: vec![\n    3u8,\n  ].into_iter().collect(),\n};\n"
+    );
+}
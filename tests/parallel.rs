@@ -0,0 +1,96 @@
+#![cfg(feature = "rayon")]
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+enum Kind {
+    Plain,
+    Tagged(String),
+    Pair { left: u16, right: (u8, char) },
+}
+
+#[derive(Serialize)]
+struct Record {
+    id: u64,
+    name: String,
+    score: f64,
+    kind: Kind,
+    tags: Vec<&'static str>,
+    extra: BTreeMap<u8, Option<i32>>,
+}
+
+fn records(len: usize) -> Vec<Record> {
+    (0..len)
+        .map(|i| Record {
+            id: i as u64,
+            name: format!("record \"{}\"", i),
+            score: i as f64 / 7.0,
+            kind: match i % 3 {
+                0 => Kind::Plain,
+                1 => Kind::Tagged(i.to_string()),
+                _ => Kind::Pair {
+                    left: i as u16,
+                    right: ((i % 256) as u8, 'x'),
+                },
+            },
+            tags: ["a", "b", "c"][..i % 4].to_vec(),
+            extra: (0..(i % 3) as u8)
+                .map(|k| (k, (k % 2 == 0).then_some(-(i as i32))))
+                .collect(),
+        })
+        .collect()
+}
+
+#[test]
+fn same_as_sequential() {
+    let values = records(50_000);
+    let sequential = uneval::to_string(&values).unwrap();
+    assert_eq!(uneval::to_string_par(&values, 1000).unwrap(), sequential);
+    // Every chunk is as short as possible.
+    let few = records(3);
+    assert_eq!(
+        uneval::to_string_par(&few, 1).unwrap(),
+        uneval::to_string(&few).unwrap()
+    );
+}
+
+#[test]
+fn below_threshold() {
+    let values = records(10);
+    assert_eq!(
+        uneval::to_string_par(&values, 100).unwrap(),
+        uneval::to_string(&values).unwrap()
+    );
+    let empty: Vec<Record> = Vec::new();
+    assert_eq!(
+        uneval::to_string_par(&empty, 0).unwrap(),
+        "Default::default()"
+    );
+}
+
+struct Failing(bool);
+
+impl Serialize for Failing {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 {
+            Err(serde::ser::Error::custom("failed on purpose"))
+        } else {
+            serializer.serialize_unit_struct("Failing")
+        }
+    }
+}
+
+#[test]
+fn reports_error() {
+    let values: Vec<_> = (0..1000).map(|i| Failing(i == 700)).collect();
+    let error = uneval::to_string_par(&values, 10).unwrap_err();
+    assert!(
+        matches!(&error, uneval::error::UnevalError::Custom { path, .. } if path == "[700]"),
+        "{}",
+        error
+    );
+    // The error is the same as for the sequential serialization.
+    let sequential = uneval::to_string(&values).unwrap_err();
+    assert_eq!(error.to_string(), sequential.to_string());
+}
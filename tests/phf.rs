@@ -0,0 +1,137 @@
+#![cfg(feature = "phf")]
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use uneval::config::{MapMode, SeqMode};
+use uneval::error::UnevalError;
+use uneval::ser::Uneval;
+
+fn to_phf(value: impl Serialize) -> Result<String, UnevalError> {
+    to_phf_with(value, SeqMode::default())
+}
+
+fn to_phf_with(value: impl Serialize, seq_mode: SeqMode) -> Result<String, UnevalError> {
+    let mut out = Vec::new();
+    value.serialize(
+        &mut Uneval::new(&mut out)
+            .with_map_mode(MapMode::Phf)
+            .with_seq_mode(seq_mode),
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    ids: Vec<u8>,
+}
+
+#[test]
+fn map() {
+    let map: BTreeMap<&str, u32> = [("one", 1), ("two", 2)].into_iter().collect();
+    let out = to_phf(map).unwrap();
+    assert_eq!(out, r#"phf::phf_map! {"one" => 1u32,"two" => 2u32}"#);
+    // The same code, to check that it compiles.
+    static MAP: phf::Map<&str, u32> = phf::phf_map! {"one" => 1u32,"two" => 2u32};
+    assert_eq!(MAP.get("two"), Some(&2));
+}
+
+#[test]
+fn set() {
+    struct Bytes(&'static [u8]);
+    impl Serialize for Bytes {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+    let out = to_phf_with(vec![Bytes(b"a\"b"), Bytes(b"\xff")], SeqMode::PhfSet);
+    assert_eq!(out.unwrap(), r#"phf::phf_set! {b"a\"b",b"\xff"}"#);
+    static SET: phf::Set<&[u8]> = phf::phf_set! {b"a\"b",b"\xff"};
+    assert!(SET.contains(&b"a\"b"[..]));
+}
+
+#[test]
+fn nested() {
+    let map: BTreeMap<char, BTreeMap<i8, Option<&str>>> =
+        [('a', [(-1, Some("x"))].into_iter().collect())]
+            .into_iter()
+            .collect();
+    let out = to_phf(map).unwrap();
+    assert_eq!(
+        out,
+        r#"phf::phf_map! {'a' => phf::phf_map! {-1i8 => Some("x")}}"#
+    );
+    static MAP: phf::Map<char, phf::Map<i8, Option<&str>>> =
+        phf::phf_map! {'a' => phf::phf_map! {-1i8 => Some("x")}};
+    assert_eq!(MAP[&'a'][&-1], Some("x"));
+}
+
+#[test]
+fn non_literal_key() {
+    let map: BTreeMap<(u8, u8), u8> = [((1, 2), 3)].into_iter().collect();
+    assert!(matches!(
+        to_phf(map),
+        Err(UnevalError::NotLiteral { what: "tuple", .. })
+    ));
+}
+
+#[test]
+fn non_const_value() {
+    let map: BTreeMap<&str, Entry> = [(
+        "a",
+        Entry {
+            name: "a".into(),
+            ids: vec![1],
+        },
+    )]
+    .into_iter()
+    .collect();
+    assert!(matches!(
+        to_phf(map),
+        Err(UnevalError::NotConst { what: "sequence", path }) if path == "[a].ids"
+    ));
+}
+
+#[test]
+fn detected_from_type() {
+    use uneval::config::{StaticKind, StaticOpts};
+
+    let map: BTreeMap<&str, u8> = [("a", 1)].into_iter().collect();
+    let opts = StaticOpts {
+        kind: StaticKind::Plain,
+        ..StaticOpts::new("CODES", "phf::Map<&'static str, u8>")
+    };
+    assert_eq!(
+        uneval::to_static(&map, opts).unwrap(),
+        "pub static CODES: phf::Map<&'static str, u8> = phf::phf_map! {\"a\" => 1u8};\n"
+    );
+    assert_eq!(
+        uneval::to_const(vec!["x"], "SET", "::phf::Set<&str>").unwrap(),
+        "pub const SET: ::phf::Set<&str> = phf::phf_set! {\"x\"};\n"
+    );
+    // The explicit mode for the top-level value wins.
+    let uneval = Uneval::new(Vec::new()).with_map_mode_at("", MapMode::Collect);
+    assert!(matches!(
+        uneval::write_const(&map, "CODES", "phf::Map<&str, u8>", uneval),
+        Err(UnevalError::NotConst { what: "map", .. })
+    ));
+}
+
+#[test]
+fn detected_per_item() {
+    let map: BTreeMap<&str, u8> = [("a", 1)].into_iter().collect();
+    let mut module = uneval::Module::new();
+    module
+        .add_const("PHF", "phf::Map<&str, u8>", &map)
+        .unwrap()
+        .add_static("TREE", "BTreeMap<&str, u8>", &map)
+        .unwrap();
+    assert_eq!(
+        module.into_string().unwrap(),
+        concat!(
+            "pub const PHF: phf::Map<&str, u8> = phf::phf_map! {\"a\" => 1u8};\n",
+            "pub static TREE: std::sync::LazyLock<BTreeMap<&str, u8>> = ",
+            "std::sync::LazyLock::new(|| vec![(\"a\".into(),1u8)].into_iter().collect());\n"
+        )
+    );
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "testing")]
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub enum Shape {
+    Dot(Point),
+    Line { from: Point, to: Point },
+}
+
+const DEFINITIONS: &str = r#"
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub enum Shape {
+    Dot(Point),
+    Line { from: Point, to: Point },
+}
+"#;
+
+#[test]
+fn embeds() {
+    let shapes: BTreeMap<String, Shape> = [
+        ("dot".to_string(), Shape::Dot(Point { x: 1, y: 2 })),
+        (
+            "line".to_string(),
+            Shape::Line {
+                from: Point { x: 0, y: 0 },
+                to: Point { x: 3, y: -4 },
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+    uneval::testing::assert_embeds(&shapes, DEFINITIONS);
+}
+
+#[test]
+#[should_panic(expected = "generated code yields the value different from the original one")]
+fn reports_mismatch() {
+    // The fields are swapped on deserialization, so the generated code compiles, but the expected value differs.
+    uneval::testing::assert_embeds(
+        &Point { x: 1, y: 2 },
+        r#"#[derive(Deserialize, PartialEq)] pub struct Point {
+            #[serde(rename = "y")] x: i32,
+            #[serde(rename = "x")] y: i32,
+        }"#,
+    );
+}
+
+#[test]
+fn parallel() {
+    std::thread::scope(|scope| {
+        for x in 0..4 {
+            scope.spawn(move || uneval::testing::assert_embeds(&Point { x, y: -x }, DEFINITIONS));
+        }
+    });
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "proc-macro")]
+
+use proc_macro2::TokenStream;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use uneval::config::TupleMode;
+use uneval::ser::Uneval;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    ids: Vec<u8>,
+    pair: (i32, char),
+    kind: Kind,
+}
+
+#[derive(Serialize)]
+enum Kind {
+    Unit,
+    Tuple(f32, Option<bool>),
+    Struct { escaped: String },
+}
+
+fn normalized(code: String) -> String {
+    code.parse::<TokenStream>().unwrap().to_string()
+}
+
+#[test]
+fn matches_string_output() {
+    let value: BTreeMap<_, _> = vec![
+        (
+            "first",
+            Entry {
+                name: "one".into(),
+                ids: vec![1, 2, 3],
+                pair: (-1, 'x'),
+                kind: Kind::Unit,
+            },
+        ),
+        (
+            "second",
+            Entry {
+                name: "two".into(),
+                ids: vec![],
+                pair: (2, '\''),
+                kind: Kind::Tuple(1.5, None),
+            },
+        ),
+        (
+            "third",
+            Entry {
+                name: String::new(),
+                ids: vec![255],
+                pair: (0, '"'),
+                kind: Kind::Struct {
+                    escaped: "quote \" and \\ backslash\n".into(),
+                },
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        uneval::to_tokens(&value).unwrap().to_string(),
+        normalized(uneval::to_string(&value).unwrap())
+    );
+}
+
+#[test]
+fn configured() {
+    let value = vec![(1u8, 2u8), (3, 4)];
+    let mut out = Vec::new();
+    value
+        .serialize(&mut Uneval::new(&mut out).with_tuple_mode(TupleMode::Tuple))
+        .unwrap();
+    let tokens = uneval::to_tokens_with(
+        &value,
+        Uneval::new(Vec::new()).with_tuple_mode(TupleMode::Tuple),
+    )
+    .unwrap();
+    assert_eq!(
+        tokens.to_string(),
+        normalized(String::from_utf8(out).unwrap())
+    );
+}
+
+#[test]
+fn interpolated_into_quote() {
+    let value = Entry {
+        name: "one".into(),
+        ids: vec![1],
+        pair: (0, 'a'),
+        kind: Kind::Unit,
+    };
+    let entry = uneval::to_tokens(&value).unwrap();
+    let item = quote::quote! {
+        fn entry() -> Entry {
+            #entry
+        }
+    };
+    assert_eq!(
+        item.to_string(),
+        normalized(format!(
+            "fn entry() -> Entry {{ {} }}",
+            uneval::to_string(&value).unwrap()
+        ))
+    );
+}
@@ -0,0 +1,126 @@
+#![cfg(feature = "validate")]
+
+use serde::Serialize;
+use std::collections::HashMap;
+use uneval::config::MapMode;
+use uneval::error::UnevalError;
+use uneval::ser::Uneval;
+
+#[derive(Serialize)]
+struct Entry {
+    name: String,
+    ids: Vec<u8>,
+}
+
+#[test]
+fn valid() {
+    let value = vec![Entry {
+        name: "quote \" and \\ backslash".into(),
+        ids: vec![1, 2, 3],
+    }];
+    assert_eq!(
+        uneval::to_string_checked(&value).unwrap(),
+        uneval::to_string(&value).unwrap()
+    );
+}
+
+#[test]
+fn invalid_type_name() {
+    let value: HashMap<_, _> = vec![(1u8, 2u8)].into_iter().collect();
+    let mut out = Vec::new();
+    value
+        .serialize(
+            &mut Uneval::new(&mut out).with_map_mode(MapMode::FromArray {
+                type_name: "HashMap<u8, u8>".into(),
+            }),
+        )
+        .unwrap();
+    let code = String::from_utf8(out).unwrap();
+    match uneval::validate(&code) {
+        Err(UnevalError::InvalidSyntax { snippet, .. }) => {
+            assert!(snippet.contains("HashMap<u8, u8>"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn truncated() {
+    let code = uneval::to_string(vec![Some(1u8), None]).unwrap();
+    let truncated = &code[..code.len() - 1];
+    match uneval::validate(truncated) {
+        Err(UnevalError::InvalidSyntax { snippet, .. }) => {
+            assert!(truncated.ends_with(&snippet))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn file_not_written() {
+    let path = "test_fixtures/validate.rs";
+    let _ = std::fs::remove_file(path);
+    uneval::to_file_checked(vec![1u8, 2, 3], path).unwrap();
+    assert!(std::fs::read_to_string(path).unwrap().starts_with("vec!"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "prettyplease")]
+#[test]
+fn formatted() {
+    #[derive(Serialize)]
+    struct Config {
+        name: String,
+        ids: Vec<u8>,
+        limits: Limits,
+        parent: Option<String>,
+    }
+    #[derive(Serialize)]
+    struct Limits {
+        description: String,
+        ids: Vec<u8>,
+    }
+    let value = Config {
+        name: "child".into(),
+        ids: vec![1, 2],
+        limits: Limits {
+            description: "long enough to be split into lines, with \"quotes\"\nand line break"
+                .into(),
+            ids: vec![],
+        },
+        parent: None,
+    };
+    assert_eq!(
+        uneval::format_expr(&uneval::to_string(&value).unwrap()).unwrap(),
+        r#"Config {
+    name: "child".into(),
+    ids: vec![1u8, 2u8].into_iter().collect(),
+    limits: Limits {
+        description: "long enough to be split into lines, with \"quotes\"\nand line break"
+            .into(),
+        ids: Default::default(),
+    },
+    parent: None,
+}"#
+    );
+
+    let path = std::path::PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("formatted.rs");
+    uneval::to_file_formatted(&value, &path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        uneval::format_expr(&uneval::to_string(&value).unwrap()).unwrap()
+    );
+}
+
+#[cfg(feature = "prettyplease")]
+#[test]
+fn formatted_invalid() {
+    assert!(matches!(
+        uneval::format_expr("vec![1u8,"),
+        Err(UnevalError::InvalidSyntax { .. })
+    ));
+    assert!(matches!(
+        uneval::format_expr("1u8; 2u8"),
+        Err(UnevalError::InvalidSyntax { .. })
+    ));
+}